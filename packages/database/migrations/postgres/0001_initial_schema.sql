@@ -0,0 +1,97 @@
+-- Initial schema for the PostgreSQL backend.
+-- Record ids are stored as `table:id` text so they match the ids SurrealDB hands out.
+
+CREATE TABLE IF NOT EXISTS "user" (
+    id TEXT PRIMARY KEY,
+    username TEXT NOT NULL,
+    user_type TEXT NOT NULL,
+    email TEXT NOT NULL,
+    password TEXT NOT NULL,
+    verified BOOLEAN NOT NULL,
+    verified_token TEXT,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS user_email_idx ON "user" (email);
+CREATE INDEX IF NOT EXISTS user_verified_token_idx ON "user" (verified_token);
+
+CREATE TABLE IF NOT EXISTS location (
+    id TEXT PRIMARY KEY,
+    address TEXT,
+    latitude DOUBLE PRECISION,
+    longitude DOUBLE PRECISION,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE TABLE IF NOT EXISTS gym (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    description TEXT,
+    location_id TEXT REFERENCES location (id),
+    owner_id TEXT REFERENCES "user" (id),
+    profile_picture TEXT,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS gym_owner_id_idx ON gym (owner_id);
+
+CREATE TABLE IF NOT EXISTS gym_seeker (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    birth_date TIMESTAMPTZ,
+    sex TEXT,
+    user_id TEXT REFERENCES "user" (id),
+    profile_picture TEXT,
+    fitness_goals TEXT,
+    preferred_workout_time TEXT,
+    gym_preferences TEXT,
+    membership_status TEXT,
+    bio TEXT,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS gym_seeker_user_id_idx ON gym_seeker (user_id);
+
+CREATE TABLE IF NOT EXISTS trainer (
+    id TEXT PRIMARY KEY,
+    name TEXT,
+    sex TEXT,
+    experience BIGINT,
+    expertise TEXT,
+    gym_id TEXT REFERENCES gym (id),
+    user_id TEXT REFERENCES "user" (id),
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS trainer_user_id_idx ON trainer (user_id);
+
+CREATE TABLE IF NOT EXISTS post (
+    id TEXT PRIMARY KEY,
+    user_id TEXT REFERENCES "user" (id),
+    gym_id TEXT REFERENCES gym (id),
+    gym_seeker_id TEXT REFERENCES gym_seeker (id),
+    trainer_id TEXT REFERENCES trainer (id),
+    is_gym BOOLEAN,
+    is_gym_seeker BOOLEAN,
+    is_trainer BOOLEAN,
+    content TEXT,
+    created_at TIMESTAMPTZ,
+    updated_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS post_user_id_idx ON post (user_id);
+CREATE INDEX IF NOT EXISTS post_updated_at_idx ON post (updated_at DESC);
+
+CREATE TABLE IF NOT EXISTS post_gallery (
+    id TEXT PRIMARY KEY,
+    content_id TEXT REFERENCES post (id) ON DELETE CASCADE,
+    media_url TEXT,
+    created_at TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS post_gallery_content_id_idx ON post_gallery (content_id);
@@ -0,0 +1,6 @@
+-- Emails and usernames are unique per user, enforced by the store instead of a read-then-write check.
+-- Index names are mapped back to the field they guard in src/constraint.rs.
+
+DROP INDEX IF EXISTS user_email_idx;
+CREATE UNIQUE INDEX IF NOT EXISTS user_email_unique ON "user" (email);
+CREATE UNIQUE INDEX IF NOT EXISTS user_username_unique ON "user" (username);
@@ -0,0 +1,2 @@
+-- Deleting a post removes its gallery.
+-- Nothing to apply here: post_gallery.content_id already references post ON DELETE CASCADE since 0001.
@@ -0,0 +1,4 @@
+-- Feed and post listings page by updated_at, a post without one would end pagination early.
+
+UPDATE post SET updated_at = COALESCE(created_at, now()) WHERE updated_at IS NULL;
+ALTER TABLE post ALTER COLUMN updated_at SET NOT NULL;
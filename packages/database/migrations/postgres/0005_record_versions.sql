@@ -0,0 +1,7 @@
+-- Updatable records carry a version that every guarded merge bumps, stale writes are rejected.
+
+ALTER TABLE gym ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE gym_seeker ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE trainer ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE post ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
+ALTER TABLE location ADD COLUMN IF NOT EXISTS version BIGINT NOT NULL DEFAULT 0;
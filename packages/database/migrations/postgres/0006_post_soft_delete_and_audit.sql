@@ -0,0 +1,33 @@
+-- Posts are soft deleted so they can be restored, their gallery rows are kept with them.
+-- Every update of a post or profile is recorded in audit_log with its before and after snapshot.
+
+ALTER TABLE post ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS post_deleted_at_idx ON post (deleted_at);
+
+CREATE TABLE IF NOT EXISTS audit_log (
+    id TEXT PRIMARY KEY DEFAULT 'audit_log:' || replace(gen_random_uuid()::TEXT, '-', ''),
+    table_name TEXT NOT NULL,
+    record_id TEXT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE INDEX IF NOT EXISTS audit_log_record_id_idx ON audit_log (record_id);
+
+CREATE OR REPLACE FUNCTION audit_update() RETURNS TRIGGER AS $$
+BEGIN
+    INSERT INTO audit_log (table_name, record_id, before, after)
+    VALUES (TG_TABLE_NAME, NEW.id, to_jsonb(OLD), to_jsonb(NEW));
+    RETURN NEW;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS post_audit ON post;
+CREATE TRIGGER post_audit AFTER UPDATE ON post FOR EACH ROW EXECUTE FUNCTION audit_update();
+DROP TRIGGER IF EXISTS gym_audit ON gym;
+CREATE TRIGGER gym_audit AFTER UPDATE ON gym FOR EACH ROW EXECUTE FUNCTION audit_update();
+DROP TRIGGER IF EXISTS gym_seeker_audit ON gym_seeker;
+CREATE TRIGGER gym_seeker_audit AFTER UPDATE ON gym_seeker FOR EACH ROW EXECUTE FUNCTION audit_update();
+DROP TRIGGER IF EXISTS trainer_audit ON trainer;
+CREATE TRIGGER trainer_audit AFTER UPDATE ON trainer FOR EACH ROW EXECUTE FUNCTION audit_update();
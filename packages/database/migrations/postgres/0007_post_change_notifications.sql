@@ -0,0 +1,23 @@
+-- Every insert, update and delete of a post is announced on the live_post channel for live queries.
+-- The payload only carries the operation and record id, listeners read the row back, so it stays under the NOTIFY size limit.
+
+CREATE OR REPLACE FUNCTION notify_change() RETURNS TRIGGER AS $$
+DECLARE
+    record_id TEXT;
+BEGIN
+    IF TG_OP = 'DELETE' THEN
+        record_id := OLD.id;
+    ELSE
+        record_id := NEW.id;
+    END IF;
+
+    PERFORM pg_notify(
+        'live_' || TG_TABLE_NAME,
+        json_build_object('action', TG_OP, 'id', record_id)::TEXT
+    );
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+DROP TRIGGER IF EXISTS post_notify_change ON post;
+CREATE TRIGGER post_notify_change AFTER INSERT OR UPDATE OR DELETE ON post FOR EACH ROW EXECUTE FUNCTION notify_change();
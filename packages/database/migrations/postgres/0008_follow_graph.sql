@@ -0,0 +1,12 @@
+-- Profiles follow gyms, trainers and gym seekers, one row per follower and followee pair.
+-- The pair index is mapped back in src/constraint.rs so following twice is reported instead of duplicated.
+
+CREATE TABLE IF NOT EXISTS follow (
+    id TEXT PRIMARY KEY,
+    follower_id TEXT NOT NULL,
+    followee_id TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS follow_pair_unique ON follow (follower_id, followee_id);
+CREATE INDEX IF NOT EXISTS follow_followee_id_idx ON follow (followee_id);
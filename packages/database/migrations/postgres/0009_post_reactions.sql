@@ -0,0 +1,13 @@
+-- Users react to posts with one reaction each, reacting again replaces the kind.
+-- The pair index is mapped back in src/constraint.rs so a second reaction is reported instead of duplicated.
+
+CREATE TABLE IF NOT EXISTS reaction (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES post (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    kind TEXT NOT NULL CHECK (kind IN ('like', 'love', 'fire', 'strong', 'clap')),
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS reaction_post_user_unique ON reaction (user_id, post_id);
+CREATE INDEX IF NOT EXISTS reaction_post_id_idx ON reaction (post_id);
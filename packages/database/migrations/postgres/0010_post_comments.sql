@@ -0,0 +1,16 @@
+-- Comments on posts with one level of replies, a reply points at the top-level comment it answers.
+-- Removing a post or a top-level comment removes everything below it.
+
+CREATE TABLE IF NOT EXISTS comment (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES post (id) ON DELETE CASCADE,
+    parent_id TEXT REFERENCES comment (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS comment_post_id_idx ON comment (post_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS comment_parent_id_idx ON comment (parent_id, created_at DESC);
@@ -0,0 +1,23 @@
+-- Hashtags and @mentions parsed from post content. A post's hashtags are rewritten whenever its content
+-- changes and carry the post's creation time, which trending counts are windowed by.
+
+CREATE TABLE IF NOT EXISTS post_hashtag (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES post (id) ON DELETE CASCADE,
+    tag TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS post_hashtag_tag_idx ON post_hashtag (tag, created_at DESC);
+CREATE INDEX IF NOT EXISTS post_hashtag_post_id_idx ON post_hashtag (post_id);
+CREATE INDEX IF NOT EXISTS post_hashtag_created_at_idx ON post_hashtag (created_at);
+
+CREATE TABLE IF NOT EXISTS mention (
+    id TEXT PRIMARY KEY,
+    post_id TEXT NOT NULL REFERENCES post (id) ON DELETE CASCADE,
+    user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS mention_post_user_unique ON mention (post_id, user_id);
+CREATE INDEX IF NOT EXISTS mention_user_id_idx ON mention (user_id, created_at DESC);
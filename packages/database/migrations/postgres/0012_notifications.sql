@@ -0,0 +1,19 @@
+-- In-app notifications, one row per recipient and event. The kind tells which of the optional links are set.
+-- New rows are announced on the live_notification channel, which pushes them to connected clients.
+
+CREATE TABLE IF NOT EXISTS notification (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    actor_id TEXT NOT NULL,
+    kind TEXT NOT NULL,
+    profile_id TEXT,
+    post_id TEXT,
+    comment_id TEXT,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS notification_user_id_idx ON notification (user_id, created_at DESC);
+
+DROP TRIGGER IF EXISTS notification_notify_change ON notification;
+CREATE TRIGGER notification_notify_change AFTER INSERT ON notification FOR EACH ROW EXECUTE FUNCTION notify_change();
@@ -0,0 +1,24 @@
+-- Direct messages between two users. A conversation stores its participants in a fixed order so a pair
+-- has a single conversation, and its last message time orders the conversation list.
+
+CREATE TABLE IF NOT EXISTS conversation (
+    id TEXT PRIMARY KEY,
+    first_user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    second_user_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL,
+    last_message_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS conversation_pair_unique ON conversation (first_user_id, second_user_id);
+CREATE INDEX IF NOT EXISTS conversation_second_user_id_idx ON conversation (second_user_id);
+
+CREATE TABLE IF NOT EXISTS message (
+    id TEXT PRIMARY KEY,
+    conversation_id TEXT NOT NULL REFERENCES conversation (id) ON DELETE CASCADE,
+    sender_id TEXT NOT NULL REFERENCES "user" (id) ON DELETE CASCADE,
+    content TEXT NOT NULL,
+    read_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS message_conversation_id_idx ON message (conversation_id, created_at DESC);
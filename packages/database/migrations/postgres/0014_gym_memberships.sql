@@ -0,0 +1,35 @@
+-- Membership plans sold by a gym and the enrollment of gym seekers in them. A seeker has one membership per
+-- gym, renewed in place when they join again, and its status is derived from its dates instead of stored.
+-- The free-text membership status of gym seekers is replaced by that derived status.
+
+CREATE TABLE IF NOT EXISTS membership_plan (
+    id TEXT PRIMARY KEY,
+    gym_id TEXT NOT NULL REFERENCES gym (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    price_cents BIGINT NOT NULL CHECK (price_cents >= 0),
+    duration_days BIGINT NOT NULL CHECK (duration_days > 0),
+    benefits TEXT[] NOT NULL DEFAULT '{}',
+    archived_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS membership_plan_gym_id_idx ON membership_plan (gym_id);
+
+CREATE TABLE IF NOT EXISTS membership (
+    id TEXT PRIMARY KEY,
+    gym_id TEXT NOT NULL REFERENCES gym (id) ON DELETE CASCADE,
+    gym_seeker_id TEXT NOT NULL REFERENCES gym_seeker (id) ON DELETE CASCADE,
+    plan_id TEXT NOT NULL REFERENCES membership_plan (id) ON DELETE CASCADE,
+    start_at TIMESTAMPTZ NOT NULL,
+    end_at TIMESTAMPTZ NOT NULL,
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS membership_pair_unique ON membership (gym_seeker_id, gym_id);
+CREATE INDEX IF NOT EXISTS membership_gym_id_idx ON membership (gym_id, start_at DESC);
+
+ALTER TABLE gym_seeker DROP COLUMN IF EXISTS membership_status;
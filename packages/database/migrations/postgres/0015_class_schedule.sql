@@ -0,0 +1,32 @@
+-- Group classes a gym publishes and the seats gym seekers book in them. The seat count lives on the class
+-- and is changed in the commit that adds or removes a booking, only while it stays within the capacity.
+-- A gym seeker holds at most one seat per class.
+
+CREATE TABLE IF NOT EXISTS class_session (
+    id TEXT PRIMARY KEY,
+    gym_id TEXT NOT NULL REFERENCES gym (id) ON DELETE CASCADE,
+    trainer_id TEXT REFERENCES trainer (id) ON DELETE SET NULL,
+    title TEXT NOT NULL,
+    description TEXT NOT NULL,
+    start_at TIMESTAMPTZ NOT NULL,
+    duration_minutes BIGINT NOT NULL CHECK (duration_minutes > 0),
+    capacity BIGINT NOT NULL CHECK (capacity > 0),
+    location TEXT NOT NULL,
+    booked_count BIGINT NOT NULL DEFAULT 0 CHECK (booked_count >= 0),
+    cancelled_at TIMESTAMPTZ,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL,
+    version BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS class_session_gym_id_idx ON class_session (gym_id, start_at);
+
+CREATE TABLE IF NOT EXISTS class_booking (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL REFERENCES class_session (id) ON DELETE CASCADE,
+    gym_seeker_id TEXT NOT NULL REFERENCES gym_seeker (id) ON DELETE CASCADE,
+    created_at TIMESTAMPTZ NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS class_booking_pair_unique ON class_booking (gym_seeker_id, session_id);
+CREATE INDEX IF NOT EXISTS class_booking_session_id_idx ON class_booking (session_id);
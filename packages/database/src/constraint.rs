@@ -0,0 +1,145 @@
+use errors::Error;
+use tokio_postgres::error::SqlState;
+
+/* Unique indexes declared by the migrations, mapped to the field they guard */
+const UNIQUE_FIELDS: &[(&str, &str)] = &[
+    ("user_email_unique", "email"),
+    ("user_username_unique", "username"),
+    ("follow_pair_unique", "follow"),
+    ("reaction_post_user_unique", "reaction"),
+    ("mention_post_user_unique", "mention"),
+    ("conversation_pair_unique", "conversation"),
+    ("membership_pair_unique", "membership"),
+    ("class_booking_pair_unique", "class_booking"),
+];
+
+fn unique_field(index: &str) -> Option<&'static str> {
+    UNIQUE_FIELDS
+        .iter()
+        .find(|(name, _)| *name == index)
+        .map(|(_, field)| *field)
+}
+
+/* Text between `start` and the following `end` */
+fn between<'a>(message: &'a str, start: &str, end: &str) -> Option<&'a str> {
+    let rest = &message[message.find(start)? + start.len()..];
+    Some(&rest[..rest.find(end)?])
+}
+
+/* Turn a SurrealDB unique index violation into DataExist("field:value") */
+pub fn from_surrealdb(error: surrealdb::Error) -> Error {
+    // Database index `user_email_unique` already contains 'a@b.c', with record `user:..`
+    let message = error.to_string();
+    let violation = between(&message, "Database index `", "`")
+        .and_then(unique_field)
+        .zip(between(&message, "already contains ", ", with record"));
+
+    match violation {
+        Some((field, value)) => {
+            let value = value.trim_matches(|quote| quote == '\'' || quote == '"');
+            Error::DataExist(format!("{}:{}", field, value))
+        }
+        None => error.into(),
+    }
+}
+
+/* Turn a PostgreSQL unique violation (23505) into DataExist("field:value") */
+pub fn from_postgres(error: tokio_postgres::Error) -> Error {
+    let violation = error.as_db_error().and_then(|db_error| {
+        unique_violation(db_error.code(), db_error.constraint(), db_error.detail())
+    });
+
+    violation.unwrap_or_else(|| error.into())
+}
+
+fn unique_violation(
+    code: &SqlState,
+    constraint: Option<&str>,
+    detail: Option<&str>,
+) -> Option<Error> {
+    if code != &SqlState::UNIQUE_VIOLATION {
+        return None;
+    }
+    // Key (email)=(a@b.c) already exists.
+    let field = constraint.and_then(unique_field)?;
+    let value = between(detail?, ")=(", ") already exists")?;
+    Some(Error::DataExist(format!("{}:{}", field, value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::{
+        error::{Api, Db},
+        sql::Thing,
+    };
+
+    fn index_exists(index: &str) -> Db {
+        Db::IndexExists {
+            thing: Thing::from(("user", "x")),
+            index: index.to_string(),
+            value: String::from("'a@b.c'"),
+        }
+    }
+
+    #[test]
+    fn between_finds_the_enclosed_text() {
+        assert_eq!(
+            between(
+                "Key (email)=(a@b.c) already exists.",
+                ")=(",
+                ") already exists"
+            ),
+            Some("a@b.c")
+        );
+        assert_eq!(between("no markers here", "`", "`"), None);
+        assert_eq!(between("unterminated `index", "`", "`"), None);
+    }
+
+    #[test]
+    fn surrealdb_unique_index_maps_to_data_exist() {
+        let embedded = from_surrealdb(index_exists("user_email_unique").into());
+        assert!(matches!(embedded, Error::DataExist(field) if field == "email:a@b.c"));
+
+        // Remote engines forward the same message as text
+        let message = index_exists("user_username_unique").to_string();
+        let remote = from_surrealdb(Api::Query(message).into());
+        assert!(matches!(remote, Error::DataExist(field) if field == "username:a@b.c"));
+    }
+
+    #[test]
+    fn surrealdb_other_errors_stay_database_errors() {
+        let unknown_index = from_surrealdb(index_exists("post_user_id_idx").into());
+        assert!(matches!(unknown_index, Error::DatabaseError(_)));
+
+        let other = from_surrealdb(Db::QueryNotExecuted.into());
+        assert!(matches!(other, Error::DatabaseError(_)));
+    }
+
+    #[test]
+    fn postgres_unique_violation_maps_to_data_exist() {
+        let violation = unique_violation(
+            &SqlState::UNIQUE_VIOLATION,
+            Some("user_email_unique"),
+            Some("Key (email)=(a@b.c) already exists."),
+        );
+        assert!(matches!(violation, Some(Error::DataExist(field)) if field == "email:a@b.c"));
+    }
+
+    #[test]
+    fn postgres_other_violations_are_not_mapped() {
+        let foreign_key = unique_violation(
+            &SqlState::FOREIGN_KEY_VIOLATION,
+            Some("user_email_unique"),
+            Some("Key (email)=(a@b.c) already exists."),
+        );
+        assert!(foreign_key.is_none());
+
+        let unknown_constraint = unique_violation(
+            &SqlState::UNIQUE_VIOLATION,
+            Some("user_pkey"),
+            Some("Key (id)=(user:x) already exists."),
+        );
+        assert!(unknown_constraint.is_none());
+    }
+}
@@ -1,31 +1,66 @@
+use crate::{metrics::QueryMetrics, query::Query};
 use axum::async_trait;
+use deadpool_postgres::{Pool, Runtime};
 use environment::Environment;
 use errors::Result;
-use serde::Deserialize;
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration,
+};
 use surrealdb::{
-    engine::remote::ws::{Client, Ws},
+    engine::any::{connect, Any},
     opt::auth::Root,
-    sql::Thing,
     Surreal,
 };
 
+/// How often the supervisor checks the SurrealDB connection
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+/// How long a ping or connection attempt may take before it counts as failed
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+/// First and longest wait between reconnection attempts
+const RECONNECT_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const RECONNECT_BACKOFF_MAX: Duration = Duration::from_secs(30);
+
+/* Define the embedded storage engines SurrealDB can run on without a server */
+#[derive(Clone, Debug, PartialEq)]
+pub enum EmbeddedStorage {
+    Memory,
+    SurrealKv(String),
+    RocksDb(String),
+}
+
 /* Define database types and their associated clients */
 pub enum DatabaseType {
     SurrealDB,
-    // Add other database types here, e.g., Postgres
+    SurrealDBEmbedded(EmbeddedStorage),
+    Postgres,
 }
 
 #[derive(Clone, Debug)]
 pub enum DatabaseClient {
     Surreal(SurrealDb),
-    // Add other database clients here, e.g., Postgres(PostgresDb)
+    Postgres(PostgresDb),
 }
 
 /* Define the SurrealDb struct */
 
 #[derive(Clone, Debug)]
 pub struct SurrealDb {
-    pub client: Option<Surreal<Client>>,
+    /// Shared by every clone so a reconnect is picked up everywhere
+    pub client: Arc<RwLock<Option<Surreal<Any>>>>,
+    /// Embedded storage to open instead of connecting to DB_HOST:DB_PORT
+    pub storage: Option<EmbeddedStorage>,
+    /// Timings of every call made through this client
+    pub metrics: Arc<QueryMetrics>,
+}
+
+/* Define the PostgresDb struct */
+
+#[derive(Clone, Debug)]
+pub struct PostgresDb {
+    pub pool: Option<Pool>,
+    /// Timings of every call made through this client
+    pub metrics: Arc<QueryMetrics>,
 }
 
 /* Define the DatabaseSource struct */
@@ -33,11 +68,64 @@ pub struct DatabaseSource {
     pub db_type: DatabaseType,
 }
 
-/* Struct for deserialization of records */
-#[derive(Clone, Debug, Deserialize)]
-struct Record {
-    #[allow(dead_code)]
-    id: Thing,
+/* Implementation of EmbeddedStorage */
+impl EmbeddedStorage {
+    fn endpoint(&self) -> String {
+        match self {
+            EmbeddedStorage::Memory => String::from("mem://"),
+            EmbeddedStorage::SurrealKv(path) => format!("surrealkv://{}", path),
+            EmbeddedStorage::RocksDb(path) => format!("rocksdb://{}", path),
+        }
+    }
+
+    /* Fail at startup when the engine was not compiled in, instead of on the first connection */
+    fn ensure_compiled(&self) -> Result<()> {
+        let (compiled, feature) = match self {
+            EmbeddedStorage::Memory => (cfg!(feature = "kv-mem"), "kv-mem"),
+            EmbeddedStorage::SurrealKv(_) => (cfg!(feature = "kv-surrealkv"), "kv-surrealkv"),
+            EmbeddedStorage::RocksDb(_) => (cfg!(feature = "kv-rocksdb"), "kv-rocksdb"),
+        };
+
+        if compiled {
+            Ok(())
+        } else {
+            Err(errors::Error::DatabaseError(format!(
+                "{} storage is not compiled in, rebuild with `--features {}`",
+                self.endpoint(),
+                feature
+            )))
+        }
+    }
+}
+
+/* Implementation of DatabaseType */
+impl DatabaseType {
+    /* Select the database type from DB_ENGINE, an unset engine means the remote SurrealDB server */
+    pub fn from_environment(env: &Environment) -> Result<Self> {
+        let db_type = match env.db_engine.as_str() {
+            "none" | "remote" => DatabaseType::SurrealDB,
+            "memory" => DatabaseType::SurrealDBEmbedded(EmbeddedStorage::Memory),
+            "surrealkv" => {
+                DatabaseType::SurrealDBEmbedded(EmbeddedStorage::SurrealKv(env.db_path.clone()))
+            }
+            "rocksdb" => {
+                DatabaseType::SurrealDBEmbedded(EmbeddedStorage::RocksDb(env.db_path.clone()))
+            }
+            "postgres" => DatabaseType::Postgres,
+            engine => {
+                return Err(errors::Error::DatabaseError(format!(
+                "Unknown DB_ENGINE `{}`, expected remote, memory, surrealkv, rocksdb or postgres",
+                engine
+            )))
+            }
+        };
+
+        if let DatabaseType::SurrealDBEmbedded(storage) = &db_type {
+            storage.ensure_compiled()?;
+        }
+
+        Ok(db_type)
+    }
 }
 
 /* Trait for initializing a database connection */
@@ -49,7 +137,11 @@ pub trait Initializable {
 /* Trait for generic database connection operations */
 #[async_trait]
 pub trait Connection {
-    fn ping(&self) -> String;
+    /* Round-trip to the database server */
+    async fn ping(&self) -> Result<()>;
+
+    /* Watch the connection in the background and re-establish it when it drops */
+    fn supervise(&self);
 }
 
 /* Trait for sources to connect to a database */
@@ -58,48 +150,220 @@ pub trait Sources {
     async fn connect(&mut self) -> Result<DatabaseClient>;
 }
 
-/* Implementation of Initializable for SurrealDb */
-#[async_trait]
-impl Initializable for SurrealDb {
-    async fn init(&self) -> Result<DatabaseClient> {
+/* Implementation of SurrealDb */
+impl SurrealDb {
+    pub fn new(storage: Option<EmbeddedStorage>) -> Self {
+        SurrealDb {
+            client: Arc::new(RwLock::new(None)),
+            storage,
+            metrics: Arc::new(QueryMetrics::from_environment()),
+        }
+    }
+
+    /* Current client, fails when no connection is open */
+    pub fn client(&self) -> Result<Surreal<Any>> {
+        self.client
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| errors::Error::DatabaseError(String::from("SurrealDB is not connected")))
+    }
+
+    /* Open a new connection, signed in and scoped to the configured namespace */
+    async fn open(&self) -> Result<Surreal<Any>> {
         let env = Environment::new();
-        let hostname = format!("{}:{}", env.db_host, env.db_port);
-        let temp_client = Surreal::new::<Ws>(hostname).await?;
 
-        temp_client
-            .signin(Root {
-                username: &env.db_user,
-                password: &env.db_pass,
-            })
-            .await?;
+        let temp_client = match &self.storage {
+            Some(storage) => connect(storage.endpoint()).await?,
+            None => {
+                let remote_client =
+                    connect(format!("ws://{}:{}", env.db_host, env.db_port)).await?;
+
+                remote_client
+                    .signin(Root {
+                        username: &env.db_user,
+                        password: &env.db_pass,
+                    })
+                    .await?;
+
+                remote_client
+            }
+        };
 
         temp_client
             .use_ns(env.db_namespace)
             .use_db(env.db_name)
             .await?;
 
-        let client = Some(temp_client);
-        Ok(DatabaseClient::Surreal(SurrealDb { client }))
+        Ok(temp_client)
+    }
+
+    /* Replace the client, retrying with exponential backoff until the server is back */
+    async fn reconnect(&self) {
+        let mut backoff = RECONNECT_BACKOFF_MIN;
+
+        loop {
+            match tokio::time::timeout(CONNECT_TIMEOUT, self.open()).await {
+                Ok(Ok(client)) => {
+                    *self.client.write().unwrap() = Some(client);
+                    tracing::info!("Reconnected to SurrealDB");
+                    return;
+                }
+                Ok(Err(error)) => {
+                    tracing::warn!("Reconnecting to SurrealDB failed: {}", error);
+                }
+                Err(_) => tracing::warn!("Reconnecting to SurrealDB timed out"),
+            }
+
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(RECONNECT_BACKOFF_MAX);
+        }
+    }
+}
+
+/* Implementation of PostgresDb */
+impl PostgresDb {
+    pub fn new() -> Self {
+        PostgresDb {
+            pool: None,
+            metrics: Arc::new(QueryMetrics::from_environment()),
+        }
+    }
+
+    /* Current pool, fails when no connection is open */
+    pub fn pool(&self) -> Result<Pool> {
+        self.pool.clone().ok_or_else(|| {
+            errors::Error::DatabaseError(String::from("PostgreSQL is not connected"))
+        })
     }
 }
 
+// Implementing Default for PostgresDb
+impl Default for PostgresDb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/* Implementation of Initializable for SurrealDb */
+#[async_trait]
+impl Initializable for SurrealDb {
+    async fn init(&self) -> Result<DatabaseClient> {
+        let client = self.open().await?;
+        *self.client.write().unwrap() = Some(client);
+
+        Ok(DatabaseClient::Surreal(self.clone()))
+    }
+}
+
+/* Implementation of Initializable for PostgresDb */
+#[async_trait]
+impl Initializable for PostgresDb {
+    async fn init(&self) -> Result<DatabaseClient> {
+        let pool = config(&Environment::new())
+            .create_pool(Some(Runtime::Tokio1), tokio_postgres::NoTls)
+            .map_err(|error| errors::Error::DatabaseError(error.to_string()))?;
+
+        // Pools connect lazily, fail here instead of on the first request
+        let _ = pool.get().await?;
+
+        Ok(DatabaseClient::Postgres(PostgresDb {
+            pool: Some(pool),
+            metrics: self.metrics.clone(),
+        }))
+    }
+}
+
+/* Connection settings of the PostgreSQL server, shared by the pool and dedicated listeners */
+pub(crate) fn config(env: &Environment) -> deadpool_postgres::Config {
+    let mut config = deadpool_postgres::Config::new();
+    config.host = Some(env.db_host.clone());
+    config.port = env.db_port.parse().ok();
+    config.user = Some(env.db_user.clone());
+    config.password = Some(env.db_pass.clone());
+    config.dbname = Some(env.db_name.clone());
+    config
+}
+
 /* Implementation of Connection for SurrealDb */
+#[async_trait]
 impl Connection for SurrealDb {
-    fn ping(&self) -> String {
-        if let Some(_client) = &self.client {
-            String::from("Pong!")
-        } else {
-            String::from("Connection Failed")
+    async fn ping(&self) -> Result<()> {
+        // A dropped WebSocket parks requests until it reconnects, so bound the wait
+        match tokio::time::timeout(CONNECT_TIMEOUT, self.client()?.health()).await {
+            Ok(result) => Ok(result?),
+            Err(_) => Err(errors::Error::DatabaseError(String::from(
+                "SurrealDB ping timed out",
+            ))),
+        }
+    }
+
+    fn supervise(&self) {
+        // Embedded engines run in-process and have no connection to lose
+        if self.storage.is_some() {
+            return;
+        }
+
+        let surrealdb = self.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+
+                if let Err(error) = surrealdb.ping().await {
+                    tracing::warn!("SurrealDB health check failed: {}", error);
+                    surrealdb.reconnect().await;
+                }
+            }
+        });
+    }
+}
+
+/* Implementation of Connection for PostgresDb */
+#[async_trait]
+impl Connection for PostgresDb {
+    async fn ping(&self) -> Result<()> {
+        self.pool()?.get().await?.simple_query("SELECT 1").await?;
+        Ok(())
+    }
+
+    fn supervise(&self) {
+        // The pool already discards broken connections and opens new ones on checkout
+    }
+}
+
+/* Implementation of DatabaseClient */
+impl DatabaseClient {
+    /* Timings of every call made through this client */
+    pub fn metrics(&self) -> &QueryMetrics {
+        match self {
+            DatabaseClient::Surreal(surrealdb) => &surrealdb.metrics,
+            DatabaseClient::Postgres(postgres) => &postgres.metrics,
+        }
+    }
+
+    /* Statement a query runs as on this backend, with its values left as placeholders */
+    pub fn statement(&self, query: &Query) -> String {
+        match self {
+            DatabaseClient::Surreal(_) => query.to_surrealql().0,
+            DatabaseClient::Postgres(_) => query.to_postgres().0,
         }
     }
 }
 
 /* Implementation of Connection for DatabaseClient */
+#[async_trait]
 impl Connection for DatabaseClient {
-    fn ping(&self) -> String {
+    async fn ping(&self) -> Result<()> {
         match self {
-            DatabaseClient::Surreal(surrealdb) => surrealdb.ping(),
-            // Add other database client pings here
+            DatabaseClient::Surreal(surrealdb) => surrealdb.ping().await,
+            DatabaseClient::Postgres(postgres) => postgres.ping().await,
+        }
+    }
+
+    fn supervise(&self) {
+        match self {
+            DatabaseClient::Surreal(surrealdb) => surrealdb.supervise(),
+            DatabaseClient::Postgres(postgres) => postgres.supervise(),
         }
     }
 }
@@ -110,9 +374,17 @@ impl Sources for DatabaseSource {
     async fn connect(&mut self) -> Result<DatabaseClient> {
         match &self.db_type {
             DatabaseType::SurrealDB => {
-                let surrealdb = SurrealDb { client: None };
+                let surrealdb = SurrealDb::new(None);
+                surrealdb.init().await
+            }
+            DatabaseType::SurrealDBEmbedded(storage) => {
+                let surrealdb = SurrealDb::new(Some(storage.clone()));
                 surrealdb.init().await
-            } // Add other database types here
+            }
+            DatabaseType::Postgres => {
+                let postgres = PostgresDb::new();
+                postgres.init().await
+            }
         }
     }
 }
@@ -0,0 +1,301 @@
+use std::{fmt, str::FromStr};
+
+use errors::{Error, Result};
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{Thing, Value};
+
+use crate::query;
+
+/* Declare an id newtype whose records live in a single table */
+macro_rules! record_id {
+    ($(#[$meta:meta])* $name:ident, $table:literal) => {
+        $(#[$meta])*
+        #[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(try_from = "Thing", into = "Thing")]
+        pub struct $name(Thing);
+
+        impl $name {
+            /// Table the records of this id live in
+            pub const TABLE: &'static str = $table;
+
+            /* Wrap a record link, failing when it points into another table */
+            pub fn new(thing: Thing) -> Result<Self> {
+                if thing.tb != Self::TABLE {
+                    return Err(Error::InvalidRecordId(thing.to_string()));
+                }
+                Ok(Self(thing))
+            }
+
+            /* Record link of this id */
+            pub fn thing(&self) -> &Thing {
+                &self.0
+            }
+        }
+
+        impl TryFrom<Thing> for $name {
+            type Error = Error;
+
+            fn try_from(thing: Thing) -> Result<Self> {
+                Self::new(thing)
+            }
+        }
+
+        impl From<$name> for Thing {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl From<$name> for Value {
+            fn from(id: $name) -> Self {
+                Value::Thing(id.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = Error;
+
+            fn from_str(id: &str) -> Result<Self> {
+                Self::new(query::thing(id)?)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                self.0.fmt(formatter)
+            }
+        }
+    };
+}
+
+record_id!(
+    /// Id of a `user` record
+    UserId,
+    "user"
+);
+record_id!(
+    /// Id of a `gym` record
+    GymId,
+    "gym"
+);
+record_id!(
+    /// Id of a `gym_seeker` record
+    GymSeekerId,
+    "gym_seeker"
+);
+record_id!(
+    /// Id of a `trainer` record
+    TrainerId,
+    "trainer"
+);
+record_id!(
+    /// Id of a `post` record
+    PostId,
+    "post"
+);
+record_id!(
+    /// Id of a `location` record
+    LocationId,
+    "location"
+);
+record_id!(
+    /// Id of a `follow` record
+    FollowId,
+    "follow"
+);
+record_id!(
+    /// Id of a `reaction` record
+    ReactionId,
+    "reaction"
+);
+record_id!(
+    /// Id of a `comment` record
+    CommentId,
+    "comment"
+);
+record_id!(
+    /// Id of a `post_hashtag` record
+    PostHashtagId,
+    "post_hashtag"
+);
+record_id!(
+    /// Id of a `mention` record
+    MentionId,
+    "mention"
+);
+record_id!(
+    /// Id of a `notification` record
+    NotificationId,
+    "notification"
+);
+record_id!(
+    /// Id of a `conversation` record
+    ConversationId,
+    "conversation"
+);
+record_id!(
+    /// Id of a `message` record
+    MessageId,
+    "message"
+);
+record_id!(
+    /// Id of a `membership_plan` record
+    MembershipPlanId,
+    "membership_plan"
+);
+record_id!(
+    /// Id of a `membership` record
+    MembershipId,
+    "membership"
+);
+record_id!(
+    /// Id of a `class_session` record
+    ClassSessionId,
+    "class_session"
+);
+record_id!(
+    /// Id of a `class_booking` record
+    ClassBookingId,
+    "class_booking"
+);
+
+/* Id of the profile a user acts as, its table follows the user type */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "Thing", into = "Thing")]
+pub enum ProfileId {
+    Gym(GymId),
+    GymSeeker(GymSeekerId),
+    Trainer(TrainerId),
+}
+
+impl ProfileId {
+    /* User type owning this kind of profile */
+    pub fn user_type(&self) -> &'static str {
+        match self {
+            ProfileId::Gym(_) => GymId::TABLE,
+            ProfileId::GymSeeker(_) => GymSeekerId::TABLE,
+            ProfileId::Trainer(_) => TrainerId::TABLE,
+        }
+    }
+
+    /* Gym profile id, failing for any other user type */
+    pub fn gym(&self) -> Result<&GymId> {
+        match self {
+            ProfileId::Gym(id) => Ok(id),
+            other => Err(Error::InvalidUserType(other.to_string())),
+        }
+    }
+
+    /* Gym seeker profile id, failing for any other user type */
+    pub fn gym_seeker(&self) -> Result<&GymSeekerId> {
+        match self {
+            ProfileId::GymSeeker(id) => Ok(id),
+            other => Err(Error::InvalidUserType(other.to_string())),
+        }
+    }
+
+    /* Trainer profile id, failing for any other user type */
+    pub fn trainer(&self) -> Result<&TrainerId> {
+        match self {
+            ProfileId::Trainer(id) => Ok(id),
+            other => Err(Error::InvalidUserType(other.to_string())),
+        }
+    }
+
+    /* Record link of this id */
+    pub fn thing(&self) -> &Thing {
+        match self {
+            ProfileId::Gym(id) => id.thing(),
+            ProfileId::GymSeeker(id) => id.thing(),
+            ProfileId::Trainer(id) => id.thing(),
+        }
+    }
+}
+
+impl TryFrom<Thing> for ProfileId {
+    type Error = Error;
+
+    fn try_from(thing: Thing) -> Result<Self> {
+        match thing.tb.as_str() {
+            GymId::TABLE => GymId::new(thing).map(ProfileId::Gym),
+            GymSeekerId::TABLE => GymSeekerId::new(thing).map(ProfileId::GymSeeker),
+            TrainerId::TABLE => TrainerId::new(thing).map(ProfileId::Trainer),
+            _ => Err(Error::InvalidRecordId(thing.to_string())),
+        }
+    }
+}
+
+impl From<ProfileId> for Thing {
+    fn from(id: ProfileId) -> Self {
+        match id {
+            ProfileId::Gym(id) => id.into(),
+            ProfileId::GymSeeker(id) => id.into(),
+            ProfileId::Trainer(id) => id.into(),
+        }
+    }
+}
+
+impl FromStr for ProfileId {
+    type Err = Error;
+
+    fn from_str(id: &str) -> Result<Self> {
+        Self::try_from(query::thing(id)?)
+    }
+}
+
+impl fmt::Display for ProfileId {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        self.thing().fmt(formatter)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ids_parse_only_their_own_table() {
+        let gym: GymId = "gym:abc".parse().unwrap();
+        assert_eq!(gym.thing(), &Thing::from(("gym", "abc")));
+        assert_eq!(gym.to_string(), "gym:abc");
+
+        assert!(matches!(
+            "user:abc".parse::<GymId>(),
+            Err(Error::InvalidRecordId(_))
+        ));
+        assert!(matches!(
+            "not an id".parse::<UserId>(),
+            Err(Error::InvalidRecordId(_))
+        ));
+    }
+
+    #[test]
+    fn profile_ids_follow_the_table() {
+        let profile: ProfileId = "gym_seeker:abc".parse().unwrap();
+        assert_eq!(profile.user_type(), "gym_seeker");
+        assert!(profile.gym_seeker().is_ok());
+        assert!(matches!(profile.gym(), Err(Error::InvalidUserType(_))));
+
+        assert!(matches!(
+            "post:abc".parse::<ProfileId>(),
+            Err(Error::InvalidRecordId(_))
+        ));
+    }
+
+    #[test]
+    fn ids_round_trip_through_serde_as_record_links() {
+        let id: TrainerId = "trainer:abc".parse().unwrap();
+        let json = serde_json::to_value(&id).unwrap();
+        assert_eq!(
+            json,
+            serde_json::to_value(Thing::from(("trainer", "abc"))).unwrap()
+        );
+
+        let back: TrainerId = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(back, id);
+        assert!(serde_json::from_value::<UserId>(json).is_err());
+
+        let value = surrealdb::sql::to_value(id.clone()).unwrap();
+        assert_eq!(value, Value::Thing(id.into()));
+    }
+}
@@ -1,8 +1,17 @@
+use std::collections::HashMap;
+
 use axum::async_trait;
 use errors::Result;
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surrealdb::sql::Thing;
 
-use crate::db::DatabaseClient;
+use crate::{
+    db::DatabaseClient,
+    live::LiveStream,
+    metrics::{self, QueryCall},
+    query::{self, Query},
+    transaction::Transaction,
+};
 
 /* Trait for database interface operations */
 #[async_trait]
@@ -17,9 +26,26 @@ pub trait DBInterface {
         data: T,
     ) -> Result<Option<U>>;
 
+    /* Method to insert many records into a table in a single round-trip */
+    async fn insert_records<
+        T: Serialize + Sync + Send + 'static,
+        U: DeserializeOwned + Sync + Clone + 'static,
+    >(
+        &self,
+        tb_name: String,
+        data: Vec<T>,
+    ) -> Result<Vec<U>>;
+
     /* Method to select records from the database */
     async fn select<T: DeserializeOwned + Sync>(&self, tb_name: String) -> Result<Vec<T>>;
 
+    /* Method to select the records of a table with the given ids in a single round-trip, keyed by `table:id` */
+    async fn select_by_ids<T: DeserializeOwned + Sync + HasId>(
+        &self,
+        tb_name: String,
+        ids: Vec<Thing>,
+    ) -> Result<HashMap<String, T>>;
+
     /* Method to delete a record from the database */
     async fn delete(&self, id: String) -> Result<bool>;
 
@@ -31,16 +57,37 @@ pub trait DBInterface {
         data: T,
     ) -> Result<bool>;
 
-    /* Method to select records from the database */
-    async fn select_where<T: DeserializeOwned + Sync>(
+    /* Method to write only the fields present in `data`, leaving the rest of the record untouched */
+    async fn merge_record<T: Serialize + Sync + Send + 'static>(
+        &self,
+        id: String,
+        tb_name: String,
+        data: T,
+    ) -> Result<bool>;
+
+    /* Method to merge `data` and bump the record version, failing with Conflict when `version` is stale */
+    async fn merge_versioned<T: Serialize + Sync + Send + 'static>(
+        &self,
+        id: String,
+        tb_name: String,
+        data: T,
+        version: Option<i64>,
+    ) -> Result<bool>;
+
+    /* Method to select records matching a query from the database */
+    async fn select_where<T: DeserializeOwned + Sync>(&self, query: Query) -> Result<Vec<T>>;
+
+    /* Method to apply every write of a transaction atomically */
+    async fn commit(&self, transaction: Transaction) -> Result<()>;
+
+    /* Method to subscribe to every record created, updated or deleted in a table from now on */
+    async fn live<T: DeserializeOwned + HasId + Send + Unpin + 'static>(
         &self,
         tb_name: String,
-        filter: String,
-        columns: String, // separate columns by ',' in string format
-    ) -> Result<Vec<T>>;
+    ) -> Result<LiveStream<T>>;
 }
 
-/* Implementation of the DBInterface trait for DatabaseClient */
+/* Implementation of the DBInterface trait for DatabaseClient, every call is timed and logged */
 #[async_trait]
 impl DBInterface for DatabaseClient {
     /* Method to insert a record into the database */
@@ -52,26 +99,86 @@ impl DBInterface for DatabaseClient {
         tb_name: String,
         data: T,
     ) -> Result<Option<U>> {
-        match self {
-            DatabaseClient::Surreal(surrealdb) => surrealdb.insert_record(tb_name, data).await,
-            // Add other database client implementations here
-        }
+        let call = QueryCall::new(
+            "insert_record",
+            &tb_name,
+            format!("INSERT INTO {}", tb_name),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.insert_record(tb_name, data).await,
+                DatabaseClient::Postgres(postgres) => postgres.insert_record(tb_name, data).await,
+            }
+        })
+        .await
+    }
+
+    /* Method to insert many records into a table in a single round-trip */
+    async fn insert_records<
+        T: Serialize + Sync + Send + 'static,
+        U: DeserializeOwned + Sync + Clone + 'static,
+    >(
+        &self,
+        tb_name: String,
+        data: Vec<T>,
+    ) -> Result<Vec<U>> {
+        let call = QueryCall::new(
+            "insert_records",
+            &tb_name,
+            format!("INSERT INTO {} ({} records)", tb_name, data.len()),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.insert_records(tb_name, data).await,
+                DatabaseClient::Postgres(postgres) => postgres.insert_records(tb_name, data).await,
+            }
+        })
+        .await
     }
 
     /* Method to select records from the database */
     async fn select<T: DeserializeOwned + Sync>(&self, tb_name: String) -> Result<Vec<T>> {
-        match self {
-            DatabaseClient::Surreal(surrealdb) => surrealdb.select(tb_name).await,
-            // Add other database client implementations here
-        }
+        let call = QueryCall::new("select", &tb_name, format!("SELECT * FROM {}", tb_name));
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.select(tb_name).await,
+                DatabaseClient::Postgres(postgres) => postgres.select(tb_name).await,
+            }
+        })
+        .await
+    }
+
+    /* Method to select the records of a table with the given ids in a single round-trip */
+    async fn select_by_ids<T: DeserializeOwned + Sync + HasId>(
+        &self,
+        tb_name: String,
+        ids: Vec<Thing>,
+    ) -> Result<HashMap<String, T>> {
+        let call = QueryCall::new(
+            "select_by_ids",
+            &tb_name,
+            format!("SELECT * FROM {} WHERE id IN ({} ids)", tb_name, ids.len()),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.select_by_ids(tb_name, ids).await,
+                DatabaseClient::Postgres(postgres) => postgres.select_by_ids(tb_name, ids).await,
+            }
+        })
+        .await
     }
 
     /* Method to delete a record from the database */
     async fn delete(&self, id: String) -> Result<bool> {
-        match self {
-            DatabaseClient::Surreal(surrealdb) => surrealdb.delete(id).await,
-            // Add other database client implementations here
-        }
+        let table = table_of(&id);
+        let call = QueryCall::new("delete", &table, format!("DELETE {}", table));
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.delete(id).await,
+                DatabaseClient::Postgres(postgres) => postgres.delete(id).await,
+            }
+        })
+        .await
     }
 
     async fn update_record<T: Serialize + for<'de> Deserialize<'de> + Sync + Send + 'static>(
@@ -80,21 +187,127 @@ impl DBInterface for DatabaseClient {
         tb_name: String,
         data: T,
     ) -> Result<bool> {
-        match self {
-            DatabaseClient::Surreal(surrealdb) => surrealdb.update_record(id, tb_name, data).await,
-        }
+        let call = QueryCall::new(
+            "update_record",
+            &tb_name,
+            format!("UPDATE {} CONTENT", tb_name),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => {
+                    surrealdb.update_record(id, tb_name, data).await
+                }
+                DatabaseClient::Postgres(postgres) => {
+                    postgres.update_record(id, tb_name, data).await
+                }
+            }
+        })
+        .await
     }
 
-    async fn select_where<T: DeserializeOwned + Sync>(
+    async fn merge_record<T: Serialize + Sync + Send + 'static>(
         &self,
+        id: String,
         tb_name: String,
-        filter: String,
-        columns: String, // separate columns by ',' in string format
-    ) -> Result<Vec<T>> {
-        match self {
-            DatabaseClient::Surreal(surrealdb) => {
-                surrealdb.select_where(tb_name, filter, columns).await
+        data: T,
+    ) -> Result<bool> {
+        let call = QueryCall::new(
+            "merge_record",
+            &tb_name,
+            format!("UPDATE {} MERGE", tb_name),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => {
+                    surrealdb.merge_record(id, tb_name, data).await
+                }
+                DatabaseClient::Postgres(postgres) => {
+                    postgres.merge_record(id, tb_name, data).await
+                }
             }
+        })
+        .await
+    }
+
+    async fn merge_versioned<T: Serialize + Sync + Send + 'static>(
+        &self,
+        id: String,
+        tb_name: String,
+        data: T,
+        version: Option<i64>,
+    ) -> Result<bool> {
+        let call = QueryCall::new(
+            "merge_versioned",
+            &tb_name,
+            format!("UPDATE {} MERGE WHERE version", tb_name),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => {
+                    surrealdb.merge_versioned(id, tb_name, data, version).await
+                }
+                DatabaseClient::Postgres(postgres) => {
+                    postgres.merge_versioned(id, tb_name, data, version).await
+                }
+            }
+        })
+        .await
+    }
+
+    async fn select_where<T: DeserializeOwned + Sync>(&self, query: Query) -> Result<Vec<T>> {
+        let call = QueryCall::new("select_where", query.table(), self.statement(&query));
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.select_where(query).await,
+                DatabaseClient::Postgres(postgres) => postgres.select_where(query).await,
+            }
+        })
+        .await
+    }
+
+    async fn commit(&self, transaction: Transaction) -> Result<()> {
+        let call = QueryCall::new(
+            "commit",
+            "transaction",
+            format!("COMMIT ({} operations)", transaction.operations().len()),
+        );
+        metrics::observe(self.metrics(), call, async {
+            match self {
+                DatabaseClient::Surreal(surrealdb) => surrealdb.commit(transaction).await,
+                DatabaseClient::Postgres(postgres) => postgres.commit(transaction).await,
+            }
+        })
+        .await
+    }
+
+    /* Subscriptions stay open for as long as the stream is held, so they are not timed */
+    async fn live<T: DeserializeOwned + HasId + Send + Unpin + 'static>(
+        &self,
+        tb_name: String,
+    ) -> Result<LiveStream<T>> {
+        match self {
+            DatabaseClient::Surreal(surrealdb) => surrealdb.live(tb_name).await,
+            DatabaseClient::Postgres(postgres) => postgres.live(tb_name).await,
         }
     }
 }
+
+/* Table of a `table:id` string, for labelling calls that only carry an id */
+fn table_of(id: &str) -> String {
+    query::thing(id)
+        .map(|thing| thing.tb)
+        .unwrap_or_else(|_| String::from("unknown"))
+}
+
+/* Records that can be keyed by their primary key */
+pub trait HasId {
+    fn id(&self) -> Option<Thing>;
+}
+
+/* Key records by their `table:id`, records without an id are dropped */
+pub(crate) fn key_by_id<T: HasId>(records: Vec<T>) -> HashMap<String, T> {
+    records
+        .into_iter()
+        .filter_map(|record| record.id().map(|id| (id.to_string(), record)))
+        .collect()
+}
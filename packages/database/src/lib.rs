@@ -1,4 +1,12 @@
+pub mod constraint;
 pub mod db;
+pub mod id;
 pub mod interface;
+pub mod live;
+pub mod metrics;
+pub mod migrations;
 pub mod model;
+pub mod postgres;
+pub mod query;
 pub mod surrealdb;
+pub mod transaction;
@@ -0,0 +1,17 @@
+use std::pin::Pin;
+
+use errors::Result;
+use futures::Stream;
+use surrealdb::sql::Thing;
+
+/* Change made to a record of a table a live query watches */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change<T> {
+    Create(T),
+    Update(T),
+    /// Only the id is left of a deleted record
+    Delete(Thing),
+}
+
+/// Changes of a table in the order they were committed, dropping it ends the live query
+pub type LiveStream<T> = Pin<Box<dyn Stream<Item = Result<Change<T>>> + Send>>;
@@ -0,0 +1,287 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt::Write as _,
+    future::Future,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use environment::Environment;
+use errors::Result;
+
+/// Upper bounds of the latency buckets, in seconds
+pub const BUCKETS: [f64; 11] = [
+    0.001, 0.0025, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5,
+];
+
+/* Description of one DBInterface call */
+#[derive(Clone, Debug, PartialEq)]
+pub struct QueryCall {
+    /// DBInterface method that ran
+    pub operation: &'static str,
+    /// Table the call touched
+    pub table: String,
+    /// Statement with its values left out, safe to log and group by
+    pub statement: String,
+}
+
+/* Cumulative latency histogram of one table and operation */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Histogram {
+    /// Calls at or under each bound of BUCKETS
+    pub buckets: [u64; BUCKETS.len()],
+    /// Total time spent, in seconds
+    pub sum: f64,
+    pub count: u64,
+}
+
+/* Timings of every DBInterface call, keyed by table and operation */
+#[derive(Debug)]
+pub struct QueryMetrics {
+    /// Calls taking at least this long are logged as slow
+    slow_query: Duration,
+    histograms: Mutex<BTreeMap<(String, &'static str), Histogram>>,
+}
+
+/* Results whose size is reported as the row count of a call */
+pub trait RowCount {
+    fn rows(&self) -> usize;
+}
+
+/* Implementation of QueryCall */
+impl QueryCall {
+    pub fn new(operation: &'static str, table: impl Into<String>, statement: String) -> Self {
+        QueryCall {
+            operation,
+            table: table.into(),
+            statement,
+        }
+    }
+}
+
+/* Implementation of Histogram */
+impl Histogram {
+    pub fn observe(&mut self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        for (bucket, bound) in self.buckets.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *bucket += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/* Implementation of QueryMetrics */
+impl QueryMetrics {
+    pub fn new(slow_query: Duration) -> Self {
+        QueryMetrics {
+            slow_query,
+            histograms: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /* Metrics with the slow query threshold taken from SLOW_QUERY_MS */
+    pub fn from_environment() -> Self {
+        Self::new(Duration::from_millis(Environment::new().slow_query_ms))
+    }
+
+    /* Add a finished call to its histogram and log it, as a warning when it was slow */
+    pub fn record(&self, call: &QueryCall, rows: Option<usize>, duration: Duration) {
+        self.histograms
+            .lock()
+            .unwrap()
+            .entry((call.table.clone(), call.operation))
+            .or_default()
+            .observe(duration);
+
+        let duration_ms = duration.as_secs_f64() * 1000.0;
+        if self.is_slow(duration) {
+            tracing::warn!(
+                operation = call.operation,
+                table = %call.table,
+                statement = %call.statement,
+                rows,
+                duration_ms,
+                "slow query"
+            );
+        } else {
+            tracing::debug!(
+                operation = call.operation,
+                table = %call.table,
+                statement = %call.statement,
+                rows,
+                duration_ms,
+                "query"
+            );
+        }
+    }
+
+    pub fn is_slow(&self, duration: Duration) -> bool {
+        duration >= self.slow_query
+    }
+
+    /* Histogram recorded so far for a table and operation */
+    pub fn histogram(&self, table: &str, operation: &'static str) -> Option<Histogram> {
+        self.histograms
+            .lock()
+            .unwrap()
+            .get(&(table.to_string(), operation))
+            .cloned()
+    }
+
+    /* Every histogram in the Prometheus text exposition format */
+    pub fn render(&self) -> String {
+        let histograms = self.histograms.lock().unwrap();
+        let mut output = String::from(
+            "# HELP db_query_duration_seconds Latency of database calls per table and operation\n\
+             # TYPE db_query_duration_seconds histogram\n",
+        );
+
+        for ((table, operation), histogram) in histograms.iter() {
+            let labels = format!("table=\"{}\",operation=\"{}\"", table, operation);
+            for (bucket, bound) in histogram.buckets.iter().zip(BUCKETS) {
+                let _ = writeln!(
+                    output,
+                    "db_query_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    labels, bound, bucket
+                );
+            }
+            let _ = writeln!(
+                output,
+                "db_query_duration_seconds_bucket{{{},le=\"+Inf\"}} {}",
+                labels, histogram.count
+            );
+            let _ = writeln!(
+                output,
+                "db_query_duration_seconds_sum{{{}}} {}",
+                labels, histogram.sum
+            );
+            let _ = writeln!(
+                output,
+                "db_query_duration_seconds_count{{{}}} {}",
+                labels, histogram.count
+            );
+        }
+
+        output
+    }
+}
+
+/* Run a database call and record its statement, row count and duration */
+pub async fn observe<T: RowCount>(
+    metrics: &QueryMetrics,
+    call: QueryCall,
+    future: impl Future<Output = Result<T>>,
+) -> Result<T> {
+    let started = Instant::now();
+    let result = future.await;
+    let rows = result.as_ref().ok().map(RowCount::rows);
+
+    metrics.record(&call, rows, started.elapsed());
+    result
+}
+
+impl<T> RowCount for Vec<T> {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> RowCount for Option<T> {
+    fn rows(&self) -> usize {
+        usize::from(self.is_some())
+    }
+}
+
+impl<K, V> RowCount for HashMap<K, V> {
+    fn rows(&self) -> usize {
+        self.len()
+    }
+}
+
+/* Writes report whether a row was touched */
+impl RowCount for bool {
+    fn rows(&self) -> usize {
+        usize::from(*self)
+    }
+}
+
+impl RowCount for () {
+    fn rows(&self) -> usize {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn histograms_count_into_every_bucket_at_or_above_the_duration() {
+        let mut histogram = Histogram::default();
+        histogram.observe(Duration::from_millis(3));
+        histogram.observe(Duration::from_secs(10));
+
+        assert_eq!(histogram.count, 2);
+        assert_eq!(histogram.buckets[0], 0);
+        assert_eq!(histogram.buckets[2], 1);
+        assert_eq!(histogram.buckets[BUCKETS.len() - 1], 1);
+        assert!((histogram.sum - 10.003).abs() < 1e-9);
+    }
+
+    #[test]
+    fn calls_are_kept_per_table_and_operation() {
+        let metrics = QueryMetrics::new(Duration::from_millis(100));
+        let call = QueryCall::new("select_where", "post", String::from("SELECT * FROM post"));
+
+        metrics.record(&call, Some(2), Duration::from_millis(1));
+        metrics.record(&call, None, Duration::from_millis(200));
+
+        let histogram = metrics.histogram("post", "select_where").unwrap();
+        assert_eq!(histogram.count, 2);
+        assert!(metrics.histogram("post", "insert_record").is_none());
+        assert!(metrics.histogram("user", "select_where").is_none());
+
+        assert!(!metrics.is_slow(Duration::from_millis(99)));
+        assert!(metrics.is_slow(Duration::from_millis(100)));
+    }
+
+    #[test]
+    fn render_uses_the_prometheus_histogram_format() {
+        let metrics = QueryMetrics::new(Duration::from_millis(100));
+        let call = QueryCall::new("select", "gym", String::from("SELECT * FROM gym"));
+        metrics.record(&call, Some(1), Duration::from_millis(2));
+
+        let output = metrics.render();
+        assert!(output.contains("# TYPE db_query_duration_seconds histogram"));
+        assert!(output.contains(
+            "db_query_duration_seconds_bucket{table=\"gym\",operation=\"select\",le=\"0.001\"} 0"
+        ));
+        assert!(output.contains(
+            "db_query_duration_seconds_bucket{table=\"gym\",operation=\"select\",le=\"0.0025\"} 1"
+        ));
+        assert!(output.contains(
+            "db_query_duration_seconds_bucket{table=\"gym\",operation=\"select\",le=\"+Inf\"} 1"
+        ));
+        assert!(output
+            .contains("db_query_duration_seconds_count{table=\"gym\",operation=\"select\"} 1"));
+    }
+
+    #[test]
+    fn observe_reports_rows_of_successful_calls() {
+        let metrics = QueryMetrics::new(Duration::from_secs(1));
+        let call = QueryCall::new("select", "user", String::from("SELECT * FROM user"));
+
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        let rows = runtime
+            .block_on(observe(&metrics, call, async { Ok(vec![1, 2, 3]) }))
+            .unwrap();
+
+        assert_eq!(rows.rows(), 3);
+        assert_eq!(metrics.histogram("user", "select").unwrap().count, 1);
+    }
+}
@@ -0,0 +1,222 @@
+use axum::async_trait;
+use errors::Result;
+
+use crate::db::{DatabaseClient, PostgresDb, SurrealDb};
+
+/* A versioned schema change, written once per database backend */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Migration {
+    /// Version number, migrations are applied in ascending order
+    pub version: u32,
+    /// Short name recorded next to the version
+    pub name: &'static str,
+    /// Script applied on SurrealDB
+    pub surrealql: &'static str,
+    /// Script applied on PostgreSQL
+    pub postgres: &'static str,
+}
+
+/* Every migration known to this build, new ones are appended with the next version */
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        surrealql: include_str!("../migrations/surrealdb/0001_initial_schema.surql"),
+        postgres: include_str!("../migrations/postgres/0001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "unique_user_fields",
+        surrealql: include_str!("../migrations/surrealdb/0002_unique_user_fields.surql"),
+        postgres: include_str!("../migrations/postgres/0002_unique_user_fields.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "post_gallery_cascade",
+        surrealql: include_str!("../migrations/surrealdb/0003_post_gallery_cascade.surql"),
+        postgres: include_str!("../migrations/postgres/0003_post_gallery_cascade.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "post_updated_at_required",
+        surrealql: include_str!("../migrations/surrealdb/0004_post_updated_at_required.surql"),
+        postgres: include_str!("../migrations/postgres/0004_post_updated_at_required.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "record_versions",
+        surrealql: include_str!("../migrations/surrealdb/0005_record_versions.surql"),
+        postgres: include_str!("../migrations/postgres/0005_record_versions.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "post_soft_delete_and_audit",
+        surrealql: include_str!("../migrations/surrealdb/0006_post_soft_delete_and_audit.surql"),
+        postgres: include_str!("../migrations/postgres/0006_post_soft_delete_and_audit.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "post_change_notifications",
+        surrealql: include_str!("../migrations/surrealdb/0007_post_change_notifications.surql"),
+        postgres: include_str!("../migrations/postgres/0007_post_change_notifications.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "follow_graph",
+        surrealql: include_str!("../migrations/surrealdb/0008_follow_graph.surql"),
+        postgres: include_str!("../migrations/postgres/0008_follow_graph.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "post_reactions",
+        surrealql: include_str!("../migrations/surrealdb/0009_post_reactions.surql"),
+        postgres: include_str!("../migrations/postgres/0009_post_reactions.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "post_comments",
+        surrealql: include_str!("../migrations/surrealdb/0010_post_comments.surql"),
+        postgres: include_str!("../migrations/postgres/0010_post_comments.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "hashtags_and_mentions",
+        surrealql: include_str!("../migrations/surrealdb/0011_hashtags_and_mentions.surql"),
+        postgres: include_str!("../migrations/postgres/0011_hashtags_and_mentions.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "notifications",
+        surrealql: include_str!("../migrations/surrealdb/0012_notifications.surql"),
+        postgres: include_str!("../migrations/postgres/0012_notifications.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "direct_messages",
+        surrealql: include_str!("../migrations/surrealdb/0013_direct_messages.surql"),
+        postgres: include_str!("../migrations/postgres/0013_direct_messages.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "gym_memberships",
+        surrealql: include_str!("../migrations/surrealdb/0014_gym_memberships.surql"),
+        postgres: include_str!("../migrations/postgres/0014_gym_memberships.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "class_schedule",
+        surrealql: include_str!("../migrations/surrealdb/0015_class_schedule.surql"),
+        postgres: include_str!("../migrations/postgres/0015_class_schedule.sql"),
+    },
+];
+
+const SURREALDB_BOOKKEEPING: &str = r#"
+DEFINE TABLE IF NOT EXISTS schema_migration SCHEMAFULL;
+DEFINE FIELD IF NOT EXISTS version ON schema_migration TYPE int;
+DEFINE FIELD IF NOT EXISTS name ON schema_migration TYPE string;
+DEFINE FIELD IF NOT EXISTS applied_at ON schema_migration TYPE datetime;
+DEFINE INDEX IF NOT EXISTS schema_migration_version_idx ON schema_migration FIELDS version UNIQUE;
+"#;
+
+const POSTGRES_BOOKKEEPING: &str = r#"
+CREATE TABLE IF NOT EXISTS schema_migration (
+    version INTEGER PRIMARY KEY,
+    name TEXT NOT NULL,
+    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+"#;
+
+/* Trait for bringing a database schema up to date */
+#[async_trait]
+pub trait Migrator {
+    /* Apply every pending migration and return the versions that were applied */
+    async fn migrate(&self) -> Result<Vec<u32>>;
+}
+
+/* Migrations that have not been recorded yet, in version order */
+fn pending(applied: &[i64]) -> Vec<&'static Migration> {
+    let mut pending: Vec<&'static Migration> = MIGRATIONS
+        .iter()
+        .filter(|migration| !applied.contains(&(migration.version as i64)))
+        .collect();
+    pending.sort_by_key(|migration| migration.version);
+    pending
+}
+
+/* Implementation of Migrator for SurrealDb */
+#[async_trait]
+impl Migrator for SurrealDb {
+    #[tracing::instrument(err, skip_all)]
+    async fn migrate(&self) -> Result<Vec<u32>> {
+        let client = self.client()?;
+        client.query(SURREALDB_BOOKKEEPING).await?.check()?;
+
+        let mut response = client
+            .query("SELECT VALUE version FROM schema_migration")
+            .await?;
+        let applied: Vec<i64> = response.take(0)?;
+
+        let mut versions = Vec::new();
+        for migration in pending(&applied) {
+            // The script and its bookkeeping row are committed together
+            let sql = format!(
+                "BEGIN TRANSACTION;\n{}\nCREATE schema_migration CONTENT {{ version: $version, name: $name, applied_at: time::now() }};\nCOMMIT TRANSACTION;",
+                migration.surrealql
+            );
+
+            client
+                .query(sql)
+                .bind(("version", migration.version))
+                .bind(("name", migration.name))
+                .await?
+                .check()?;
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+}
+
+/* Implementation of Migrator for PostgresDb */
+#[async_trait]
+impl Migrator for PostgresDb {
+    #[tracing::instrument(err, skip_all)]
+    async fn migrate(&self) -> Result<Vec<u32>> {
+        let mut client = self.pool()?.get().await?;
+        client.batch_execute(POSTGRES_BOOKKEEPING).await?;
+
+        let applied: Vec<i64> = client
+            .query("SELECT version FROM schema_migration", &[])
+            .await?
+            .iter()
+            .map(|row| row.get::<_, i32>(0) as i64)
+            .collect();
+
+        let mut versions = Vec::new();
+        for migration in pending(&applied) {
+            let transaction = client.transaction().await?;
+            transaction.batch_execute(migration.postgres).await?;
+            transaction
+                .execute(
+                    "INSERT INTO schema_migration (version, name) VALUES ($1, $2)",
+                    &[&(migration.version as i32), &migration.name],
+                )
+                .await?;
+            transaction.commit().await?;
+            versions.push(migration.version);
+        }
+
+        Ok(versions)
+    }
+}
+
+/* Implementation of Migrator for DatabaseClient */
+#[async_trait]
+impl Migrator for DatabaseClient {
+    async fn migrate(&self) -> Result<Vec<u32>> {
+        match self {
+            DatabaseClient::Surreal(surrealdb) => surrealdb.migrate().await,
+            DatabaseClient::Postgres(postgres) => postgres.migrate().await,
+        }
+    }
+}
@@ -1,11 +1,22 @@
+use std::collections::BTreeMap;
+
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Datetime, Thing};
 
+use crate::{
+    id::{
+        ClassBookingId, ClassSessionId, CommentId, ConversationId, FollowId, GymId, GymSeekerId,
+        LocationId, MembershipId, MembershipPlanId, MentionId, MessageId, NotificationId,
+        PostHashtagId, PostId, ProfileId, ReactionId, TrainerId, UserId,
+    },
+    interface::HasId,
+};
+
 /* Struct representing a User in the database */
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[allow(dead_code)]
 pub struct User {
-    pub id: Option<Thing>,
+    pub id: Option<UserId>,
     /// Username of the user
     pub username: String,
     /// Type of the user (e.g., admin, regular user)
@@ -28,21 +39,24 @@ pub struct User {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Gym {
     /// Primary Key
-    pub id: Option<Thing>,
+    pub id: Option<GymId>,
     /// Name of the gym
     pub name: String,
     /// Detailed description of gym facilities and services
     pub description: String,
     /// References location_id for gym's location
-    pub location_id: Option<Thing>,
+    pub location_id: Option<LocationId>,
     /// References user_id of the gym owner
-    pub owner_id: Thing,
+    pub owner_id: UserId,
     /// Path or URL to profile picture
     pub profile_picture: String,
     /// Timestamp when the gym was added
     pub created_at: Option<Datetime>, // Timestamp when the gym was added
     /// Timestamp of the last update
     pub updated_at: Option<Datetime>, // Timestamp of the last update
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing an ID in the database */
@@ -95,15 +109,22 @@ pub struct PayloadGymResponses {
     /// Detailed description of gym facilities and services
     pub description: String,
     /// References location_id for gym's location
-    pub location_id: Option<Thing>,
+    pub location_id: Option<LocationId>,
     /// Path or URL to profile picture of the gym
     pub profile_picture: String,
     /// References user_id of the gym owner
-    pub owner_id: Option<Thing>,
+    pub owner_id: Option<UserId>,
     /// Timestamp when the gym was added
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing responses for gym profile */
@@ -119,32 +140,49 @@ pub struct PayloadGymProfileResponses {
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing requests for gym information */
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadGymRequest {
     /// Name of the gym (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Detailed description of gym facilities and services (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
     /// Path or URL to the profile picture of the gym (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub profile_picture: Option<String>,
     /// References user_id of the gym owner (optional)
-    pub owner_id: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owner_id: Option<UserId>,
     /// References location_id for gym's location (optional)
-    pub location_id: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location_id: Option<LocationId>,
     /// Timestamp when the gym was added (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<Datetime>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
 }
 
 /* Struct representing user responses in the database */
 #[derive(Clone, Debug, PartialEq, Deserialize)]
 pub struct PayloadUserResponse {
     /// Primary Key of the user
-    pub id: Thing,
+    pub id: UserId,
     /// Username of the user
     pub username: String,
     /// Type of the user (e.g., admin, regular user)
@@ -167,7 +205,7 @@ pub struct PayloadUserResponse {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct GymSeeker {
     /// Primary Key of the gym seeker
-    pub id: Option<Thing>,
+    pub id: Option<GymSeekerId>,
     /// Name of the gym
     pub name: String,
     /// Date of birth of the gym seeker (optional)
@@ -175,7 +213,7 @@ pub struct GymSeeker {
     /// Gender of the gym seeker
     pub sex: String,
     /// References user_id of the gym seeker (optional)
-    pub user_id: Option<Thing>,
+    pub user_id: Option<UserId>,
     /// Path or URL to the profile picture of the gym seeker
     pub profile_picture: String,
     /// Description of fitness goals
@@ -184,43 +222,56 @@ pub struct GymSeeker {
     pub preferred_workout_time: String,
     /// Preferences for gyms
     pub gym_preferences: String,
-    /// Current membership status
-    pub membership_status: String,
     /// Short biography of the gym seeker
     pub bio: String,
     /// Timestamp when the seeker registered (optional)
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing requests for gym seeker information */
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadGymSeekerRequest {
     /// Name of the gym
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Date of birth of the gym seeker (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub birth_date: Option<Datetime>,
     /// Gender of the gym seeker (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sex: Option<String>,
     /// References user_id of the gym seeker (optional)
-    pub user_id: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<UserId>,
     /// Path or URL to the profile picture of the gym seeker (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub profile_picture: Option<String>,
     /// Description of fitness goals (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub fitness_goals: Option<String>,
     /// Preferred times for workouts (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub preferred_workout_time: Option<String>,
     /// Preferences for gyms (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub gym_preferences: Option<String>,
-    /// Current membership status (optional)
-    pub membership_status: Option<String>,
     /// Short biography of the gym seeker (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub bio: Option<String>,
     /// Timestamp when the seeker registered (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<Datetime>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
 }
 
 /* Struct representing responses for gym seeker information */
@@ -235,7 +286,7 @@ pub struct PayloadGymSeekerResponse {
     /// Gender of the gym seeker
     pub sex: String,
     /// References user_id of the gym seeker (optional)
-    pub user_id: Option<Thing>,
+    pub user_id: Option<UserId>,
     /// Path or URL to the profile picture of the gym seeker
     pub profile_picture: String,
     /// Description of fitness goals
@@ -244,14 +295,21 @@ pub struct PayloadGymSeekerResponse {
     pub preferred_workout_time: String,
     /// Preferences for gyms
     pub gym_preferences: String,
-    /// Current membership status
-    pub membership_status: String,
+    /// Status derived from the gym memberships, None when the seeker never joined a gym
+    pub membership_status: Option<MembershipStatus>,
     /// Short biography of the gym seeker
     pub bio: String,
     /// Timestamp when the seeker registered (optional)
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing a gym seeker profile response */
@@ -271,14 +329,21 @@ pub struct PayloadGymSeekerProfileResponse {
     pub preferred_workout_time: String,
     /// Preferences for gyms
     pub gym_preferences: String,
-    /// Current membership status
-    pub membership_status: String,
+    /// Status derived from the gym memberships, None when the seeker never joined a gym
+    pub membership_status: Option<MembershipStatus>,
     /// Short biography of the gym seeker
     pub bio: String,
     /// Timestamp when the seeker registered (optional)
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing login user credentials */
@@ -295,7 +360,7 @@ pub struct LoginUserSchema {
 #[allow(dead_code)]
 pub struct Location {
     /// Primary Key of the location (optional)
-    pub id: Option<Thing>,
+    pub id: Option<LocationId>,
     /// Full address of the location
     pub address: String,
     /// Latitude coordinate of the location
@@ -306,6 +371,9 @@ pub struct Location {
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
 }
 
 /* Struct representing requests for location information */
@@ -318,16 +386,21 @@ pub struct PayloadLocationRequest {
     /// Longitude coordinate of the location
     pub longitude: f64,
     /// Timestamp when the location was added (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<Datetime>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
 }
 
 /* Struct representing responses for location information */
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadLocationResponse {
     /// Primary Key of the location (optional)
-    pub id: Option<Thing>,
+    pub id: Option<LocationId>,
     /// Full address of the location (optional)
     pub address: Option<String>,
     /// Latitude coordinate of the location (optional)
@@ -344,7 +417,7 @@ pub struct PayloadLocationResponse {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trainer {
     /// Primary Key - Unique identifier for the trainer (optional)
-    pub id: Option<Thing>,
+    pub id: Option<TrainerId>,
     /// Name of the trainer
     pub name: Option<String>,
     /// Gender of the gym seeker
@@ -354,33 +427,47 @@ pub struct Trainer {
     /// Field of specialization (e.g., yoga, strength training) (optional)
     pub expertise: Option<String>,
     /// Foreign Key - References the gym where the trainer is based
-    pub gym_id: Thing,
+    pub gym_id: GymId,
     /// Foreign Key - References the user_id of the trainer
-    pub user_id: Thing,
+    pub user_id: UserId,
     /// Timestamp when the trainer was added (optional)
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadTrainerRequest {
     /// Name of the trainer
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub name: Option<String>,
     /// Gender of the gym seeker
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub sex: Option<String>,
     /// year of the trainer exeperience
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub experience: Option<i64>,
     /// Field of specialization (e.g., yoga, strength training) (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub expertise: Option<String>,
     /// Foreign Key - References the gym where the trainer is based
-    pub gym_id: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gym_id: Option<GymId>,
     /// Foreign Key - References the user_id of the trainer
-    pub user_id: Option<Thing>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_id: Option<UserId>,
     /// Timestamp when the trainer was added (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<Datetime>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -400,6 +487,13 @@ pub struct PayloadTrainerResponse {
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -416,20 +510,27 @@ pub struct PayloadTrainerProfileResponse {
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Number of profiles following this one
+    pub followers_count: u64,
+    /// Number of profiles this one follows
+    pub following_count: u64,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Post {
     /// Primary Key
-    pub id: Option<Thing>,
+    pub id: Option<PostId>,
     /// References the user creating the post
-    pub user_id: Option<Thing>,
+    pub user_id: Option<UserId>,
     /// Foreign Key - Related gym ID if post is gym-specific (optional)
-    pub gym_id: Option<Thing>,
+    pub gym_id: Option<GymId>,
     /// Foreign Key - ID if post relates to a gym seeker (optional)
-    pub gym_seeker_id: Option<Thing>,
+    pub gym_seeker_id: Option<GymSeekerId>,
     /// Foreign Key - ID if post relates to a trainer (optional)
-    pub trainer_id: Option<Thing>,
+    pub trainer_id: Option<TrainerId>,
     /// Flag indicating if the post is from a gym (optional)
     pub is_gym: Option<bool>,
     /// Flag indicating if the post is from a gym seeker (optional)
@@ -442,6 +543,12 @@ pub struct Post {
     pub created_at: Option<Datetime>,
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
+    /// Timestamp of the soft deletion, hidden from every read while set (optional)
+    #[serde(default)]
+    pub deleted_at: Option<Datetime>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -452,15 +559,15 @@ pub struct PayloadPostRequest {
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadPostResponse {
     /// Primary Key
-    pub id: Option<Thing>,
+    pub id: Option<PostId>,
     /// References the user creating the post
-    pub user_id: Option<Thing>,
+    pub user_id: Option<UserId>,
     /// Foreign Key - Related gym ID if post is gym-specific (optional)
-    pub gym_id: Option<Thing>,
+    pub gym_id: Option<GymId>,
     /// Foreign Key - ID if post relates to a gym seeker (optional)
-    pub gym_seeker_id: Option<Thing>,
+    pub gym_seeker_id: Option<GymSeekerId>,
     /// Foreign Key - ID if post relates to a trainer (optional)
-    pub trainer_id: Option<Thing>,
+    pub trainer_id: Option<TrainerId>,
     /// Flag indicating if the post is from a gym (optional)
     pub is_gym: Option<bool>,
     /// Flag indicating if the post is from a gym seeker (optional)
@@ -474,6 +581,9 @@ pub struct PayloadPostResponse {
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
     pub post_gallery: Option<Vec<PostGalleryForFeed>>,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
@@ -501,12 +611,753 @@ pub struct PayloadPostResponseDeserilize {
     /// Timestamp of the last update (optional)
     pub updated_at: Option<Datetime>,
     pub post_gallery: Option<Vec<PostGalleryForFeedDeserialize>>,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    #[serde(default)]
+    pub version: i64,
+    /// Number of reactions of each kind
+    #[serde(default)]
+    pub reaction_counts: BTreeMap<ReactionKind, u64>,
+    /// Reaction the requesting user left, None when they have not reacted
+    #[serde(default)]
+    pub reacted_by_me: Option<ReactionKind>,
 }
 
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct PayloadPostUpdateRequest {
     pub content: String,
     pub content_id: String,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
+}
+
+/* Struct representing the fields written when a post is edited */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PostContentUpdate {
+    /// New content of the post
+    pub content: String,
+    /// Timestamp of the edit
+    pub updated_at: Datetime,
+}
+
+/* Struct representing the soft deletion marker of a post, None restores it */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PostDeletion {
+    /// Timestamp of the deletion
+    pub deleted_at: Option<Datetime>,
+}
+
+/* Struct representing a follow edge, `follower_id` follows `followee_id` */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Follow {
+    /// Primary Key
+    pub id: Option<FollowId>,
+    /// Profile doing the following
+    pub follower_id: ProfileId,
+    /// Profile being followed
+    pub followee_id: ProfileId,
+    /// Timestamp when the follow started
+    pub created_at: Datetime,
+}
+
+/* Struct representing a profile listed as a follower or as followed */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadFollowResponse {
+    /// Primary Key of the profile
+    pub id: String,
+    /// Type of the profile (gym, gym_seeker or trainer)
+    pub user_type: String,
+    /// Name of the profile, None when it no longer exists
+    pub name: Option<String>,
+    /// Path or URL to the profile picture (optional)
+    pub profile_picture: Option<String>,
+    /// Timestamp when the follow started
+    pub followed_at: Datetime,
+}
+
+/* Kinds of reaction a user can leave on a post */
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum ReactionKind {
+    #[default]
+    Like,
+    Love,
+    Fire,
+    Strong,
+    Clap,
+}
+
+/* Struct representing a user's reaction to a post, one per user and post */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Reaction {
+    /// Primary Key
+    pub id: Option<ReactionId>,
+    /// Post reacted to
+    pub post_id: PostId,
+    /// User reacting
+    pub user_id: UserId,
+    /// Kind of the reaction
+    pub kind: ReactionKind,
+    /// Timestamp when the reaction was left or last changed
+    pub created_at: Datetime,
+}
+
+/* Struct representing the fields written when a user changes their reaction */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ReactionUpdate {
+    /// New kind of the reaction
+    pub kind: ReactionKind,
+    /// Timestamp of the change
+    pub created_at: Datetime,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadReactionRequest {
+    /// Kind of the reaction, a like unless set
+    #[serde(default)]
+    pub kind: ReactionKind,
+}
+
+/* Struct representing the number of reactions of one kind on one post */
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ReactionCount {
+    pub post_id: PostId,
+    pub kind: ReactionKind,
+    pub count: u64,
+}
+
+/* Struct representing the reactions on a post as seen by one user */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reactions {
+    /// Number of reactions of each kind
+    pub counts: BTreeMap<ReactionKind, u64>,
+    /// Reaction the user left, None when they have not reacted
+    pub mine: Option<ReactionKind>,
+}
+
+/* Struct representing a comment on a post, or a reply to a top-level comment */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Comment {
+    /// Primary Key
+    pub id: Option<CommentId>,
+    /// Post commented on
+    pub post_id: PostId,
+    /// Top-level comment this one replies to, None for a top-level comment
+    pub parent_id: Option<CommentId>,
+    /// User writing the comment
+    pub user_id: UserId,
+    /// Text of the comment
+    pub content: String,
+    /// Timestamp when the comment was written
+    pub created_at: Datetime,
+    /// Timestamp of the last edit
+    pub updated_at: Datetime,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
+}
+
+/* Struct representing the fields written when a comment is edited */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct CommentContentUpdate {
+    /// New text of the comment
+    pub content: String,
+    /// Timestamp of the edit
+    pub updated_at: Datetime,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadCommentRequest {
+    pub content: String,
+    /// Top-level comment to reply to (optional)
+    #[serde(default)]
+    pub parent_id: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadCommentUpdateRequest {
+    pub content: String,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
+}
+
+/* Struct representing a comment as listed under a post or a comment */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadCommentResponse {
+    /// Primary Key
+    pub id: String,
+    /// Post commented on
+    pub post_id: String,
+    /// Top-level comment this one replies to, None for a top-level comment
+    pub parent_id: Option<String>,
+    /// User writing the comment
+    pub user_id: String,
+    /// Username of the author, empty when the user no longer exists
+    pub username: String,
+    /// Type of the author (gym, gym_seeker or trainer)
+    pub user_type: String,
+    pub content: String,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    pub version: i64,
+    /// Number of replies, always 0 for a reply
+    pub reply_count: u64,
+}
+
+/* Struct representing the number of comments and replies on one post */
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct CommentCount {
+    pub post_id: PostId,
+    pub count: u64,
+}
+
+/* Struct representing the number of replies to one top-level comment */
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct ReplyCount {
+    pub parent_id: CommentId,
+    pub count: u64,
+}
+
+/* Struct representing a hashtag used in a post */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PostHashtag {
+    /// Primary Key
+    pub id: Option<PostHashtagId>,
+    /// Post using the hashtag
+    pub post_id: PostId,
+    /// Hashtag without its `#`, lowercased
+    pub tag: String,
+    /// Timestamp when the post was created
+    pub created_at: Datetime,
+}
+
+/* Struct representing a user mentioned in a post */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Mention {
+    /// Primary Key
+    pub id: Option<MentionId>,
+    /// Post mentioning the user
+    pub post_id: PostId,
+    /// User mentioned
+    pub user_id: UserId,
+    /// Timestamp when the mention was first written
+    pub created_at: Datetime,
+}
+
+/* Struct representing how often a hashtag was used, also a row of grouped hashtag counts */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadHashtagCount {
+    /// Hashtag without its `#`
+    pub tag: String,
+    /// Number of posts using it
+    pub count: u64,
+}
+
+/// Default window trending hashtags are counted over, in hours
+pub const DEFAULT_TRENDING_HOURS: u64 = 24;
+/// Longest window trending hashtags can be counted over, in hours
+pub const MAX_TRENDING_HOURS: u64 = 24 * 30;
+
+#[derive(Deserialize)]
+pub struct TrendingWindow {
+    /// Hours back from now the hashtags are counted over
+    pub hours: Option<u64>,
+    /// Number of hashtags returned
+    pub limit: Option<u64>,
+}
+
+impl TrendingWindow {
+    /* Requested window, falling back to the default and capped at MAX_TRENDING_HOURS */
+    pub fn hours(&self) -> u64 {
+        self.hours
+            .unwrap_or(DEFAULT_TRENDING_HOURS)
+            .clamp(1, MAX_TRENDING_HOURS)
+    }
+
+    /* Requested number of hashtags, falling back to the default and capped at MAX_PAGE_SIZE */
+    pub fn limit(&self, default: u64) -> u64 {
+        self.limit.unwrap_or(default).clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/* Events a user is notified about */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    /// A profile followed one of the user's profiles
+    Follow,
+    /// Someone commented on the user's post
+    Comment,
+    /// Someone replied to the user's comment
+    Reply,
+    /// The user was mentioned in a post
+    Mention,
+    /// A gym seeker joined the user's gym
+    MembershipJoined,
+    /// A gym membership of the user, or of a member of the user's gym, was cancelled
+    MembershipCancelled,
+}
+
+/* Struct representing a notification of one user about something another user did */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Notification {
+    /// Primary Key
+    pub id: Option<NotificationId>,
+    /// User notified
+    pub user_id: UserId,
+    /// User whose action caused the notification
+    pub actor_id: UserId,
+    pub kind: NotificationKind,
+    /// Profile that followed, joined or cancelled, set for follow and membership notifications
+    pub profile_id: Option<ProfileId>,
+    /// Post commented on or mentioning the user
+    pub post_id: Option<PostId>,
+    /// Comment or reply written, set for comment and reply notifications
+    pub comment_id: Option<CommentId>,
+    /// Timestamp when the user read it, None while unread
+    pub read_at: Option<Datetime>,
+    pub created_at: Datetime,
+}
+
+/* Struct representing the only field written when a notification is read */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NotificationRead {
+    pub read_at: Datetime,
+}
+
+/* Struct representing a notification as listed and pushed to its user */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadNotificationResponse {
+    /// Primary Key
+    pub id: String,
+    pub kind: NotificationKind,
+    /// User whose action caused the notification
+    pub actor_id: String,
+    /// Username of the actor, empty when the user no longer exists
+    pub actor_username: String,
+    pub profile_id: Option<String>,
+    pub post_id: Option<String>,
+    pub comment_id: Option<String>,
+    pub read: bool,
+    pub created_at: Datetime,
+}
+
+#[derive(Deserialize)]
+pub struct NotificationFilter {
+    /// Only list notifications that were not read yet
+    #[serde(default)]
+    pub unread: bool,
+}
+
+/* Struct representing the direct messages between two users */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Conversation {
+    /// Primary Key
+    pub id: Option<ConversationId>,
+    /// Participant whose id sorts first, so each pair has one conversation
+    pub first_user_id: UserId,
+    pub second_user_id: UserId,
+    pub created_at: Datetime,
+    /// Timestamp of the latest message, the conversation list is ordered by it
+    pub last_message_at: Datetime,
+}
+
+impl Conversation {
+    /* Whether the user takes part in the conversation */
+    pub fn has_participant(&self, user_id: &UserId) -> bool {
+        &self.first_user_id == user_id || &self.second_user_id == user_id
+    }
+
+    /* Participant other than `user_id` */
+    pub fn other_participant(&self, user_id: &UserId) -> &UserId {
+        if &self.first_user_id == user_id {
+            &self.second_user_id
+        } else {
+            &self.first_user_id
+        }
+    }
+}
+
+/* Struct representing the only field written when a conversation gets a message */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ConversationActivity {
+    pub last_message_at: Datetime,
+}
+
+/* Struct representing a direct message */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Message {
+    /// Primary Key
+    pub id: Option<MessageId>,
+    pub conversation_id: ConversationId,
+    /// User who wrote the message
+    pub sender_id: UserId,
+    pub content: String,
+    /// Timestamp when the other participant read it, None while unread
+    pub read_at: Option<Datetime>,
+    pub created_at: Datetime,
+}
+
+/* Struct representing the only field written when a message is read */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MessageRead {
+    pub read_at: Datetime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadConversationRequest {
+    /// User to talk to
+    pub user_id: String,
+}
+
+/* Struct representing a conversation as listed for one of its participants */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadConversationResponse {
+    /// Primary Key
+    pub id: String,
+    /// Other participant
+    pub user_id: String,
+    /// Username of the other participant, empty when the user no longer exists
+    pub username: String,
+    /// Type of the other participant (gym, gym_seeker or trainer)
+    pub user_type: String,
+    pub last_message_at: Datetime,
+    /// Messages of the other participant the caller has not read
+    pub unread_count: u64,
+}
+
+/* Struct representing a direct message as listed and pushed to the participants */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadMessageResponse {
+    /// Primary Key
+    pub id: String,
+    pub conversation_id: String,
+    pub sender_id: String,
+    pub content: String,
+    pub read_at: Option<Datetime>,
+    pub created_at: Datetime,
+}
+
+/* Struct representing the number of unread messages in one conversation */
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct MessageCount {
+    pub conversation_id: ConversationId,
+    pub count: u64,
+}
+
+/* Frame a client sends over the direct message socket */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PayloadChatRequest {
+    /// Send a message to the conversation
+    Message {
+        conversation_id: String,
+        content: String,
+    },
+    /// Tell the other participant the user is typing
+    Typing { conversation_id: String },
+    /// Mark the messages of the other participant read
+    Read { conversation_id: String },
+}
+
+/* Struct representing a membership plan a gym sells */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MembershipPlan {
+    /// Primary Key
+    pub id: Option<MembershipPlanId>,
+    /// Gym selling the plan
+    pub gym_id: GymId,
+    pub name: String,
+    /// Price in the smallest currency unit
+    pub price_cents: i64,
+    /// Number of days a membership on this plan lasts
+    pub duration_days: i64,
+    /// What members on this plan get
+    pub benefits: Vec<String>,
+    /// Timestamp when the gym stopped selling the plan, memberships on it keep running
+    pub archived_at: Option<Datetime>,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
+}
+
+/* Struct representing a request to create or change a membership plan, only the fields sent are written */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadMembershipPlanRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Price in the smallest currency unit (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub price_cents: Option<i64>,
+    /// Number of days a membership lasts (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_days: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub benefits: Option<Vec<String>>,
+    /// Timestamp of the last update (optional)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<Datetime>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
+}
+
+/* Struct representing the only field written when a plan is archived */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MembershipPlanArchive {
+    pub archived_at: Datetime,
+}
+
+/* Struct representing a membership plan as listed */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadMembershipPlanResponse {
+    /// Primary Key
+    pub id: String,
+    pub gym_id: String,
+    pub name: String,
+    pub price_cents: i64,
+    pub duration_days: i64,
+    pub benefits: Vec<String>,
+    /// Whether the gym stopped selling the plan
+    pub archived: bool,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    pub version: i64,
+}
+
+/* Status of a membership, derived from its dates rather than stored. Ordered so the most relevant of several is the greatest */
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipStatus {
+    /// Cancelled by the member or the gym before it ended
+    Cancelled,
+    /// Ran until its end date
+    Expired,
+    /// Started and not yet ended
+    Active,
+}
+
+/* Struct representing the enrollment of a gym seeker in a gym, one per pair renewed on every join */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Membership {
+    /// Primary Key
+    pub id: Option<MembershipId>,
+    pub gym_id: GymId,
+    pub gym_seeker_id: GymSeekerId,
+    /// Plan of the latest enrollment
+    pub plan_id: MembershipPlanId,
+    pub start_at: Datetime,
+    /// Timestamp when the membership runs out, start plus the plan duration
+    pub end_at: Datetime,
+    /// Timestamp when it was cancelled, None unless cancelled
+    pub cancelled_at: Option<Datetime>,
+    pub created_at: Datetime,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
+}
+
+impl Membership {
+    /* Status of the membership at `now` */
+    pub fn status(&self, now: &Datetime) -> MembershipStatus {
+        if self.cancelled_at.is_some() {
+            MembershipStatus::Cancelled
+        } else if &self.end_at <= now {
+            MembershipStatus::Expired
+        } else {
+            MembershipStatus::Active
+        }
+    }
+}
+
+/* Struct representing the fields written when a gym seeker joins again on a new plan */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MembershipRenewal {
+    pub plan_id: MembershipPlanId,
+    pub start_at: Datetime,
+    pub end_at: Datetime,
+    pub cancelled_at: Option<Datetime>,
+}
+
+/* Struct representing the only field written when a membership is cancelled */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MembershipCancellation {
+    pub cancelled_at: Datetime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PayloadMembershipRequest {
+    /// Plan to join the gym on
+    pub plan_id: String,
+}
+
+/* Struct representing a membership as listed to the member and to the gym */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadMembershipResponse {
+    /// Primary Key
+    pub id: String,
+    pub gym_id: String,
+    pub gym_seeker_id: String,
+    pub plan_id: String,
+    /// Name of the plan, empty when it no longer exists
+    pub plan_name: String,
+    pub start_at: Datetime,
+    pub end_at: Datetime,
+    pub cancelled_at: Option<Datetime>,
+    pub status: MembershipStatus,
+}
+
+#[derive(Deserialize)]
+pub struct MembershipFilter {
+    /// Only list memberships that are active
+    #[serde(default)]
+    pub active: bool,
+}
+
+/* Struct representing a group class a gym holds, optionally led by one of its trainers */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassSession {
+    /// Primary Key
+    pub id: Option<ClassSessionId>,
+    /// Gym holding the class
+    pub gym_id: GymId,
+    /// Trainer leading the class, None when nobody is assigned
+    pub trainer_id: Option<TrainerId>,
+    pub title: String,
+    pub description: String,
+    pub start_at: Datetime,
+    pub duration_minutes: i64,
+    /// Number of seats
+    pub capacity: i64,
+    /// Room or area of the gym the class takes place in
+    pub location: String,
+    /// Seats taken, only ever changed together with the version so it never exceeds the capacity
+    pub booked_count: i64,
+    /// Timestamp when the gym cancelled the class, None unless cancelled
+    pub cancelled_at: Option<Datetime>,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+    /// Bumped by every update, a write carrying an older value is rejected
+    #[serde(default)]
+    pub version: i64,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadClassSessionRequest {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    /// Trainer of the gym leading the class (optional)
+    pub trainer_id: Option<String>,
+    pub start_at: Option<Datetime>,
+    pub duration_minutes: Option<i64>,
+    pub capacity: Option<i64>,
+    pub location: Option<String>,
+    /// Version the client last read, the update fails with a conflict when it is stale (optional)
+    #[serde(default, skip_serializing)]
+    pub version: Option<i64>,
+}
+
+/* Struct representing the fields written when a class is changed, only the ones sent are set */
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClassSessionUpdate {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trainer_id: Option<TrainerId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_at: Option<Datetime>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_minutes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub capacity: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub location: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cancelled_at: Option<Datetime>,
+    pub updated_at: Datetime,
+}
+
+/* Struct representing a class as listed in a gym's schedule */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadClassSessionResponse {
+    /// Primary Key
+    pub id: String,
+    pub gym_id: String,
+    pub trainer_id: Option<String>,
+    pub title: String,
+    pub description: String,
+    pub start_at: Datetime,
+    pub duration_minutes: i64,
+    pub capacity: i64,
+    pub location: String,
+    pub booked_count: i64,
+    /// Seats still free, 0 once the class is cancelled
+    pub seats_left: i64,
+    pub cancelled: bool,
+    /// Whether the requesting gym seeker holds a seat
+    pub booked_by_me: bool,
+    /// Current version, sent back as `If-Match` or `version` when updating
+    pub version: i64,
+}
+
+/* Struct representing the seat of a gym seeker in a class */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ClassBooking {
+    /// Primary Key
+    pub id: Option<ClassBookingId>,
+    pub session_id: ClassSessionId,
+    pub gym_seeker_id: GymSeekerId,
+    pub created_at: Datetime,
+}
+
+/* Struct representing a booking as listed to the gym */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PayloadClassBookingResponse {
+    /// Primary Key
+    pub id: String,
+    pub session_id: String,
+    pub gym_seeker_id: String,
+    /// Name of the gym seeker, empty when the profile no longer exists
+    pub name: String,
+    pub profile_picture: String,
+    pub created_at: Datetime,
+}
+
+/// Days of schedule listed when the client does not ask for a window
+pub const DEFAULT_SCHEDULE_DAYS: i64 = 7;
+/// Longest window of schedule a client may request, in days
+pub const MAX_SCHEDULE_DAYS: i64 = 31;
+
+#[derive(Deserialize)]
+pub struct ScheduleFilter {
+    /// Start of the listed window, now by default
+    pub from: Option<Datetime>,
+    /// Number of days listed from `from`
+    pub days: Option<i64>,
+}
+
+impl ScheduleFilter {
+    /* Requested number of days, falling back to the default and capped at MAX_SCHEDULE_DAYS */
+    pub fn days(&self) -> i64 {
+        self.days
+            .unwrap_or(DEFAULT_SCHEDULE_DAYS)
+            .clamp(1, MAX_SCHEDULE_DAYS)
+    }
+}
+
+/* Struct representing the number of records matched by a counting query */
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct Count {
+    pub count: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -517,6 +1368,7 @@ pub struct FeedLocation {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Feed {
+    pub id: Option<PostId>,
     pub content: String,
     pub gym_name: Option<String>, // Use Option because it can be NONE
     pub location: Option<FeedLocation>, // Use Option because it can be NULL
@@ -529,6 +1381,7 @@ pub struct Feed {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PayloadFeed {
+    pub id: String,
     pub content: String,
     pub gym_name: Option<String>, // Use Option because it can be NONE
     pub location: Option<FeedLocation>, // Use Option because it can be NULL
@@ -537,11 +1390,60 @@ pub struct PayloadFeed {
     pub post_gallery: Option<Vec<PostGalleryForFeedDeserialize>>,
     pub user_type: String,
     pub username: String,
+    /// Number of reactions of each kind
+    #[serde(default)]
+    pub reaction_counts: BTreeMap<ReactionKind, u64>,
+    /// Reaction the requesting user left, None when they have not reacted
+    #[serde(default)]
+    pub reacted_by_me: Option<ReactionKind>,
+    /// Number of comments and replies on the post
+    #[serde(default)]
+    pub comment_count: u64,
 }
 
+/// Largest page a client may request
+pub const MAX_PAGE_SIZE: u64 = 50;
+
 #[derive(Deserialize)]
 pub struct Pagination {
-    pub page: Option<i64>,
+    /// Opaque cursor returned as `next_cursor` by the previous page
+    pub cursor: Option<String>,
+    /// Number of records per page
+    pub limit: Option<u64>,
+}
+
+impl Pagination {
+    /* Requested page size, falling back to the default and capped at MAX_PAGE_SIZE */
+    pub fn limit(&self, default: u64) -> u64 {
+        self.limit.unwrap_or(default).clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+/* Struct representing the feed query parameters besides pagination */
+#[derive(Deserialize)]
+pub struct FeedView {
+    /// Which feed to list, the personalized home feed unless set
+    #[serde(default)]
+    pub mode: FeedMode,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeedMode {
+    /// Posts from followed profiles, the viewer's gym and nearby gyms
+    #[default]
+    Home,
+    /// Every post, newest first
+    Explore,
+}
+
+/* Struct representing one page of a cursor-paginated listing */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Paginated<T> {
+    /// Records of this page
+    pub data: Vec<T>,
+    /// Cursor of the next page, None on the last page
+    pub next_cursor: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -554,7 +1456,7 @@ pub struct PostGallery {
     /// Unique identifier for the gallery post
     pub id: Option<Thing>,
     /// Identifier linking this gallery post to the main feed content
-    pub content_id: Option<Thing>,
+    pub content_id: Option<PostId>,
     /// URL or path to the image associated with the post
     pub media_url: Option<String>,
     /// Timestamp indicating when the post was created
@@ -572,3 +1474,63 @@ pub struct PostGalleryForFeedDeserialize {
     pub id: String,
     pub link: String,
 }
+
+impl HasId for User {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Gym {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for GymSeeker {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Trainer {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Location {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Post {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Notification {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for Conversation {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for MembershipPlan {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
+
+impl HasId for ClassSession {
+    fn id(&self) -> Option<Thing> {
+        self.id.clone().map(Thing::from)
+    }
+}
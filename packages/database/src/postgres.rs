@@ -0,0 +1,544 @@
+use std::collections::HashMap;
+
+use super::interface;
+use crate::{
+    constraint,
+    db::{self, PostgresDb},
+    live::{Change, LiveStream},
+    query::{self, quote_identifier, Condition, Query},
+    transaction::{Operation, Transaction},
+};
+use axum::async_trait;
+use deadpool_postgres::Pool;
+use environment::Environment;
+use errors::{Error, Result};
+use futures::{channel::mpsc, stream, StreamExt};
+use interface::{DBInterface, HasId};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value as Json;
+use surrealdb::sql::{Id, Number, Thing, Value};
+use tokio_postgres::{types::ToSql, AsyncMessage, GenericClient, NoTls, Row};
+
+type Param = Box<dyn ToSql + Sync + Send>;
+
+/* Implementation of DBInterface for PostgresDb */
+#[async_trait]
+impl DBInterface for PostgresDb {
+    /* Method to insert a record into the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn insert_record<T, U>(&self, tb_name: String, data: T) -> Result<Option<U>>
+    where
+        T: Serialize + Sync + Send + 'static,
+        U: DeserializeOwned + Sync + Clone + 'static,
+    {
+        let client = self.pool()?.get().await?;
+        let id = Thing::from((tb_name.as_str(), Id::rand()));
+
+        let row = insert(&**client, &id, to_json(data)?).await?;
+        Ok(Some(from_row(row)?))
+    }
+
+    /* Method to insert many records with one INSERT statement */
+    #[tracing::instrument(err, skip_all)]
+    async fn insert_records<T, U>(&self, tb_name: String, data: Vec<T>) -> Result<Vec<U>>
+    where
+        T: Serialize + Sync + Send + 'static,
+        U: DeserializeOwned + Sync + Clone + 'static,
+    {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let records = data
+            .into_iter()
+            .map(|record| {
+                let id = Thing::from((tb_name.as_str(), Id::rand()));
+                Ok(with_id(&id, to_json(record)?))
+            })
+            .collect::<Result<Vec<Json>>>()?;
+
+        let client = self.pool()?.get().await?;
+        let table = quote_identifier(&tb_name);
+        let sql = format!(
+            "INSERT INTO {table} SELECT * FROM json_populate_recordset(NULL::{table}, $1) RETURNING row_to_json({table}.*)"
+        );
+
+        let rows = client
+            .query(&sql, &[&Json::Array(records)])
+            .await
+            .map_err(constraint::from_postgres)?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /* Method to select records from the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn select<T: DeserializeOwned + Sync>(&self, tb_name: String) -> Result<Vec<T>> {
+        let client = self.pool()?.get().await?;
+        let sql = format!(
+            "SELECT row_to_json(record) FROM {} AS record",
+            quote_identifier(&tb_name)
+        );
+
+        let rows = client.query(&sql, &[]).await?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /* Method to fetch records by primary key with a single `= ANY` lookup */
+    #[tracing::instrument(err, skip_all)]
+    async fn select_by_ids<T: DeserializeOwned + Sync + HasId>(
+        &self,
+        tb_name: String,
+        ids: Vec<Thing>,
+    ) -> Result<HashMap<String, T>> {
+        let ids = query::record_ids(&tb_name, ids)?;
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let client = self.pool()?.get().await?;
+        let sql = format!(
+            "SELECT row_to_json(record) FROM {} AS record WHERE id = ANY($1)",
+            quote_identifier(&tb_name)
+        );
+        let ids: Vec<String> = ids.iter().map(ToString::to_string).collect();
+
+        let rows = client.query(&sql, &[&ids]).await?;
+        let records = rows.into_iter().map(from_row).collect::<Result<Vec<T>>>()?;
+        Ok(interface::key_by_id(records))
+    }
+
+    /* Method to delete a record from the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn delete(&self, id: String) -> Result<bool> {
+        let client = self.pool()?.get().await?;
+        let record_id = query::thing(&id)?;
+        let sql = format!(
+            "DELETE FROM {} WHERE id = $1",
+            quote_identifier(&record_id.tb)
+        );
+
+        let result = client.execute(&sql, &[&record_id.to_string()]).await;
+
+        match result {
+            Ok(_) => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /* Method to update a record in the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn update_record<T>(&self, id: String, tb_name: String, data: T) -> Result<bool>
+    where
+        T: Serialize + for<'de> Deserialize<'de> + Sync + Send + 'static,
+    {
+        let client = self.pool()?.get().await?;
+        let record_id = query::record_id(&tb_name, &id)?;
+
+        // Every serialized field is written, matching the replace semantics of the SurrealDB backend
+        update(&**client, &tb_name, &record_id, to_json(data)?).await
+    }
+
+    /* Method to merge the provided fields into an existing record */
+    #[tracing::instrument(err, skip_all)]
+    async fn merge_record<T>(&self, id: String, tb_name: String, data: T) -> Result<bool>
+    where
+        T: Serialize + Sync + Send + 'static,
+    {
+        let client = self.pool()?.get().await?;
+        let record_id = query::record_id(&tb_name, &id)?;
+
+        // Fields skipped during serialization are absent from the JSON and keep their stored value
+        update(&**client, &record_id.tb, &record_id, to_json(data)?).await
+    }
+
+    /* Method to merge fields and bump the version, guarded by the version the client last read */
+    #[tracing::instrument(err, skip_all)]
+    async fn merge_versioned<T>(
+        &self,
+        id: String,
+        tb_name: String,
+        data: T,
+        version: Option<i64>,
+    ) -> Result<bool>
+    where
+        T: Serialize + Sync + Send + 'static,
+    {
+        let client = self.pool()?.get().await?;
+        let record_id = query::record_id(&tb_name, &id)?;
+        let mut data = to_json(data)?;
+        // The version is only ever bumped here, a client supplied one is never written
+        if let Json::Object(object) = &mut data {
+            object.remove("version");
+        }
+        let columns = columns(&mut data);
+
+        let table = quote_identifier(&record_id.tb);
+        let mut assignments: Vec<String> = columns
+            .iter()
+            .map(|column| format!("{} = data.{}", column, column))
+            .collect();
+        assignments.push(format!("version = {table}.version + 1"));
+        let sql = format!(
+            "UPDATE {table} SET {} FROM json_populate_record(NULL::{table}, $1) AS data \
+             WHERE {table}.id = $2 AND ($3::BIGINT IS NULL OR {table}.version = $3)",
+            assignments.join(", ")
+        );
+
+        let updated = client
+            .execute(&sql, &[&data, &record_id.to_string(), &version])
+            .await
+            .map_err(constraint::from_postgres)?;
+        if updated > 0 {
+            return Ok(true);
+        }
+
+        // Nothing matched, tell a missing record apart from a stale version
+        let sql = format!("SELECT 1 FROM {table} WHERE id = $1");
+        let existing = client.query(&sql, &[&record_id.to_string()]).await?;
+        match existing.is_empty() {
+            true => Ok(false),
+            false => Err(Error::Conflict(id)),
+        }
+    }
+
+    /* Method to select records with parameters from the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn select_where<T: DeserializeOwned + Sync>(&self, query: Query) -> Result<Vec<T>> {
+        let client = self.pool()?.get().await?;
+        let (sql, values) = query.to_postgres();
+        let sql = format!("SELECT row_to_json(record) FROM ({}) AS record", sql);
+
+        let params: Vec<Param> = values.into_iter().map(to_param).collect();
+        let params: Vec<&(dyn ToSql + Sync)> = params
+            .iter()
+            .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+            .collect();
+
+        let rows = client.query(&sql, &params).await?;
+        rows.into_iter().map(from_row).collect()
+    }
+
+    /* Method to commit a transaction inside a single PostgreSQL transaction */
+    #[tracing::instrument(err, skip_all)]
+    async fn commit(&self, transaction: Transaction) -> Result<()> {
+        if transaction.is_empty() {
+            return Ok(());
+        }
+
+        let mut client = self.pool()?.get().await?;
+        let pg_transaction = client.transaction().await?;
+
+        for operation in transaction.operations() {
+            match operation {
+                Operation::Create { id, data } => {
+                    insert(&*pg_transaction, id, data.clone().into_json()).await?;
+                }
+                Operation::Merge { id, data } => {
+                    update(&*pg_transaction, &id.tb, id, data.clone().into_json()).await?;
+                }
+                Operation::DeleteWhere { table, condition } => {
+                    delete_where(&*pg_transaction, table, condition).await?;
+                }
+                Operation::Delete { id } => {
+                    let sql = format!("DELETE FROM {} WHERE id = $1", quote_identifier(&id.tb));
+                    if pg_transaction.execute(&sql, &[&id.to_string()]).await? == 0 {
+                        return Err(Error::DataNotAvaliable(id.to_string()));
+                    }
+                }
+                Operation::Increment {
+                    id,
+                    field,
+                    by,
+                    at_most,
+                } => {
+                    if !increment(&*pg_transaction, id, field, *by, *at_most).await? {
+                        return Err(Error::Conflict(id.to_string()));
+                    }
+                }
+            }
+        }
+
+        // Dropping the transaction on an early return rolls every staged write back
+        pg_transaction.commit().await?;
+        Ok(())
+    }
+
+    /* Method to subscribe to a table with LISTEN, fed by the notify_change trigger of its migration */
+    #[tracing::instrument(err, skip_all)]
+    async fn live<T: DeserializeOwned + HasId + Send + Unpin + 'static>(
+        &self,
+        tb_name: String,
+    ) -> Result<LiveStream<T>> {
+        let pool = self.pool()?;
+        let config = db::config(&Environment::new())
+            .get_pg_config()
+            .map_err(|error| Error::DatabaseError(error.to_string()))?;
+        // Pooled connections are handed to other requests, a listener needs one of its own
+        let (listener, mut connection) = config.connect(NoTls).await?;
+
+        let (sender, receiver) = mpsc::unbounded();
+        tokio::spawn(async move {
+            let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+            while let Some(message) = messages.next().await {
+                match message {
+                    Ok(AsyncMessage::Notification(notification)) => {
+                        let payload = notification.payload().to_string();
+                        if sender.unbounded_send(payload).is_err() {
+                            break;
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(error) => {
+                        tracing::warn!("Live query connection failed: {}", error);
+                        break;
+                    }
+                }
+            }
+        });
+
+        let channel = quote_identifier(&format!("live_{}", tb_name));
+        listener
+            .batch_execute(&format!("LISTEN {}", channel))
+            .await?;
+
+        // The listener is kept in the state so the connection lives as long as the stream
+        let changes = stream::unfold(
+            (receiver, listener, pool),
+            |(mut receiver, listener, pool)| async move {
+                loop {
+                    let payload = receiver.next().await?;
+                    match change(&pool, &payload).await {
+                        Ok(Some(change)) => return Some((Ok(change), (receiver, listener, pool))),
+                        Ok(None) => continue,
+                        Err(error) => return Some((Err(error), (receiver, listener, pool))),
+                    }
+                }
+            },
+        );
+
+        Ok(Box::pin(changes))
+    }
+}
+
+/* Payload of a notify_change notification, the row itself is read back by id */
+#[derive(Debug, Deserialize, PartialEq)]
+struct ChangeNotice {
+    action: String,
+    id: String,
+}
+
+/* Implementation of ChangeNotice */
+impl ChangeNotice {
+    fn parse(payload: &str) -> Result<Self> {
+        serde_json::from_str(payload).map_err(|error| Error::DatabaseError(error.to_string()))
+    }
+}
+
+/* Turn a notification into the change it announces, None when the record is already gone */
+async fn change<T: DeserializeOwned>(pool: &Pool, payload: &str) -> Result<Option<Change<T>>> {
+    let notice = ChangeNotice::parse(payload)?;
+    let id = query::thing(&notice.id)?;
+    if notice.action == "DELETE" {
+        return Ok(Some(Change::Delete(id)));
+    }
+
+    let client = pool.get().await?;
+    let sql = format!(
+        "SELECT row_to_json(record) FROM {} AS record WHERE id = $1",
+        quote_identifier(&id.tb)
+    );
+    let Some(row) = client.query_opt(&sql, &[&notice.id]).await? else {
+        return Ok(None);
+    };
+
+    let record = from_row(row)?;
+    match notice.action.as_str() {
+        "INSERT" => Ok(Some(Change::Create(record))),
+        _ => Ok(Some(Change::Update(record))),
+    }
+}
+
+/* Insert a JSON document under the given record id and return the stored row */
+async fn insert<C: GenericClient>(client: &C, id: &Thing, data: Json) -> Result<Row> {
+    let data = with_id(id, data);
+    let table = quote_identifier(&id.tb);
+    let sql = format!(
+        "INSERT INTO {table} SELECT * FROM json_populate_record(NULL::{table}, $1) RETURNING row_to_json({table}.*)"
+    );
+
+    client
+        .query_one(&sql, &[&data])
+        .await
+        .map_err(constraint::from_postgres)
+}
+
+/* Delete every row of the table matching the condition, returns how many were removed */
+async fn delete_where<C: GenericClient>(
+    client: &C,
+    tb_name: &str,
+    condition: &Condition,
+) -> Result<u64> {
+    let (predicate, values) = condition.to_postgres();
+    let sql = format!(
+        "DELETE FROM {} WHERE {}",
+        quote_identifier(tb_name),
+        predicate
+    );
+
+    let params: Vec<Param> = values.into_iter().map(to_param).collect();
+    let params: Vec<&(dyn ToSql + Sync)> = params
+        .iter()
+        .map(|param| param.as_ref() as &(dyn ToSql + Sync))
+        .collect();
+
+    client
+        .execute(&sql, &params)
+        .await
+        .map_err(constraint::from_postgres)
+}
+
+/* Add `by` to a counter and bump the version, false when the record is missing or would pass `at_most` */
+async fn increment<C: GenericClient>(
+    client: &C,
+    id: &Thing,
+    field: &str,
+    by: i64,
+    at_most: Option<&str>,
+) -> Result<bool> {
+    let field = quote_identifier(field);
+    // The row lock taken by UPDATE makes concurrent increments re-check the guard against each other
+    let guard = at_most
+        .map(|limit| format!(" AND {} + $1 <= {}", field, quote_identifier(limit)))
+        .unwrap_or_default();
+    let sql = format!(
+        "UPDATE {} SET {field} = {field} + $1, version = version + 1 WHERE id = $2{guard}",
+        quote_identifier(&id.tb)
+    );
+
+    let updated = client
+        .execute(&sql, &[&by, &id.to_string()])
+        .await
+        .map_err(constraint::from_postgres)?;
+    Ok(updated > 0)
+}
+
+/* Set the primary key of a JSON document about to be inserted */
+fn with_id(id: &Thing, mut data: Json) -> Json {
+    if let Json::Object(object) = &mut data {
+        object.insert("id".to_string(), Json::String(id.to_string()));
+    }
+    data
+}
+
+/* Write every column present in the JSON document to the record, returns false when no row matched */
+async fn update<C: GenericClient>(
+    client: &C,
+    tb_name: &str,
+    record_id: &Thing,
+    mut data: Json,
+) -> Result<bool> {
+    let columns = columns(&mut data);
+    if columns.is_empty() {
+        return Ok(false);
+    }
+
+    let table = quote_identifier(tb_name);
+    let assignments: Vec<String> = columns
+        .iter()
+        .map(|column| format!("{} = data.{}", column, column))
+        .collect();
+    let sql = format!(
+        "UPDATE {table} SET {} FROM json_populate_record(NULL::{table}, $1) AS data WHERE {table}.id = $2",
+        assignments.join(", ")
+    );
+
+    let updated = client
+        .execute(&sql, &[&data, &record_id.to_string()])
+        .await
+        .map_err(constraint::from_postgres)?;
+    Ok(updated > 0)
+}
+
+/* Quoted columns written by an update, the id is never part of them */
+fn columns(data: &mut Json) -> Vec<String> {
+    match data {
+        Json::Object(object) => {
+            object.remove("id");
+            object
+                .keys()
+                .map(|column| quote_identifier(column))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+/* Serialize a model the way SurrealDB stores it, record links become `table:id` strings */
+fn to_json<T: Serialize + 'static>(data: T) -> Result<Json> {
+    let value =
+        surrealdb::sql::to_value(data).map_err(|error| Error::DatabaseError(error.to_string()))?;
+    Ok(value.into_json())
+}
+
+/* Deserialize a `row_to_json` row, turning `id` and `*_id` columns back into record links */
+fn from_row<T: DeserializeOwned>(row: Row) -> Result<T> {
+    let mut record: Json = row.try_get(0)?;
+
+    if let Json::Object(object) = &mut record {
+        for (column, value) in object.iter_mut() {
+            if column != "id" && !column.ends_with("_id") {
+                continue;
+            }
+            if let Some(thing) = value.as_str().and_then(|raw| query::thing(raw).ok()) {
+                *value = serde_json::to_value(thing)
+                    .map_err(|error| Error::DatabaseError(error.to_string()))?;
+            }
+        }
+    }
+
+    serde_json::from_value(record).map_err(|error| Error::DatabaseError(error.to_string()))
+}
+
+/* Convert a bound query value into a PostgreSQL parameter */
+fn to_param(value: Value) -> Param {
+    match value {
+        Value::Bool(boolean) => Box::new(boolean),
+        Value::Number(Number::Int(int)) => Box::new(int),
+        Value::Number(Number::Float(float)) => Box::new(float),
+        Value::Datetime(datetime) => Box::new(datetime.0),
+        Value::Array(array) => Box::new(
+            array
+                .into_iter()
+                .map(|value| match value {
+                    Value::Strand(strand) => strand.0,
+                    other => other.to_string(),
+                })
+                .collect::<Vec<String>>(),
+        ),
+        Value::Strand(strand) => Box::new(strand.0),
+        Value::None | Value::Null => Box::new(None::<String>),
+        other => Box::new(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn change_notices_carry_the_operation_and_record_id() {
+        let notice = ChangeNotice::parse(r#"{"action": "UPDATE", "id": "post:abc"}"#).unwrap();
+        assert_eq!(
+            notice,
+            ChangeNotice {
+                action: String::from("UPDATE"),
+                id: String::from("post:abc"),
+            }
+        );
+
+        assert!(matches!(
+            ChangeNotice::parse(r#"{"action": "INSERT"}"#),
+            Err(Error::DatabaseError(_))
+        ));
+    }
+}
@@ -0,0 +1,708 @@
+use std::collections::BTreeMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::{DateTime, Utc};
+use errors::Result;
+use surrealdb::sql::{Datetime, Thing, Value};
+
+/* Comparison operators supported by the query builder */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operator {
+    Eq,
+    NotEq,
+    Gt,
+    Gte,
+    Lt,
+    Lte,
+    In,
+}
+
+impl Operator {
+    fn as_sql(&self) -> &'static str {
+        match self {
+            Operator::Eq => "=",
+            Operator::NotEq => "!=",
+            Operator::Gt => ">",
+            Operator::Gte => ">=",
+            Operator::Lt => "<",
+            Operator::Lte => "<=",
+            Operator::In => "IN",
+        }
+    }
+}
+
+/* A single condition of a WHERE clause, values are always sent as bound parameters */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Condition {
+    Compare {
+        field: &'static str,
+        operator: Operator,
+        value: Value,
+    },
+    IsNone(&'static str),
+    And(Vec<Condition>),
+    Or(Vec<Condition>),
+}
+
+impl Condition {
+    pub fn compare(field: &'static str, operator: Operator, value: impl Into<Value>) -> Self {
+        Condition::Compare {
+            field,
+            operator,
+            value: value.into(),
+        }
+    }
+
+    pub fn eq(field: &'static str, value: impl Into<Value>) -> Self {
+        Self::compare(field, Operator::Eq, value)
+    }
+
+    /* Matches records where the field is not set */
+    pub fn is_none(field: &'static str) -> Self {
+        Condition::IsNone(field)
+    }
+
+    /* Render as a SurrealQL predicate, appending its values to parameters shared with other statements */
+    pub(crate) fn to_surrealql(&self, params: &mut Vec<Value>) -> String {
+        self.render(Dialect::SurrealQl, params)
+    }
+
+    /* Render as a PostgreSQL predicate, parameters are returned in placeholder order */
+    pub(crate) fn to_postgres(&self) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        let predicate = self.render(Dialect::Postgres, &mut params);
+        (predicate, params)
+    }
+
+    fn render(&self, dialect: Dialect, params: &mut Vec<Value>) -> String {
+        match self {
+            Condition::Compare {
+                field,
+                operator,
+                value,
+            } => {
+                params.push(value.clone());
+                dialect.compare(field, operator, params.len())
+            }
+            Condition::IsNone(field) => dialect.is_none(field),
+            Condition::And(conditions) => Self::join(conditions, " AND ", dialect, params),
+            Condition::Or(conditions) => Self::join(conditions, " OR ", dialect, params),
+        }
+    }
+
+    fn join(
+        conditions: &[Condition],
+        separator: &str,
+        dialect: Dialect,
+        params: &mut Vec<Value>,
+    ) -> String {
+        let rendered: Vec<String> = conditions
+            .iter()
+            .map(|condition| condition.render(dialect, params))
+            .collect();
+        format!("({})", rendered.join(separator))
+    }
+}
+
+/* Query languages a Query can be rendered to */
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Dialect {
+    SurrealQl,
+    Postgres,
+}
+
+impl Dialect {
+    /* Render a comparison against the parameter at the given 1-based position */
+    fn compare(&self, field: &str, operator: &Operator, position: usize) -> String {
+        match (self, operator) {
+            (Dialect::SurrealQl, _) => {
+                format!("{} {} $p{}", field, operator.as_sql(), position - 1)
+            }
+            (Dialect::Postgres, Operator::In) => {
+                format!("{} = ANY(${})", quote_identifier(field), position)
+            }
+            (Dialect::Postgres, _) => {
+                format!(
+                    "{} {} ${}",
+                    quote_identifier(field),
+                    operator.as_sql(),
+                    position
+                )
+            }
+        }
+    }
+}
+
+impl Dialect {
+    /* Field name as written in this dialect */
+    fn identifier(&self, field: &str) -> String {
+        match self {
+            Dialect::SurrealQl => field.to_string(),
+            Dialect::Postgres => quote_identifier(field),
+        }
+    }
+
+    /* Render a check that the field holds no value */
+    fn is_none(&self, field: &str) -> String {
+        match self {
+            Dialect::SurrealQl => format!("{} = NONE", field),
+            Dialect::Postgres => format!("{} IS NULL", quote_identifier(field)),
+        }
+    }
+}
+
+/* Sort direction for ORDER BY */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Direction {
+    Asc,
+    Desc,
+}
+
+/* Typed SELECT query executed by DBInterface::select_where */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Query {
+    /// Table to select from
+    table: &'static str,
+    /// Projection, defaults to every field
+    columns: &'static str,
+    /// Conditions joined with AND
+    conditions: Vec<Condition>,
+    /// Ordering applied to the result set
+    order_by: Vec<(&'static str, Direction)>,
+    /// Maximum number of records returned
+    limit: Option<u64>,
+    /// Number of records skipped
+    start: Option<u64>,
+    /// Count the matching records instead of returning them
+    count: bool,
+    /// Fields the count is broken down by, one row per distinct combination
+    group_by: Vec<&'static str>,
+}
+
+impl Query {
+    pub fn select(table: &'static str) -> Self {
+        Query {
+            table,
+            columns: "*",
+            conditions: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            start: None,
+            count: false,
+            group_by: Vec::new(),
+        }
+    }
+
+    /* Projection is trusted input and must never contain request data */
+    pub fn columns(mut self, columns: &'static str) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    pub fn eq(self, field: &'static str, value: impl Into<Value>) -> Self {
+        self.filter(Condition::eq(field, value))
+    }
+
+    pub fn order_by(mut self, field: &'static str, direction: Direction) -> Self {
+        self.order_by.push((field, direction));
+        self
+    }
+
+    /* Return a single `Count` row of the matching records instead of the records */
+    pub fn count(mut self) -> Self {
+        self.count = true;
+        self
+    }
+
+    /* Count per distinct value of the field, each row carries the field next to its `count` */
+    pub fn count_by(mut self, field: &'static str) -> Self {
+        self.count = true;
+        self.group_by.push(field);
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn start(mut self, start: u64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    /* Newest-first keyset page: records strictly older than the cursor by `updated_at`, then `id` */
+    pub fn page(self, cursor: Option<&Cursor>, limit: u64) -> Self {
+        self.page_by("updated_at", cursor, limit)
+    }
+
+    /* Newest-first keyset page on any timestamp field, ties broken by `id` */
+    pub fn page_by(self, field: &'static str, cursor: Option<&Cursor>, limit: u64) -> Self {
+        let query = self
+            .order_by(field, Direction::Desc)
+            .order_by("id", Direction::Desc)
+            .limit(limit);
+
+        match cursor {
+            Some(cursor) => query.filter(Condition::Or(vec![
+                Condition::compare(field, Operator::Lt, cursor.updated_at.clone()),
+                Condition::And(vec![
+                    Condition::eq(field, cursor.updated_at.clone()),
+                    Condition::compare("id", Operator::Lt, cursor.id.clone()),
+                ]),
+            ])),
+            None => query,
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+
+    /* Render the query as SurrealQL together with its bound parameters */
+    pub fn to_surrealql(&self) -> (String, BTreeMap<String, Value>) {
+        let mut params = Vec::new();
+        let columns = match self.count {
+            true => self.counted_columns(Dialect::SurrealQl),
+            false => self.columns.to_string(),
+        };
+        let mut sql = format!("SELECT {} FROM type::table($table)", columns);
+        sql.push_str(&self.render_clauses(Dialect::SurrealQl, &mut params));
+
+        let mut bindings: BTreeMap<String, Value> = params
+            .into_iter()
+            .enumerate()
+            .map(|(index, value)| (format!("p{}", index), value))
+            .collect();
+        bindings.insert("table".to_string(), Value::from(self.table));
+
+        if let Some(limit) = self.limit {
+            bindings.insert("limit".to_string(), Value::from(limit));
+            sql.push_str(" LIMIT $limit");
+        }
+
+        if let Some(start) = self.start {
+            bindings.insert("start".to_string(), Value::from(start));
+            sql.push_str(" START $start");
+        }
+
+        (sql, bindings)
+    }
+
+    /* Render the query as PostgreSQL, parameters are returned in placeholder order */
+    pub fn to_postgres(&self) -> (String, Vec<Value>) {
+        let mut params = Vec::new();
+        let columns = match self.count {
+            true => self.counted_columns(Dialect::Postgres),
+            false => self.columns.to_string(),
+        };
+        let mut sql = format!("SELECT {} FROM {}", columns, quote_identifier(self.table));
+        sql.push_str(&self.render_clauses(Dialect::Postgres, &mut params));
+
+        if let Some(limit) = self.limit {
+            params.push(Value::from(limit));
+            sql.push_str(&format!(" LIMIT ${}", params.len()));
+        }
+
+        if let Some(start) = self.start {
+            params.push(Value::from(start));
+            sql.push_str(&format!(" OFFSET ${}", params.len()));
+        }
+
+        (sql, params)
+    }
+
+    /* WHERE and ORDER BY are shared by every dialect */
+    fn render_clauses(&self, dialect: Dialect, params: &mut Vec<Value>) -> String {
+        let mut clauses = String::new();
+
+        if !self.conditions.is_empty() {
+            let conditions: Vec<String> = self
+                .conditions
+                .iter()
+                .map(|condition| condition.render(dialect, params))
+                .collect();
+            clauses.push_str(&format!(" WHERE {}", conditions.join(" AND ")));
+        }
+
+        if !self.group_by.is_empty() {
+            let fields: Vec<String> = self
+                .group_by
+                .iter()
+                .map(|field| dialect.identifier(field))
+                .collect();
+            clauses.push_str(&format!(" GROUP BY {}", fields.join(", ")));
+        } else if self.count && dialect == Dialect::SurrealQl {
+            // SurrealDB only aggregates over the whole result set when asked to
+            clauses.push_str(" GROUP ALL");
+        }
+
+        if !self.order_by.is_empty() {
+            let order_by: Vec<String> = self
+                .order_by
+                .iter()
+                .map(|(field, direction)| {
+                    let field = dialect.identifier(field);
+                    match direction {
+                        Direction::Asc => format!("{} ASC", field),
+                        Direction::Desc => format!("{} DESC", field),
+                    }
+                })
+                .collect();
+            clauses.push_str(&format!(" ORDER BY {}", order_by.join(", ")));
+        }
+
+        clauses
+    }
+
+    /* Grouped fields followed by the count */
+    fn counted_columns(&self, dialect: Dialect) -> String {
+        let mut columns: Vec<String> = self
+            .group_by
+            .iter()
+            .map(|field| dialect.identifier(field))
+            .collect();
+        columns.push(match dialect {
+            Dialect::SurrealQl => String::from("count() AS count"),
+            Dialect::Postgres => String::from("COUNT(*) AS count"),
+        });
+        columns.join(", ")
+    }
+}
+
+/* Position of the last record of a page, handed to clients as an opaque string */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cursor {
+    /// Timestamp the page is sorted by, `updated_at` unless paged by another field
+    pub updated_at: Datetime,
+    pub id: Thing,
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(format!("{}|{}", self.updated_at.0.to_rfc3339(), self.id))
+    }
+
+    pub fn decode(cursor: &str) -> Result<Self> {
+        let invalid = || errors::Error::InvalidCursor(cursor.to_string());
+
+        let raw = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let raw = String::from_utf8(raw).map_err(|_| invalid())?;
+        let (updated_at, id) = raw.split_once('|').ok_or_else(invalid)?;
+
+        let updated_at = DateTime::parse_from_rfc3339(updated_at).map_err(|_| invalid())?;
+        Ok(Cursor {
+            updated_at: Datetime::from(updated_at.with_timezone(&Utc)),
+            id: surrealdb::sql::thing(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+/* Quote a table or column name for PostgreSQL, `user` is a reserved word there */
+pub fn quote_identifier(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/* Parse a `table:id` string coming from a request or token into a record id */
+pub fn thing(id: &str) -> Result<Thing> {
+    surrealdb::sql::thing(id).map_err(|_| errors::Error::InvalidRecordId(id.to_string()))
+}
+
+/* Parse the id of a record that must belong to `tb_name`, failing on an id from another table */
+pub fn record_id(tb_name: &str, id: &str) -> Result<Thing> {
+    let record_id = thing(id)?;
+    if record_id.tb != tb_name {
+        return Err(errors::Error::InvalidRecordId(id.to_string()));
+    }
+
+    Ok(record_id)
+}
+
+/* Sorted and deduplicated ids of a batch lookup, failing on an id from another table */
+pub fn record_ids(tb_name: &str, mut ids: Vec<Thing>) -> Result<Vec<Thing>> {
+    if let Some(foreign) = ids.iter().find(|id| id.tb != tb_name) {
+        return Err(errors::Error::InvalidRecordId(foreign.to_string()));
+    }
+
+    ids.sort();
+    ids.dedup();
+    Ok(ids)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INJECTION: &str = "a@b.c' OR 1=1; DELETE user; --";
+
+    #[test]
+    fn surrealql_binds_request_values() {
+        let (sql, bindings) = Query::select("user")
+            .eq("email", INJECTION)
+            .eq("verified", true)
+            .to_surrealql();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) WHERE email = $p0 AND verified = $p1"
+        );
+        assert!(!sql.contains(INJECTION));
+        assert_eq!(bindings.get("p0"), Some(&Value::from(INJECTION)));
+        assert_eq!(bindings.get("p1"), Some(&Value::from(true)));
+        assert_eq!(bindings.get("table"), Some(&Value::from("user")));
+    }
+
+    #[test]
+    fn surrealql_binds_nested_conditions_limit_and_start() {
+        let (sql, bindings) = Query::select("post")
+            .filter(Condition::Or(vec![
+                Condition::eq("content", INJECTION),
+                Condition::And(vec![
+                    Condition::compare("is_gym", Operator::NotEq, false),
+                    Condition::compare("content", Operator::In, vec![INJECTION]),
+                ]),
+            ]))
+            .order_by("updated_at", Direction::Desc)
+            .limit(10)
+            .start(20)
+            .to_surrealql();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) WHERE (content = $p0 OR (is_gym != $p1 AND content IN $p2)) ORDER BY updated_at DESC LIMIT $limit START $start"
+        );
+        assert!(!sql.contains(INJECTION));
+        assert_eq!(bindings.get("p0"), Some(&Value::from(INJECTION)));
+        assert_eq!(bindings.get("limit"), Some(&Value::from(10u64)));
+        assert_eq!(bindings.get("start"), Some(&Value::from(20u64)));
+    }
+
+    #[test]
+    fn postgres_binds_request_values_in_placeholder_order() {
+        let (sql, params) = Query::select("user")
+            .eq("email", INJECTION)
+            .filter(Condition::compare("username", Operator::In, vec!["a", "b"]))
+            .limit(5)
+            .start(10)
+            .to_postgres();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"user\" WHERE \"email\" = $1 AND \"username\" = ANY($2) LIMIT $3 OFFSET $4"
+        );
+        assert!(!sql.contains(INJECTION));
+        assert_eq!(
+            params,
+            vec![
+                Value::from(INJECTION),
+                Value::from(vec!["a", "b"]),
+                Value::from(5u64),
+                Value::from(10u64),
+            ]
+        );
+    }
+
+    #[test]
+    fn is_none_takes_no_parameter() {
+        let query = Query::select("post")
+            .filter(Condition::is_none("deleted_at"))
+            .eq("user_id", "user:a");
+
+        let (sql, bindings) = query.to_surrealql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) WHERE deleted_at = NONE AND user_id = $p0"
+        );
+        assert_eq!(bindings.get("p0"), Some(&Value::from("user:a")));
+
+        let (sql, params) = query.to_postgres();
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"post\" WHERE \"deleted_at\" IS NULL AND \"user_id\" = $1"
+        );
+        assert_eq!(params, vec![Value::from("user:a")]);
+    }
+
+    fn cursor() -> Cursor {
+        Cursor {
+            updated_at: Datetime::from(
+                DateTime::parse_from_rfc3339("2024-05-01T10:20:30.123456Z")
+                    .unwrap()
+                    .with_timezone(&Utc),
+            ),
+            id: Thing::from(("post", "abc")),
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        let encoded = cursor().encode();
+        assert!(!encoded.contains('|'));
+        assert_eq!(Cursor::decode(&encoded).unwrap(), cursor());
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let malformed = [
+            String::from("not base64!"),
+            URL_SAFE_NO_PAD.encode("no separator"),
+            URL_SAFE_NO_PAD.encode("yesterday|post:abc"),
+            URL_SAFE_NO_PAD.encode("2024-05-01T10:20:30Z|not an id"),
+            URL_SAFE_NO_PAD.encode([0xff, 0xfe]),
+        ];
+
+        for cursor in malformed {
+            assert!(
+                matches!(
+                    Cursor::decode(&cursor),
+                    Err(errors::Error::InvalidCursor(_))
+                ),
+                "{} was accepted",
+                cursor
+            );
+        }
+    }
+
+    #[test]
+    fn first_page_is_ordered_newest_first() {
+        let (sql, bindings) = Query::select("post").page(None, 6).to_surrealql();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) ORDER BY updated_at DESC, id DESC LIMIT $limit"
+        );
+        assert_eq!(bindings.get("limit"), Some(&Value::from(6u64)));
+    }
+
+    #[test]
+    fn next_page_continues_after_the_cursor() {
+        let cursor = cursor();
+        let query = Query::select("post")
+            .eq("user_id", Thing::from(("user", "u")))
+            .page(Some(&cursor), 6);
+
+        let (sql, bindings) = query.to_surrealql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) WHERE user_id = $p0 AND (updated_at < $p1 OR (updated_at = $p2 AND id < $p3)) ORDER BY updated_at DESC, id DESC LIMIT $limit"
+        );
+        assert_eq!(
+            bindings.get("p1"),
+            Some(&Value::from(cursor.updated_at.clone()))
+        );
+        assert_eq!(bindings.get("p3"), Some(&Value::from(cursor.id.clone())));
+
+        let (sql, params) = query.to_postgres();
+        assert_eq!(
+            sql,
+            "SELECT * FROM \"post\" WHERE \"user_id\" = $1 AND (\"updated_at\" < $2 OR (\"updated_at\" = $3 AND \"id\" < $4)) ORDER BY \"updated_at\" DESC, \"id\" DESC LIMIT $5"
+        );
+        assert_eq!(params.len(), 5);
+    }
+
+    #[test]
+    fn pages_can_be_sorted_by_another_timestamp() {
+        let query = Query::select("follow").page_by("created_at", Some(&cursor()), 3);
+
+        let (sql, _) = query.to_surrealql();
+        assert_eq!(
+            sql,
+            "SELECT * FROM type::table($table) WHERE (created_at < $p0 OR (created_at = $p1 AND id < $p2)) ORDER BY created_at DESC, id DESC LIMIT $limit"
+        );
+    }
+
+    #[test]
+    fn counts_aggregate_the_matching_records() {
+        let query = Query::select("follow")
+            .eq("followee_id", Thing::from(("gym", "g")))
+            .count();
+
+        let (sql, bindings) = query.to_surrealql();
+        assert_eq!(
+            sql,
+            "SELECT count() AS count FROM type::table($table) WHERE followee_id = $p0 GROUP ALL"
+        );
+        assert_eq!(bindings.len(), 2);
+
+        let (sql, params) = query.to_postgres();
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) AS count FROM \"follow\" WHERE \"followee_id\" = $1"
+        );
+        assert_eq!(params.len(), 1);
+    }
+
+    #[test]
+    fn grouped_counts_return_one_row_per_value() {
+        let query = Query::select("reaction")
+            .filter(Condition::compare(
+                "post_id",
+                Operator::In,
+                vec![Value::from(Thing::from(("post", "p")))],
+            ))
+            .count_by("post_id")
+            .count_by("kind");
+
+        let (sql, _) = query.to_surrealql();
+        assert_eq!(
+            sql,
+            "SELECT post_id, kind, count() AS count FROM type::table($table) WHERE post_id IN $p0 GROUP BY post_id, kind"
+        );
+
+        let (sql, _) = query.to_postgres();
+        assert_eq!(
+            sql,
+            "SELECT \"post_id\", \"kind\", COUNT(*) AS count FROM \"reaction\" WHERE \"post_id\" = ANY($1) GROUP BY \"post_id\", \"kind\""
+        );
+    }
+
+    #[test]
+    fn quote_identifier_escapes_quotes() {
+        assert_eq!(quote_identifier("user"), "\"user\"");
+        assert_eq!(quote_identifier("a\"b"), "\"a\"\"b\"");
+    }
+
+    #[test]
+    fn thing_rejects_malformed_ids() {
+        assert_eq!(thing("gym:abc").unwrap(), Thing::from(("gym", "abc")));
+        assert!(matches!(
+            thing("not an id"),
+            Err(errors::Error::InvalidRecordId(_))
+        ));
+    }
+
+    #[test]
+    fn record_id_stays_in_its_table() {
+        assert_eq!(
+            record_id("gym", "gym:abc").unwrap(),
+            Thing::from(("gym", "abc"))
+        );
+        assert!(matches!(
+            record_id("gym", "user:abc"),
+            Err(errors::Error::InvalidRecordId(id)) if id == "user:abc"
+        ));
+    }
+
+    #[test]
+    fn record_ids_dedup_and_stay_in_their_table() {
+        let ids = vec![
+            Thing::from(("gym", "b")),
+            Thing::from(("gym", "a")),
+            Thing::from(("gym", "b")),
+        ];
+        assert_eq!(
+            record_ids("gym", ids).unwrap(),
+            vec![Thing::from(("gym", "a")), Thing::from(("gym", "b"))]
+        );
+
+        assert!(matches!(
+            record_ids("gym", vec![Thing::from(("user", "a"))]),
+            Err(errors::Error::InvalidRecordId(_))
+        ));
+    }
+}
@@ -1,9 +1,25 @@
+use std::collections::HashMap;
+
 use super::interface;
-use crate::db::SurrealDb;
+use crate::{
+    constraint,
+    db::SurrealDb,
+    live::{Change, LiveStream},
+    query::{self, Query},
+    transaction::{self, Transaction},
+};
 use axum::async_trait;
-use errors::Result;
-use interface::DBInterface;
+use errors::{Error, Result};
+use futures::StreamExt;
+use interface::{DBInterface, HasId};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surrealdb::{
+    error::{Api, Db},
+    sql::{Object, Thing, Value},
+    Action,
+};
+
+use crate::model::Id;
 
 /* Implementation of DBInterface for SurrealDb */
 #[async_trait]
@@ -15,27 +31,78 @@ impl DBInterface for SurrealDb {
         T: Serialize + Sync + Send + 'static,
         U: DeserializeOwned + Sync + Clone + 'static,
     {
-        let client = self.client.clone().unwrap();
-        let created: Vec<U> = client.insert(tb_name).content(data).await?;
+        let client = self.client()?;
+        let created: Vec<U> = client
+            .insert(tb_name)
+            .content(data)
+            .await
+            .map_err(constraint::from_surrealdb)?;
         let record = created.first().cloned();
         Ok(record)
     }
 
+    /* Method to insert many records with one INSERT statement */
+    #[tracing::instrument(err, skip_all)]
+    async fn insert_records<T, U>(&self, tb_name: String, data: Vec<T>) -> Result<Vec<U>>
+    where
+        T: Serialize + Sync + Send + 'static,
+        U: DeserializeOwned + Sync + Clone + 'static,
+    {
+        if data.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let client = self.client()?;
+        let created: Vec<U> = client
+            .insert(tb_name)
+            .content(data)
+            .await
+            .map_err(constraint::from_surrealdb)?;
+        Ok(created)
+    }
+
     /* Method to select records from the database */
 
     #[tracing::instrument(err, skip_all)]
     async fn select<T: DeserializeOwned + Sync>(&self, tb_name: String) -> Result<Vec<T>> {
-        let client = self.client.clone().unwrap();
+        let client = self.client()?;
         let data: Vec<T> = client.select(tb_name).await?;
         Ok(data)
     }
 
+    /* Method to fetch records straight by their ids, without scanning the table */
+    #[tracing::instrument(err, skip_all)]
+    async fn select_by_ids<T: DeserializeOwned + Sync + HasId>(
+        &self,
+        tb_name: String,
+        ids: Vec<Thing>,
+    ) -> Result<HashMap<String, T>> {
+        let ids = query::record_ids(&tb_name, ids)?;
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let client = self.client()?;
+        let ids: Vec<Value> = ids.into_iter().map(Value::from).collect();
+        let mut results = client
+            .query("SELECT * FROM $ids")
+            .bind(("ids", Value::from(ids)))
+            .await?;
+        let records: Vec<T> = results.take(0)?;
+        Ok(interface::key_by_id(records))
+    }
+
     /* Method to delete a record from the database */
 
     #[tracing::instrument(err, skip_all)]
     async fn delete(&self, id: String) -> Result<bool> {
-        let client = self.client.clone().unwrap();
-        let result = client.query(format!("DELETE {}", id)).await?.check();
+        let client = self.client()?;
+        let record_id = query::thing(&id)?;
+        let result = client
+            .query("DELETE $record_id")
+            .bind(("record_id", record_id))
+            .await?
+            .check();
 
         match result {
             Ok(_) => Ok(true),
@@ -49,38 +116,268 @@ impl DBInterface for SurrealDb {
     where
         T: Serialize + for<'de> Deserialize<'de> + Sync + Send + 'static,
     {
-        let data_id: Vec<&str> = id.split(':').collect();
-        let client = self.client.clone().unwrap();
-        let updated_result: Option<T> = client.update((tb_name, data_id[1])).content(data).await?;
-        Ok(updated_result.is_some())
+        let client = self.client()?;
+        let record_id = query::record_id(&tb_name, &id)?;
+
+        let mut response = client
+            .query("UPDATE $record_id CONTENT $data RETURN id")
+            .bind(("record_id", record_id))
+            .bind(("data", data))
+            .await?;
+        // UPDATE on a single record id never creates it, a missing record yields no row
+        let updated: Vec<Id> = response.take(0).map_err(constraint::from_surrealdb)?;
+        Ok(!updated.is_empty())
     }
 
-    /* Method to select records with parameters from the database */
+    /* Method to merge the provided fields into an existing record */
     #[tracing::instrument(err, skip_all)]
-    async fn select_where<T: DeserializeOwned + Sync>(
+    async fn merge_record<T>(&self, id: String, tb_name: String, data: T) -> Result<bool>
+    where
+        T: Serialize + Sync + Send + 'static,
+    {
+        let client = self.client()?;
+        let record_id = query::record_id(&tb_name, &id)?;
+        let mut response = client
+            .query("UPDATE $record_id MERGE $data RETURN id")
+            .bind(("record_id", record_id))
+            .bind(("data", data))
+            .await?;
+        // UPDATE on a single record id never creates it, a missing record yields no row
+        let merged: Vec<Id> = response.take(0).map_err(constraint::from_surrealdb)?;
+        Ok(!merged.is_empty())
+    }
+
+    /* Method to merge fields and bump the version, guarded by the version the client last read */
+    #[tracing::instrument(err, skip_all)]
+    async fn merge_versioned<T>(
         &self,
+        id: String,
         tb_name: String,
-        filter: String,
-        columns: String,
-    ) -> Result<Vec<T>> {
-        let client = self.client.clone().unwrap();
-
-        let filtered_query = if filter.is_empty() {
-            String::new()
-        } else {
-            format!("where {}", filter)
-        };
+        data: T,
+        version: Option<i64>,
+    ) -> Result<bool>
+    where
+        T: Serialize + Sync + Send + 'static,
+    {
+        let client = self.client()?;
+        let record_id = query::record_id(&tb_name, &id)?;
+        // The merge object is computed against the stored record, so the bump and the check are one write
+        let mut response = client
+            .query(
+                "UPDATE $record_id MERGE object::from_entries(array::concat(object::entries($data), \
+                 [[\"version\", (version ?? 0) + 1]])) WHERE $version = NONE OR version = $version \
+                 RETURN id; SELECT id FROM $record_id;",
+            )
+            .bind(("record_id", record_id))
+            .bind(("data", data))
+            .bind(("version", version))
+            .await
+            .map_err(|error| lost_write(error, &id))?;
+        let merged: Vec<Id> = response.take(0).map_err(|error| lost_write(error, &id))?;
+        let existing: Vec<Id> = response.take(1)?;
 
-        let tb_columns = if columns.is_empty() {
-            String::from(" * ")
-        } else {
-            format!(" {} ", columns)
-        };
+        match (merged.is_empty(), existing.is_empty()) {
+            (false, _) => Ok(true),
+            (true, true) => Ok(false),
+            (true, false) => Err(Error::Conflict(id)),
+        }
+    }
 
-        let sql = format!("SELECT {} FROM {} {}", tb_columns, tb_name, filtered_query);
+    /* Method to select records with parameters from the database */
+    #[tracing::instrument(err, skip_all)]
+    async fn select_where<T: DeserializeOwned + Sync>(&self, query: Query) -> Result<Vec<T>> {
+        let client = self.client()?;
+        let (sql, bindings) = query.to_surrealql();
 
-        let mut results = client.query(&sql).await?;
+        let mut results = client.query(sql).bind(Object::from(bindings)).await?;
         let data: Vec<T> = results.take(0)?;
         Ok(data)
     }
+
+    /* Method to commit a transaction in a single round-trip */
+    #[tracing::instrument(err, skip_all)]
+    async fn commit(&self, transaction: Transaction) -> Result<()> {
+        if transaction.is_empty() {
+            return Ok(());
+        }
+
+        let client = self.client()?;
+        let (sql, bindings) = transaction.to_surrealql();
+
+        let mut response = client
+            .query(sql)
+            .bind(Object::from(bindings))
+            .await
+            .map_err(commit_error)?;
+        let mut failures: Vec<(usize, surrealdb::Error)> =
+            response.take_errors().into_iter().collect();
+        failures.sort_by_key(|(index, _)| *index);
+
+        // Statements cancelled by the failing one only report the rollback, surface the cause instead
+        let cause = failures
+            .iter()
+            .position(|(_, error)| !is_cancelled(error))
+            .unwrap_or(0);
+
+        match failures.into_iter().nth(cause) {
+            Some((_, error)) => Err(commit_error(error)),
+            None => Ok(()),
+        }
+    }
+
+    /* Method to subscribe to a table with LIVE SELECT, the query is killed when the stream is dropped */
+    #[tracing::instrument(err, skip_all)]
+    async fn live<T: DeserializeOwned + HasId + Send + Unpin + 'static>(
+        &self,
+        tb_name: String,
+    ) -> Result<LiveStream<T>> {
+        let client = self.client()?;
+        let notifications = client.select::<Vec<T>>(tb_name).live().await?;
+
+        let changes = notifications.filter_map(|notification| async move {
+            let notification = match notification {
+                Ok(notification) => notification,
+                Err(error) => return Some(Err(error.into())),
+            };
+
+            match notification.action {
+                Action::Create => Some(Ok(Change::Create(notification.data))),
+                Action::Update => Some(Ok(Change::Update(notification.data))),
+                Action::Delete => notification.data.id().map(|id| Ok(Change::Delete(id))),
+                // Actions added by newer servers are not changes of a record
+                _ => None,
+            }
+        });
+
+        Ok(Box::pin(changes))
+    }
+}
+
+/* Whether a statement was skipped because another statement of its transaction failed */
+fn is_cancelled(error: &surrealdb::Error) -> bool {
+    match error {
+        surrealdb::Error::Db(
+            Db::QueryNotExecuted | Db::QueryNotExecutedDetail { .. } | Db::QueryCancelled,
+        ) => true,
+        // Remote engines only forward the rendered server error, which starts with the same text
+        surrealdb::Error::Api(Api::Query(message)) => [
+            Db::QueryNotExecuted.to_string(),
+            Db::QueryCancelled.to_string(),
+        ]
+        .iter()
+        .any(|cancelled| message.starts_with(cancelled.as_str())),
+        _ => false,
+    }
+}
+
+/* Error of the statement that failed a transaction, guarded writes report what they found instead of the THROW */
+fn commit_error(error: surrealdb::Error) -> Error {
+    let thrown = match &error {
+        surrealdb::Error::Db(Db::Thrown(message)) => Some(message.as_str()),
+        // Remote engines only forward the rendered server error
+        surrealdb::Error::Api(Api::Query(message)) => {
+            message.strip_prefix(Db::Thrown(String::new()).to_string().as_str())
+        }
+        _ => None,
+    };
+
+    match thrown.and_then(transaction::aborted) {
+        Some(aborted) => aborted,
+        None => lost_write(error, "transaction"),
+    }
+}
+
+/* A write that lost a commit race to another write is reported as Conflict, like a stale version */
+fn lost_write(error: surrealdb::Error, id: &str) -> Error {
+    let race = Db::TxRetryable.to_string();
+    let retryable = match &error {
+        surrealdb::Error::Db(Db::TxRetryable) => true,
+        // A transaction whose commit lost the race reports it on every statement it ran
+        surrealdb::Error::Db(Db::QueryNotExecutedDetail { message }) => message == &race,
+        surrealdb::Error::Api(Api::Query(message)) => message.contains(race.as_str()),
+        _ => false,
+    };
+
+    match retryable {
+        true => Error::Conflict(id.to_string()),
+        false => constraint::from_surrealdb(error),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn embedded_cancellations_are_matched_by_variant() {
+        assert!(is_cancelled(&Db::QueryNotExecuted.into()));
+        assert!(is_cancelled(&Db::QueryCancelled.into()));
+        assert!(is_cancelled(
+            &Db::QueryNotExecutedDetail {
+                message: String::from("rollback"),
+            }
+            .into()
+        ));
+        assert!(!is_cancelled(
+            &Db::Unreachable("failed transaction".into()).into()
+        ));
+    }
+
+    #[test]
+    fn remote_cancellations_are_matched_by_message() {
+        let cancelled = Api::Query(String::from(
+            "The query was not executed due to a failed transaction",
+        ));
+        let cause = Api::Query(String::from(
+            "Database index `user_email_unique` already contains 'a@b.c', with record `user:x`",
+        ));
+
+        assert!(is_cancelled(&cancelled.into()));
+        assert!(!is_cancelled(&cause.into()));
+    }
+
+    #[test]
+    fn guarded_writes_report_what_they_found() {
+        let conflict = Db::Thrown(String::from("conflict class_session:a"));
+        let remote = Api::Query(Db::Thrown(String::from("missing class_booking:b")).to_string());
+
+        assert!(matches!(
+            commit_error(conflict.into()),
+            Error::Conflict(record) if record == "class_session:a"
+        ));
+        assert!(matches!(
+            commit_error(remote.into()),
+            Error::DataNotAvaliable(record) if record == "class_booking:b"
+        ));
+        assert!(matches!(
+            commit_error(Db::Thrown(String::from("other")).into()),
+            Error::DatabaseError(_)
+        ));
+    }
+
+    #[test]
+    fn commit_races_are_reported_as_conflicts() {
+        let id = "post:x";
+        let remote = Api::Query(Db::TxRetryable.to_string());
+        let failed_commit = || Db::QueryNotExecutedDetail {
+            message: Db::TxRetryable.to_string(),
+        };
+
+        assert!(
+            matches!(lost_write(Db::TxRetryable.into(), id), Error::Conflict(record) if record == id)
+        );
+        assert!(matches!(lost_write(remote.into(), id), Error::Conflict(_)));
+        assert!(matches!(
+            lost_write(failed_commit().into(), id),
+            Error::Conflict(_)
+        ));
+        assert!(matches!(
+            lost_write(Api::Query(failed_commit().to_string()).into(), id),
+            Error::Conflict(_)
+        ));
+        assert!(matches!(
+            lost_write(Db::QueryCancelled.into(), id),
+            Error::DatabaseError(_)
+        ));
+    }
 }
@@ -0,0 +1,272 @@
+use std::collections::BTreeMap;
+
+use errors::{Error, Result};
+use serde::Serialize;
+use surrealdb::sql::{Id, Thing, Value};
+
+use crate::query::Condition;
+
+/* A single write staged inside a transaction */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Operation {
+    Create {
+        id: Thing,
+        data: Value,
+    },
+    Merge {
+        id: Thing,
+        data: Value,
+    },
+    DeleteWhere {
+        table: &'static str,
+        condition: Condition,
+    },
+    Delete {
+        id: Thing,
+    },
+    Increment {
+        id: Thing,
+        field: &'static str,
+        by: i64,
+        at_most: Option<&'static str>,
+    },
+}
+
+/// Start of the error a guarded write throws when its record changed under it, followed by the id
+const CONFLICT: &str = "conflict ";
+/// Start of the error a required write throws when its record does not exist, followed by the id
+const MISSING: &str = "missing ";
+
+/* Unit of work: every staged write is committed together or not at all */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Transaction {
+    operations: Vec<Operation>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /* Stage a record creation, the id is generated up front so later writes can reference it */
+    pub fn create<T: Serialize + 'static>(&mut self, tb_name: &str, data: T) -> Result<Thing> {
+        let id = Thing::from((tb_name, Id::rand()));
+
+        let mut data = to_value(data)?;
+        if let Value::Object(object) = &mut data {
+            object.insert("id".to_string(), Value::from(id.clone()));
+        }
+
+        self.operations.push(Operation::Create {
+            id: id.clone(),
+            data,
+        });
+        Ok(id)
+    }
+
+    /* Stage writing the fields present in `data` to an existing record, a missing record is left alone */
+    pub fn merge<T: Serialize + 'static>(&mut self, id: Thing, data: T) -> Result<()> {
+        let data = to_value(data)?;
+        self.operations.push(Operation::Merge { id, data });
+        Ok(())
+    }
+
+    /* Stage the removal of every record of the table matching the condition */
+    pub fn delete_where(&mut self, tb_name: &'static str, condition: Condition) {
+        self.operations.push(Operation::DeleteWhere {
+            table: tb_name,
+            condition,
+        });
+    }
+
+    /* Stage the removal of a record, the transaction fails with DataNotAvaliable when it does not exist */
+    pub fn delete(&mut self, id: Thing) {
+        self.operations.push(Operation::Delete { id });
+    }
+
+    /* Stage adding `by` to a counter and bumping the record version. The transaction fails with
+    Conflict when the record does not exist or the counter would pass the `at_most` field */
+    pub fn increment(
+        &mut self,
+        id: Thing,
+        field: &'static str,
+        by: i64,
+        at_most: Option<&'static str>,
+    ) {
+        self.operations.push(Operation::Increment {
+            id,
+            field,
+            by,
+            at_most,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    pub fn operations(&self) -> &[Operation] {
+        &self.operations
+    }
+
+    /* Render the transaction as a single SurrealQL block together with its bound parameters */
+    pub fn to_surrealql(&self) -> (String, BTreeMap<String, Value>) {
+        let mut bindings = BTreeMap::new();
+        // Conditions of every statement share one numbering, so their `$p` bindings never collide
+        let mut params = Vec::new();
+        let mut statements = vec![String::from("BEGIN TRANSACTION;")];
+
+        for (index, operation) in self.operations.iter().enumerate() {
+            match operation {
+                Operation::Create { id, data } => {
+                    bindings.insert(format!("id{}", index), Value::from(id.clone()));
+                    bindings.insert(format!("data{}", index), data.clone());
+                    statements.push(format!("CREATE $id{} CONTENT $data{};", index, index));
+                }
+                Operation::Merge { id, data } => {
+                    bindings.insert(format!("id{}", index), Value::from(id.clone()));
+                    bindings.insert(format!("data{}", index), data.clone());
+                    statements.push(format!("UPDATE $id{} MERGE $data{};", index, index));
+                }
+                Operation::DeleteWhere { table, condition } => {
+                    bindings.insert(format!("table{}", index), Value::from(*table));
+                    statements.push(format!(
+                        "DELETE type::table($table{}) WHERE {};",
+                        index,
+                        condition.to_surrealql(&mut params)
+                    ));
+                }
+                Operation::Delete { id } => {
+                    bindings.insert(format!("id{}", index), Value::from(id.clone()));
+                    bindings.insert(format!("abort{}", index), Value::from(missing(id)));
+                    statements.push(format!(
+                        "IF array::len((DELETE $id{} RETURN BEFORE)) = 0 {{ THROW $abort{} }};",
+                        index, index
+                    ));
+                }
+                Operation::Increment {
+                    id,
+                    field,
+                    by,
+                    at_most,
+                } => {
+                    bindings.insert(format!("id{}", index), Value::from(id.clone()));
+                    bindings.insert(format!("by{}", index), Value::from(*by));
+                    bindings.insert(format!("abort{}", index), Value::from(conflict(id)));
+                    let guard = at_most
+                        .map(|limit| format!(" WHERE {} + $by{} <= {}", field, index, limit))
+                        .unwrap_or_default();
+                    statements.push(format!(
+                        "IF array::len((UPDATE $id{index} SET {field} += $by{index}, \
+                         version = (version ?? 0) + 1{guard} RETURN id)) = 0 {{ THROW $abort{index} }};",
+                    ));
+                }
+            }
+        }
+
+        for (index, value) in params.into_iter().enumerate() {
+            bindings.insert(format!("p{}", index), value);
+        }
+
+        statements.push(String::from("COMMIT TRANSACTION;"));
+        (statements.join("\n"), bindings)
+    }
+}
+
+/* Message thrown when a guarded write finds its record changed */
+pub(crate) fn conflict(id: &Thing) -> String {
+    format!("{}{}", CONFLICT, id)
+}
+
+/* Message thrown when a required write finds no record */
+pub(crate) fn missing(id: &Thing) -> String {
+    format!("{}{}", MISSING, id)
+}
+
+/* Error a guarded or required write aborted the transaction with, None for any other message */
+pub(crate) fn aborted(message: &str) -> Option<Error> {
+    if let Some(id) = message.strip_prefix(CONFLICT) {
+        return Some(Error::Conflict(id.to_string()));
+    }
+    message
+        .strip_prefix(MISSING)
+        .map(|id| Error::DataNotAvaliable(id.to_string()))
+}
+
+/* Serialize staged data the way the SurrealDB client would */
+fn to_value<T: Serialize + 'static>(data: T) -> Result<Value> {
+    surrealdb::sql::to_value(data).map_err(|error| errors::Error::DatabaseError(error.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn conditional_deletes_share_the_parameter_numbering() {
+        let comment = Thing::from(("comment", "c1"));
+        let mut transaction = Transaction::new();
+        transaction.delete_where("post_hashtag", Condition::eq("post_id", "post:p1"));
+        transaction.delete_where(
+            "comment",
+            Condition::Or(vec![
+                Condition::eq("id", comment.clone()),
+                Condition::eq("parent_id", comment.clone()),
+            ]),
+        );
+
+        let (sql, bindings) = transaction.to_surrealql();
+        assert_eq!(
+            sql,
+            "BEGIN TRANSACTION;\n\
+             DELETE type::table($table0) WHERE post_id = $p0;\n\
+             DELETE type::table($table1) WHERE (id = $p1 OR parent_id = $p2);\n\
+             COMMIT TRANSACTION;"
+        );
+        assert_eq!(bindings.get("table1"), Some(&Value::from("comment")));
+        assert_eq!(bindings.get("p0"), Some(&Value::from("post:p1")));
+        assert_eq!(bindings.get("p2"), Some(&Value::from(comment)));
+    }
+
+    #[test]
+    fn aborted_writes_are_read_back_from_their_message() {
+        let id = Thing::from(("class_session", "s1"));
+
+        assert!(matches!(
+            aborted(&conflict(&id)),
+            Some(Error::Conflict(record)) if record == "class_session:s1"
+        ));
+        assert!(matches!(
+            aborted(&missing(&id)),
+            Some(Error::DataNotAvaliable(record)) if record == "class_session:s1"
+        ));
+        assert!(aborted("something else").is_none());
+    }
+
+    #[test]
+    fn increments_are_guarded_by_their_limit() {
+        let mut transaction = Transaction::new();
+        transaction.increment(
+            Thing::from(("class_session", "s1")),
+            "booked_count",
+            1,
+            Some("capacity"),
+        );
+        transaction.increment(
+            Thing::from(("class_session", "s1")),
+            "booked_count",
+            -1,
+            None,
+        );
+
+        let (sql, bindings) = transaction.to_surrealql();
+        assert!(sql.contains(
+            "UPDATE $id0 SET booked_count += $by0, version = (version ?? 0) + 1 \
+             WHERE booked_count + $by0 <= capacity RETURN id"
+        ));
+        assert!(sql.contains(
+            "UPDATE $id1 SET booked_count += $by1, version = (version ?? 0) + 1 RETURN id"
+        ));
+        assert_eq!(bindings.get("by1"), Some(&Value::from(-1)));
+    }
+}
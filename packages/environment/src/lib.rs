@@ -9,6 +9,8 @@ pub struct Environment {
     pub db_pass: String,
     pub db_name: String,
     pub db_namespace: String,
+    pub db_engine: String,
+    pub db_path: String,
     pub host_ip: String,
     pub host_port: String,
     pub refresh_token_private_key: String,
@@ -25,6 +27,11 @@ pub struct Environment {
     pub storage_bucket: String,
     pub gcp_storage_api: String,
     pub app_port: String,
+    pub page_size: u64,
+    pub slow_query_ms: u64,
+    pub profile_cache_ttl: u64,
+    pub feed_cache_ttl: u64,
+    pub nearby_radius_km: f64,
 }
 
 impl Environment {
@@ -37,6 +44,8 @@ impl Environment {
         let db_pass = env::var("DB_PASS").unwrap_or(String::from("none"));
         let db_name = env::var("DB_NAME").unwrap_or(String::from("none"));
         let db_namespace = env::var("DB_NAMESPACE").unwrap_or(String::from("none"));
+        let db_engine = env::var("DB_ENGINE").unwrap_or(String::from("none"));
+        let db_path = env::var("DB_PATH").unwrap_or(String::from("none"));
 
         let redis_host = env::var("REDIS_HOST").unwrap_or(String::from("none"));
         let redis_username = env::var("REDIS_USERNAME").unwrap_or(String::from("none"));
@@ -61,6 +70,26 @@ impl Environment {
         let storage_bucket = env::var("STORAGE_BUCKET").unwrap_or(String::from("none"));
         let gcp_storage_api = env::var("GOOGLE_STORAGE_API_HOST").unwrap_or(String::from("none"));
         let app_port = env::var("APP_PORT").unwrap_or(String::from("none"));
+        let page_size = env::var("PAGE_SIZE")
+            .ok()
+            .and_then(|page_size| page_size.parse().ok())
+            .unwrap_or(5);
+        let slow_query_ms = env::var("SLOW_QUERY_MS")
+            .ok()
+            .and_then(|slow_query_ms| slow_query_ms.parse().ok())
+            .unwrap_or(200);
+        let profile_cache_ttl = env::var("PROFILE_CACHE_TTL")
+            .ok()
+            .and_then(|profile_cache_ttl| profile_cache_ttl.parse().ok())
+            .unwrap_or(300);
+        let feed_cache_ttl = env::var("FEED_CACHE_TTL")
+            .ok()
+            .and_then(|feed_cache_ttl| feed_cache_ttl.parse().ok())
+            .unwrap_or(30);
+        let nearby_radius_km = env::var("NEARBY_RADIUS_KM")
+            .ok()
+            .and_then(|nearby_radius_km| nearby_radius_km.parse().ok())
+            .unwrap_or(25.0);
 
         Environment {
             db_host,
@@ -69,6 +98,8 @@ impl Environment {
             db_pass,
             db_name,
             db_namespace,
+            db_engine,
+            db_path,
             redis_host,
             redis_username,
             redis_password,
@@ -85,6 +116,11 @@ impl Environment {
             storage_bucket,
             gcp_storage_api,
             app_port,
+            page_size,
+            slow_query_ms,
+            profile_cache_ttl,
+            feed_cache_ttl,
+            nearby_radius_km,
         }
     }
 }
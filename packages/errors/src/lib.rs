@@ -25,6 +25,11 @@ pub enum Error {
     UploadProcessingError(String),
     CloudAuthError(String),
     InvalidUserType(String),
+    InvalidRecordId(String),
+    InvalidCursor(String),
+    Conflict(String),
+    InvalidVersion(String),
+    InvalidRequest(String),
 }
 
 impl core::fmt::Display for Error {
@@ -41,6 +46,18 @@ impl From<surrealdb::Error> for Error {
     }
 }
 
+impl From<tokio_postgres::Error> for Error {
+    fn from(error: tokio_postgres::Error) -> Self {
+        Error::DatabaseError(error.to_string())
+    }
+}
+
+impl From<deadpool_postgres::PoolError> for Error {
+    fn from(error: deadpool_postgres::PoolError) -> Self {
+        Error::DatabaseError(error.to_string())
+    }
+}
+
 impl From<jsonwebtoken::errors::Error> for Error {
     fn from(error: jsonwebtoken::errors::Error) -> Self {
         Error::TokenError(error.to_string())
@@ -95,9 +112,14 @@ impl From<google_cloud_storage::client::google_cloud_auth::error::Error> for Err
     }
 }
 
-impl IntoResponse for Error {
-    fn into_response(self) -> Response<Body> {
-        let (status, error_message) = match &self {
+impl Error {
+    /* Message shown to the client, also where no HTTP response carries it */
+    pub fn message(&self) -> String {
+        self.parts().1
+    }
+
+    fn parts(&self) -> (StatusCode, String) {
+        match self {
             Error::LoginFail => (StatusCode::UNAUTHORIZED, "Login failed".to_string()),
             Error::DatabaseError(error) => (
                 StatusCode::INTERNAL_SERVER_ERROR,
@@ -123,7 +145,29 @@ impl IntoResponse for Error {
                 (StatusCode::INTERNAL_SERVER_ERROR, message.to_string())
             }
             Error::InvalidUserType(message) => (StatusCode::FORBIDDEN, message.to_string()),
-        };
+            Error::InvalidRecordId(id) => {
+                (StatusCode::BAD_REQUEST, format!("{} is not a valid id", id))
+            }
+            Error::InvalidCursor(cursor) => (
+                StatusCode::BAD_REQUEST,
+                format!("{} is not a valid cursor", cursor),
+            ),
+            Error::Conflict(id) => (
+                StatusCode::CONFLICT,
+                format!("{} was modified by another request", id),
+            ),
+            Error::InvalidVersion(version) => (
+                StatusCode::BAD_REQUEST,
+                format!("{} is not a valid version", version),
+            ),
+            Error::InvalidRequest(message) => (StatusCode::BAD_REQUEST, message.to_string()),
+        }
+    }
+}
+
+impl IntoResponse for Error {
+    fn into_response(self) -> Response<Body> {
+        let (status, error_message) = self.parts();
 
         let body = Body::from(
             json!({
@@ -0,0 +1,252 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+};
+
+use super::{ClassBookingModel, ClassSessionModel, DBClient, GymSeekerModel, RepositoryResult};
+use database::{
+    id::{ClassBookingId, ClassSessionId, GymId, GymSeekerId},
+    interface::DBInterface as _,
+    model::{ClassSessionUpdate, Id, PayloadClassBookingResponse, PayloadClassSessionResponse},
+    query::{Condition, Direction, Operator, Query},
+    transaction::Transaction,
+};
+use surrealdb::sql::{Datetime, Thing, Value};
+
+#[derive(Clone, Debug)]
+pub struct ClassRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl ClassRepository {
+    pub async fn insert_session(
+        &self,
+        data: ClassSessionModel,
+    ) -> RepositoryResult<Option<ClassSessionId>> {
+        let created: Option<Id> = self
+            .repo
+            .insert_record(ClassSessionId::TABLE.to_string(), data)
+            .await?;
+        created
+            .map(|created| ClassSessionId::new(created.id))
+            .transpose()
+    }
+
+    pub async fn get_session(
+        &self,
+        id: &ClassSessionId,
+    ) -> RepositoryResult<Option<ClassSessionModel>> {
+        let sessions: Vec<ClassSessionModel> = self
+            .repo
+            .select_where(Query::select("class_session").eq("id", id.clone()))
+            .await?;
+
+        Ok(sessions.into_iter().next())
+    }
+
+    pub async fn update_session(
+        &self,
+        id: &ClassSessionId,
+        data: ClassSessionUpdate,
+        version: Option<i64>,
+    ) -> RepositoryResult<bool> {
+        self.repo
+            .merge_versioned(
+                id.to_string(),
+                ClassSessionId::TABLE.to_string(),
+                data,
+                version,
+            )
+            .await
+    }
+
+    /* Classes of the gym starting in [from, to), the earliest first */
+    pub async fn schedule(
+        &self,
+        gym_id: &GymId,
+        from: Datetime,
+        to: Datetime,
+    ) -> RepositoryResult<Vec<ClassSessionModel>> {
+        self.repo
+            .select_where(
+                Query::select("class_session")
+                    .eq("gym_id", gym_id.clone())
+                    .filter(Condition::compare("start_at", Operator::Gte, from))
+                    .filter(Condition::compare("start_at", Operator::Lt, to))
+                    .order_by("start_at", Direction::Asc),
+            )
+            .await
+    }
+
+    /* Classes with the given ids, keyed by `class_session:id` */
+    pub async fn sessions(
+        &self,
+        ids: Vec<ClassSessionId>,
+    ) -> RepositoryResult<HashMap<String, ClassSessionModel>> {
+        self.repo
+            .select_by_ids(
+                ClassSessionId::TABLE.to_string(),
+                ids.into_iter().map(Thing::from).collect(),
+            )
+            .await
+    }
+
+    /* Take a seat for the gym seeker and count it on the class in one commit. Fails with DataExist
+    when they already hold one, and with Conflict when the class is full or gone */
+    pub async fn insert_booking(
+        &self,
+        data: ClassBookingModel,
+    ) -> RepositoryResult<ClassBookingId> {
+        let session_id = data.session_id.clone();
+
+        // The seat is counted first, so a class that is gone fails the same way on every backend
+        let mut transaction = Transaction::new();
+        transaction.increment(session_id.into(), "booked_count", 1, Some("capacity"));
+        let id = transaction.create(ClassBookingId::TABLE, data)?;
+        self.repo.commit(transaction).await?;
+
+        ClassBookingId::new(id)
+    }
+
+    /* Give up the seat of the gym seeker and free it on the class in one commit, false when they held none */
+    pub async fn delete_booking(
+        &self,
+        session_id: &ClassSessionId,
+        gym_seeker_id: &GymSeekerId,
+    ) -> RepositoryResult<bool> {
+        let repo = &self.repo;
+
+        let bookings: Vec<ClassBookingModel> = repo
+            .select_where(
+                Query::select("class_booking")
+                    .eq("gym_seeker_id", gym_seeker_id.clone())
+                    .eq("session_id", session_id.clone()),
+            )
+            .await?;
+
+        let Some(id) = bookings.into_iter().find_map(|booking| booking.id) else {
+            return Ok(false);
+        };
+
+        let mut transaction = Transaction::new();
+        transaction.delete(id.into());
+        transaction.increment(session_id.clone().into(), "booked_count", -1, None);
+
+        // A request that removed the same booking first leaves nothing to delete
+        match repo.commit(transaction).await {
+            Ok(()) => Ok(true),
+            Err(errors::Error::DataNotAvaliable(_)) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /* Bookings of the gym seeker in every class */
+    pub async fn bookings(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+    ) -> RepositoryResult<Vec<ClassBookingModel>> {
+        self.repo
+            .select_where(Query::select("class_booking").eq("gym_seeker_id", gym_seeker_id.clone()))
+            .await
+    }
+
+    /* Which of the classes the gym seeker holds a seat in */
+    pub async fn booked(
+        &self,
+        sessions: &[ClassSessionModel],
+        gym_seeker_id: &GymSeekerId,
+    ) -> RepositoryResult<HashSet<String>> {
+        let ids: Vec<Value> = sessions
+            .iter()
+            .filter_map(|session| session.id.clone().map(Value::from))
+            .collect();
+        if ids.is_empty() {
+            return Ok(HashSet::new());
+        }
+
+        let bookings: Vec<ClassBookingModel> = self
+            .repo
+            .select_where(
+                Query::select("class_booking")
+                    .filter(Condition::compare("session_id", Operator::In, ids))
+                    .eq("gym_seeker_id", gym_seeker_id.clone()),
+            )
+            .await?;
+
+        Ok(bookings
+            .into_iter()
+            .map(|booking| booking.session_id.to_string())
+            .collect())
+    }
+
+    /* Gym seekers holding a seat in the class, in booking order */
+    pub async fn attendees(
+        &self,
+        session_id: &ClassSessionId,
+    ) -> RepositoryResult<Vec<PayloadClassBookingResponse>> {
+        let repo = &self.repo;
+
+        let mut bookings: Vec<ClassBookingModel> = repo
+            .select_where(Query::select("class_booking").eq("session_id", session_id.clone()))
+            .await?;
+        bookings.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+
+        let gym_seekers: HashMap<String, GymSeekerModel> = repo
+            .select_by_ids(
+                GymSeekerId::TABLE.to_string(),
+                bookings
+                    .iter()
+                    .map(|booking| Thing::from(booking.gym_seeker_id.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        Ok(bookings
+            .into_iter()
+            .map(|booking| {
+                let gym_seeker = gym_seekers.get(&booking.gym_seeker_id.to_string());
+                PayloadClassBookingResponse {
+                    id: booking.id.map(|id| id.to_string()).unwrap_or_default(),
+                    session_id: booking.session_id.to_string(),
+                    gym_seeker_id: booking.gym_seeker_id.to_string(),
+                    name: gym_seeker
+                        .map(|gym_seeker| gym_seeker.name.clone())
+                        .unwrap_or_default(),
+                    profile_picture: gym_seeker
+                        .map(|gym_seeker| gym_seeker.profile_picture.clone())
+                        .unwrap_or_default(),
+                    created_at: booking.created_at,
+                }
+            })
+            .collect())
+    }
+}
+
+/* Class as listed, `booked_by_me` tells whether the requesting gym seeker holds a seat */
+pub fn session_response(
+    session: ClassSessionModel,
+    booked_by_me: bool,
+) -> PayloadClassSessionResponse {
+    let cancelled = session.cancelled_at.is_some();
+    let seats_left = match cancelled {
+        true => 0,
+        false => (session.capacity - session.booked_count).max(0),
+    };
+
+    PayloadClassSessionResponse {
+        id: session.id.map(|id| id.to_string()).unwrap_or_default(),
+        gym_id: session.gym_id.to_string(),
+        trainer_id: session.trainer_id.map(|id| id.to_string()),
+        title: session.title,
+        description: session.description,
+        start_at: session.start_at,
+        duration_minutes: session.duration_minutes,
+        capacity: session.capacity,
+        location: session.location,
+        booked_count: session.booked_count,
+        seats_left,
+        cancelled,
+        booked_by_me,
+        version: session.version,
+    }
+}
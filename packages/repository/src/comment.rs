@@ -0,0 +1,196 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{paginate, CommentModel, DBClient, RepositoryResult, UserModel};
+use database::{
+    id::{CommentId, PostId, UserId},
+    interface::DBInterface as _,
+    model::{
+        CommentContentUpdate, CommentCount, Id, Paginated, PayloadCommentResponse, ReplyCount,
+    },
+    query::{Condition, Cursor, Operator, Query},
+    transaction::Transaction,
+};
+use surrealdb::sql::{Thing, Value};
+
+#[derive(Clone, Debug)]
+pub struct CommentRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl CommentRepository {
+    pub async fn insert_data(&self, data: CommentModel) -> RepositoryResult<Option<CommentId>> {
+        let created: Option<Id> = self
+            .repo
+            .insert_record(CommentId::TABLE.to_string(), data)
+            .await?;
+        created
+            .map(|created| CommentId::new(created.id))
+            .transpose()
+    }
+
+    pub async fn get(&self, id: &CommentId) -> RepositoryResult<Option<CommentModel>> {
+        let comments: Vec<CommentModel> = self
+            .repo
+            .select_where(Query::select("comment").eq("id", id.clone()))
+            .await?;
+
+        Ok(comments.into_iter().next())
+    }
+
+    pub async fn update_data(
+        &self,
+        id: &CommentId,
+        data: CommentContentUpdate,
+        version: Option<i64>,
+    ) -> RepositoryResult<bool> {
+        let updated = self
+            .repo
+            .merge_versioned(id.to_string(), CommentId::TABLE.to_string(), data, version)
+            .await?;
+
+        Ok(updated)
+    }
+
+    /* Delete the comment together with its replies in one commit */
+    pub async fn delete_data(&self, id: &CommentId) -> RepositoryResult<bool> {
+        let mut transaction = Transaction::new();
+        transaction.delete_where(
+            CommentId::TABLE,
+            Condition::Or(vec![
+                Condition::eq("id", id.clone()),
+                Condition::eq("parent_id", id.clone()),
+            ]),
+        );
+
+        self.repo.commit(transaction).await?;
+        Ok(true)
+    }
+
+    /* Top-level comments of the post, newest first */
+    pub async fn by_post(
+        &self,
+        post_id: &PostId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadCommentResponse>> {
+        let comments: Vec<CommentModel> = self
+            .repo
+            .select_where(
+                Query::select("comment")
+                    .eq("post_id", post_id.clone())
+                    .filter(Condition::is_none("parent_id"))
+                    .page_by("created_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (comments, next_cursor) = paginate(comments, limit)?;
+        let data = self.responses(comments).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Replies to the comment, newest first */
+    pub async fn replies(
+        &self,
+        parent_id: &CommentId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadCommentResponse>> {
+        let comments: Vec<CommentModel> = self
+            .repo
+            .select_where(
+                Query::select("comment")
+                    .eq("parent_id", parent_id.clone())
+                    .page_by("created_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (comments, next_cursor) = paginate(comments, limit)?;
+        let data = self.responses(comments).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Number of comments and replies on each post, keyed by post id */
+    pub async fn counts(&self, post_ids: &[PostId]) -> RepositoryResult<HashMap<String, u64>> {
+        if post_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let ids: Vec<Value> = post_ids.iter().cloned().map(Value::from).collect();
+        let counts: Vec<CommentCount> = self
+            .repo
+            .select_where(
+                Query::select("comment")
+                    .filter(Condition::compare("post_id", Operator::In, ids))
+                    .count_by("post_id"),
+            )
+            .await?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.post_id.to_string(), count.count))
+            .collect())
+    }
+
+    /* Authors and reply counts of the listed comments */
+    async fn responses(
+        &self,
+        comments: Vec<CommentModel>,
+    ) -> RepositoryResult<Vec<PayloadCommentResponse>> {
+        let repo = &self.repo;
+
+        let users: HashMap<String, UserModel> = repo
+            .select_by_ids(
+                UserId::TABLE.to_string(),
+                comments
+                    .iter()
+                    .map(|comment| Thing::from(comment.user_id.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        let top_level: Vec<Value> = comments
+            .iter()
+            .filter(|comment| comment.parent_id.is_none())
+            .filter_map(|comment| comment.id.clone().map(Value::from))
+            .collect();
+        let reply_counts: HashMap<String, u64> = if top_level.is_empty() {
+            HashMap::new()
+        } else {
+            let counts: Vec<ReplyCount> = repo
+                .select_where(
+                    Query::select("comment")
+                        .filter(Condition::compare("parent_id", Operator::In, top_level))
+                        .count_by("parent_id"),
+                )
+                .await?;
+            counts
+                .into_iter()
+                .map(|count| (count.parent_id.to_string(), count.count))
+                .collect()
+        };
+
+        let data = comments
+            .into_iter()
+            .map(|comment| {
+                let id = comment.id.map(|id| id.to_string()).unwrap_or_default();
+                let user = users.get(&comment.user_id.to_string());
+
+                PayloadCommentResponse {
+                    reply_count: reply_counts.get(&id).copied().unwrap_or_default(),
+                    id,
+                    post_id: comment.post_id.to_string(),
+                    parent_id: comment.parent_id.map(|id| id.to_string()),
+                    user_id: comment.user_id.to_string(),
+                    username: user.map(|user| user.username.clone()).unwrap_or_default(),
+                    user_type: user.map(|user| user.user_type.clone()).unwrap_or_default(),
+                    content: comment.content,
+                    created_at: comment.created_at,
+                    updated_at: comment.updated_at,
+                    version: comment.version,
+                }
+            })
+            .collect();
+
+        Ok(data)
+    }
+}
@@ -1,65 +1,342 @@
-use super::{DBClient, FeedModel, RepositoryResult};
-use database::interface::DBInterface;
-use std::sync::Arc;
+use super::{
+    paginate,
+    post::{galleries_by_post, visible_posts},
+    DBClient, FeedModel, GymModel, GymSeekerModel, LocationModel, PostHashtagModel, PostModel,
+    RepositoryResult, UserModel,
+};
+use database::{
+    id::{GymId, GymSeekerId, LocationId, PostId, ProfileId, TrainerId, UserId},
+    interface::DBInterface,
+    live::LiveStream,
+    model::{FeedLocation, Paginated},
+    query::{Condition, Cursor, Operator, Query},
+};
+use std::{collections::HashMap, sync::Arc};
+use surrealdb::sql::{Thing, Value};
 
 #[derive(Clone)]
 pub struct FeedRepository {
     pub repo: Arc<DBClient>,
 }
 
-impl FeedRepository {
-    pub async fn get_list(&self, page: i64) -> RepositoryResult<Vec<FeedModel>> {
-        // Calculate start page based on given page number
-        let start_page = match page {
-            1 => 0,
-            p if p >= 1 => ((page - 1) * 5) + 1,
+/* Profiles whose posts make up a viewer's home feed */
+#[derive(Clone, Debug, Default)]
+pub struct FeedSources {
+    /// Profiles the viewer follows
+    pub followed: Vec<ProfileId>,
+    /// Gym the viewer belongs to, the gym itself for a gym viewer
+    pub own_gym: Option<GymId>,
+    /// Trainers working at the viewer's gym
+    pub gym_trainers: Vec<TrainerId>,
+    /// Gyms close to the viewer's gym
+    pub nearby_gyms: Vec<GymId>,
+}
+
+impl FeedSources {
+    pub fn is_empty(&self) -> bool {
+        self.followed.is_empty()
+            && self.own_gym.is_none()
+            && self.gym_trainers.is_empty()
+            && self.nearby_gyms.is_empty()
+    }
+
+    /* Posts authored by any of the sources */
+    fn condition(&self) -> Condition {
+        let mut gyms: Vec<Value> = self.nearby_gyms.iter().cloned().map(Value::from).collect();
+        gyms.extend(self.own_gym.iter().cloned().map(Value::from));
+        let mut gym_seekers: Vec<Value> = Vec::new();
+        let mut trainers: Vec<Value> = self.gym_trainers.iter().cloned().map(Value::from).collect();
+        for profile in &self.followed {
+            match profile {
+                ProfileId::Gym(id) => gyms.push(id.clone().into()),
+                ProfileId::GymSeeker(id) => gym_seekers.push(id.clone().into()),
+                ProfileId::Trainer(id) => trainers.push(id.clone().into()),
+            }
+        }
+
+        let authors = [
+            ("gym_id", gyms),
+            ("gym_seeker_id", gym_seekers),
+            ("trainer_id", trainers),
+        ];
+        Condition::Or(
+            authors
+                .into_iter()
+                .filter(|(_, ids)| !ids.is_empty())
+                .map(|(field, ids)| Condition::compare(field, Operator::In, ids))
+                .collect(),
+        )
+    }
+
+    /* How close the author of a post is to the viewer, higher ranks first within a page */
+    fn affinity(&self, post: &PostModel) -> u8 {
+        let followed = |profile: ProfileId| self.followed.contains(&profile);
+
+        if let Some(gym_id) = &post.gym_id {
+            if self.own_gym.as_ref() == Some(gym_id) {
+                return 3;
+            }
+            if followed(ProfileId::Gym(gym_id.clone())) {
+                return 2;
+            }
+            if self.nearby_gyms.contains(gym_id) {
+                return 1;
+            }
+        }
+        if let Some(trainer_id) = &post.trainer_id {
+            if self.gym_trainers.contains(trainer_id) {
+                return 3;
+            }
+            if followed(ProfileId::Trainer(trainer_id.clone())) {
+                return 2;
+            }
+        }
+        match &post.gym_seeker_id {
+            Some(gym_seeker_id) if followed(ProfileId::GymSeeker(gym_seeker_id.clone())) => 2,
             _ => 0,
-        };
+        }
+    }
+}
 
+impl FeedRepository {
+    pub async fn get_list(
+        &self,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<FeedModel>> {
         let repo = &self.repo;
 
-        // Construct the filter query
-        let filter = format!(
-            r#"
-            ORDER BY updated_at DESC 
-            LIMIT 5 START {}
-            "#,
-            start_page
-        );
+        let posts: Vec<PostModel> = repo
+            .select_where(visible_posts().page(cursor.as_ref(), limit + 1))
+            .await?;
+        let (posts, next_cursor) = paginate(posts, limit)?;
+        let data = self.entries(posts).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Posts by the viewer's sources, paged by time and ranked by affinity within each page */
+    pub async fn get_home(
+        &self,
+        sources: &FeedSources,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<FeedModel>> {
+        if sources.is_empty() {
+            return Ok(Paginated {
+                data: Vec::new(),
+                next_cursor: None,
+            });
+        }
 
-        // Define the data selection structure
-        let data: Vec<FeedModel> = repo
+        let posts: Vec<PostModel> = self
+            .repo
             .select_where(
-                format!("post {}", filter),
-                "".to_string(),
-                r#"
-                    content,
-                    user_id.user_type AS user_type,
-                    user_id.username AS username,
-                    gym_id.name AS gym_name,
-                    
-                    IF is_gym_seeker = true THEN
-                        gym_seeker_id.profile_picture
-                    ELSE IF is_gym = true THEN
-                        gym_id.profile_picture
-                    ELSE IF is_trainer = true THEN
-                        trainer.profile_picture
-                    ELSE
-                        NULL
-                    END AS profile_picture,
-                    
-                    IF is_gym = true THEN
-                        gym_id.location_id.{latitude, longitude}
-                    ELSE
-                        NULL
-                    END AS location,
-                    (SELECT id,media_url as link FROM post_gallery WHERE content_id == $parent.id) AS post_gallery,
-                    updated_at
-                "#
-                .to_string(),
+                visible_posts()
+                    .filter(sources.condition())
+                    .page(cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        // The cursor comes from the time order, ranking only reorders the page itself
+        let (mut posts, next_cursor) = paginate(posts, limit)?;
+        posts.sort_by_key(|post| std::cmp::Reverse(sources.affinity(post)));
+        let data = self.entries(posts).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Posts using the hashtag, newest first */
+    pub async fn get_by_hashtag(
+        &self,
+        tag: &str,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<FeedModel>> {
+        let repo = &self.repo;
+
+        // Pages run over the hashtag rows, which carry the creation time of their post
+        let hashtags: Vec<PostHashtagModel> = repo
+            .select_where(Query::select("post_hashtag").eq("tag", tag).page_by(
+                "created_at",
+                cursor.as_ref(),
+                limit + 1,
+            ))
+            .await?;
+        let (hashtags, next_cursor) = paginate(hashtags, limit)?;
+
+        let mut posts: HashMap<String, PostModel> = repo
+            .select_by_ids(
+                PostId::TABLE.to_string(),
+                hashtags
+                    .iter()
+                    .map(|hashtag| Thing::from(hashtag.post_id.clone()))
+                    .collect(),
+            )
+            .await?;
+        // Deleting a post drops its hashtags, the check only covers a deletion racing this read
+        let posts = hashtags
+            .iter()
+            .filter_map(|hashtag| posts.remove(&hashtag.post_id.to_string()))
+            .filter(|post| post.deleted_at.is_none())
+            .collect();
+        let data = self.entries(posts).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Every post created, updated or deleted from now on, soft deleted posts included */
+    pub async fn live(&self) -> RepositoryResult<LiveStream<PostModel>> {
+        self.repo.live(PostId::TABLE.to_string()).await
+    }
+
+    /* Feed entries of the given posts, in the same order */
+    pub async fn entries(&self, posts: Vec<PostModel>) -> RepositoryResult<Vec<FeedModel>> {
+        let repo = &self.repo;
+
+        // Authors are resolved with one query per table instead of per-row subselects,
+        // which keeps the feed portable across database backends
+        let users: HashMap<String, UserModel> = repo
+            .select_by_ids(
+                UserId::TABLE.to_string(),
+                posts
+                    .iter()
+                    .filter_map(|post| post.user_id.clone().map(Thing::from))
+                    .collect(),
+            )
+            .await?;
+        let gyms: HashMap<String, GymModel> = repo
+            .select_by_ids(
+                GymId::TABLE.to_string(),
+                posts
+                    .iter()
+                    .filter_map(|post| post.gym_id.clone().map(Thing::from))
+                    .collect(),
+            )
+            .await?;
+        let gym_seekers: HashMap<String, GymSeekerModel> = repo
+            .select_by_ids(
+                GymSeekerId::TABLE.to_string(),
+                posts
+                    .iter()
+                    .filter_map(|post| post.gym_seeker_id.clone().map(Thing::from))
+                    .collect(),
+            )
+            .await?;
+        let locations: HashMap<String, LocationModel> = repo
+            .select_by_ids(
+                LocationId::TABLE.to_string(),
+                gyms.values()
+                    .filter_map(|gym| gym.location_id.clone().map(Thing::from))
+                    .collect(),
             )
             .await?;
+        let mut galleries = galleries_by_post(repo, &posts).await?;
 
-        Ok(data)
+        let entries = posts
+            .into_iter()
+            .map(|post| {
+                let user = post
+                    .user_id
+                    .as_ref()
+                    .and_then(|id| users.get(&id.to_string()));
+                let gym = post
+                    .gym_id
+                    .as_ref()
+                    .and_then(|id| gyms.get(&id.to_string()));
+                let gym_seeker = post
+                    .gym_seeker_id
+                    .as_ref()
+                    .and_then(|id| gym_seekers.get(&id.to_string()));
+
+                let profile_picture = if post.is_gym_seeker == Some(true) {
+                    gym_seeker.map(|gym_seeker| gym_seeker.profile_picture.clone())
+                } else if post.is_gym == Some(true) {
+                    gym.map(|gym| gym.profile_picture.clone())
+                } else {
+                    None
+                };
+
+                let location = if post.is_gym == Some(true) {
+                    gym.and_then(|gym| gym.location_id.as_ref())
+                        .and_then(|id| locations.get(&id.to_string()))
+                        .map(|location| FeedLocation {
+                            latitude: location.latitude as f32,
+                            longitude: location.longitude as f32,
+                        })
+                } else {
+                    None
+                };
+
+                FeedModel {
+                    id: post.id.clone(),
+                    content: post.content.unwrap_or_default(),
+                    gym_name: gym.map(|gym| gym.name.clone()),
+                    location,
+                    profile_picture,
+                    post_gallery: Some(
+                        post.id
+                            .as_ref()
+                            .and_then(|id| galleries.remove(&id.to_string()))
+                            .unwrap_or_default(),
+                    ),
+                    updated_at: post.updated_at,
+                    user_type: user.map(|user| user.user_type.clone()).unwrap_or_default(),
+                    username: user.map(|user| user.username.clone()).unwrap_or_default(),
+                }
+            })
+            .collect();
+
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn post(gym: Option<&str>, gym_seeker: Option<&str>, trainer: Option<&str>) -> PostModel {
+        PostModel {
+            id: None,
+            user_id: None,
+            gym_id: gym.map(|id| id.parse().unwrap()),
+            gym_seeker_id: gym_seeker.map(|id| id.parse().unwrap()),
+            trainer_id: trainer.map(|id| id.parse().unwrap()),
+            is_gym: Some(gym.is_some()),
+            is_gym_seeker: Some(gym_seeker.is_some()),
+            is_trainer: Some(trainer.is_some()),
+            content: None,
+            created_at: None,
+            updated_at: None,
+            version: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn own_gym_ranks_above_follows_and_nearby_gyms() {
+        let sources = FeedSources {
+            followed: vec![
+                "gym:followed".parse().unwrap(),
+                "gym_seeker:friend".parse().unwrap(),
+            ],
+            own_gym: Some("gym:own".parse().unwrap()),
+            gym_trainers: vec!["trainer:coach".parse().unwrap()],
+            nearby_gyms: vec!["gym:near".parse().unwrap()],
+        };
+
+        assert_eq!(sources.affinity(&post(Some("gym:own"), None, None)), 3);
+        assert_eq!(
+            sources.affinity(&post(None, None, Some("trainer:coach"))),
+            3
+        );
+        assert_eq!(sources.affinity(&post(Some("gym:followed"), None, None)), 2);
+        assert_eq!(
+            sources.affinity(&post(None, Some("gym_seeker:friend"), None)),
+            2
+        );
+        assert_eq!(sources.affinity(&post(Some("gym:near"), None, None)), 1);
+        assert_eq!(sources.affinity(&post(Some("gym:other"), None, None)), 0);
+        assert!(!sources.is_empty());
+        assert!(FeedSources::default().is_empty());
     }
 }
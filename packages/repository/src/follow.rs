@@ -0,0 +1,235 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    count, paginate, DBClient, FollowModel, GymModel, GymSeekerModel, RepositoryResult,
+    TrainerModel,
+};
+use database::{
+    id::{FollowId, GymId, GymSeekerId, ProfileId, TrainerId, UserId},
+    interface::DBInterface as _,
+    model::{Id, Paginated, PayloadFollowResponse},
+    query::{Cursor, Query},
+};
+use surrealdb::sql::{Datetime, Thing};
+
+#[derive(Clone, Debug)]
+pub struct FollowRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl FollowRepository {
+    /* Store a follow edge, false when the follower already follows the followee */
+    pub async fn insert(&self, data: FollowModel) -> RepositoryResult<bool> {
+        let created: RepositoryResult<Option<Id>> = self
+            .repo
+            .insert_record(FollowId::TABLE.to_string(), data)
+            .await;
+
+        match created {
+            Ok(_) => Ok(true),
+            // The pair index rejects the second edge, also when two requests race
+            Err(errors::Error::DataExist(_)) => Ok(false),
+            Err(error) => Err(error),
+        }
+    }
+
+    /* Remove the follow edge, false when there was none */
+    pub async fn delete(
+        &self,
+        follower: &ProfileId,
+        followee: &ProfileId,
+    ) -> RepositoryResult<bool> {
+        let repo = &self.repo;
+
+        let edges: Vec<FollowModel> = repo
+            .select_where(
+                Query::select("follow")
+                    .eq("follower_id", follower.thing().clone())
+                    .eq("followee_id", followee.thing().clone()),
+            )
+            .await?;
+
+        let mut deleted = false;
+        for id in edges.into_iter().filter_map(|edge| edge.id) {
+            deleted |= repo.delete(id.to_string()).await?;
+        }
+
+        Ok(deleted)
+    }
+
+    /* Whether the profile exists in the table of its user type */
+    pub async fn profile_exists(&self, profile: &ProfileId) -> RepositoryResult<bool> {
+        let found = count(
+            &self.repo,
+            Query::select(profile.user_type()).eq("id", profile.thing().clone()),
+        )
+        .await?;
+
+        Ok(found > 0)
+    }
+
+    /* User owning the profile, None when the profile or its owner does not exist */
+    pub async fn profile_owner(&self, profile: &ProfileId) -> RepositoryResult<Option<UserId>> {
+        let repo = &self.repo;
+        let query = || Query::select(profile.user_type()).eq("id", profile.thing().clone());
+
+        let owner = match profile {
+            ProfileId::Gym(_) => {
+                let gyms: Vec<GymModel> = repo.select_where(query()).await?;
+                gyms.into_iter().next().map(|gym| gym.owner_id)
+            }
+            ProfileId::GymSeeker(_) => {
+                let gym_seekers: Vec<GymSeekerModel> = repo.select_where(query()).await?;
+                gym_seekers
+                    .into_iter()
+                    .next()
+                    .and_then(|gym_seeker| gym_seeker.user_id)
+            }
+            ProfileId::Trainer(_) => {
+                let trainers: Vec<TrainerModel> = repo.select_where(query()).await?;
+                trainers.into_iter().next().map(|trainer| trainer.user_id)
+            }
+        };
+
+        Ok(owner)
+    }
+
+    /* Number of profiles following `profile` and number of profiles it follows */
+    pub async fn counts(&self, profile: &ProfileId) -> RepositoryResult<(u64, u64)> {
+        let followers = count(
+            &self.repo,
+            Query::select("follow").eq("followee_id", profile.thing().clone()),
+        )
+        .await?;
+        let following = count(
+            &self.repo,
+            Query::select("follow").eq("follower_id", profile.thing().clone()),
+        )
+        .await?;
+
+        Ok((followers, following))
+    }
+
+    /* Profiles following `profile`, most recent first */
+    pub async fn followers(
+        &self,
+        profile: &ProfileId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadFollowResponse>> {
+        let edges: Vec<FollowModel> = self
+            .repo
+            .select_where(
+                Query::select("follow")
+                    .eq("followee_id", profile.thing().clone())
+                    .page_by("created_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (edges, next_cursor) = paginate(edges, limit)?;
+
+        let profiles = edges
+            .into_iter()
+            .map(|edge| (edge.follower_id, edge.created_at))
+            .collect();
+        let data = self.profiles(profiles).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Profiles `profile` follows, most recent first */
+    pub async fn following(
+        &self,
+        profile: &ProfileId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadFollowResponse>> {
+        let edges: Vec<FollowModel> = self
+            .repo
+            .select_where(
+                Query::select("follow")
+                    .eq("follower_id", profile.thing().clone())
+                    .page_by("created_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (edges, next_cursor) = paginate(edges, limit)?;
+
+        let profiles = edges
+            .into_iter()
+            .map(|edge| (edge.followee_id, edge.created_at))
+            .collect();
+        let data = self.profiles(profiles).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Every profile `follower` follows */
+    pub async fn followed_by(&self, follower: &ProfileId) -> RepositoryResult<Vec<ProfileId>> {
+        let edges: Vec<FollowModel> = self
+            .repo
+            .select_where(Query::select("follow").eq("follower_id", follower.thing().clone()))
+            .await?;
+
+        Ok(edges.into_iter().map(|edge| edge.followee_id).collect())
+    }
+
+    /* Names and pictures of the listed profiles, one query per profile table */
+    async fn profiles(
+        &self,
+        profiles: Vec<(ProfileId, Datetime)>,
+    ) -> RepositoryResult<Vec<PayloadFollowResponse>> {
+        let repo = &self.repo;
+        let ids = |user_type: &str| -> Vec<Thing> {
+            profiles
+                .iter()
+                .filter(|(id, _)| id.user_type() == user_type)
+                .map(|(id, _)| id.thing().clone())
+                .collect()
+        };
+
+        let gyms: HashMap<String, GymModel> = repo
+            .select_by_ids(GymId::TABLE.to_string(), ids(GymId::TABLE))
+            .await?;
+        let gym_seekers: HashMap<String, GymSeekerModel> = repo
+            .select_by_ids(GymSeekerId::TABLE.to_string(), ids(GymSeekerId::TABLE))
+            .await?;
+        let trainers: HashMap<String, TrainerModel> = repo
+            .select_by_ids(TrainerId::TABLE.to_string(), ids(TrainerId::TABLE))
+            .await?;
+
+        let data = profiles
+            .into_iter()
+            .map(|(id, followed_at)| {
+                let key = id.to_string();
+                let (name, profile_picture) = match &id {
+                    ProfileId::Gym(_) => gyms
+                        .get(&key)
+                        .map(|gym| (Some(gym.name.clone()), Some(gym.profile_picture.clone())))
+                        .unwrap_or_default(),
+                    ProfileId::GymSeeker(_) => gym_seekers
+                        .get(&key)
+                        .map(|gym_seeker| {
+                            (
+                                Some(gym_seeker.name.clone()),
+                                Some(gym_seeker.profile_picture.clone()),
+                            )
+                        })
+                        .unwrap_or_default(),
+                    ProfileId::Trainer(_) => trainers
+                        .get(&key)
+                        .map(|trainer| (trainer.name.clone(), None))
+                        .unwrap_or_default(),
+                };
+
+                PayloadFollowResponse {
+                    id: key,
+                    user_type: id.user_type().to_string(),
+                    name,
+                    profile_picture,
+                    followed_at,
+                }
+            })
+            .collect();
+
+        Ok(data)
+    }
+}
@@ -1,7 +1,14 @@
 use std::sync::Arc;
 
-use super::{DBClient, GymId, GymModel, RepositoryGymRequest, RepositoryResult, UserModel};
-use database::interface::DBInterface as _;
+use super::{
+    DBClient, GymId, GymModel, LocationId, RepositoryGymRequest, RepositoryResult, UserId,
+};
+use database::{
+    interface::DBInterface as _,
+    query::{Condition, Operator, Query},
+    transaction::Transaction,
+};
+use surrealdb::sql::Value;
 
 #[derive(Clone)]
 pub struct GymRepository {
@@ -11,13 +18,13 @@ pub struct GymRepository {
 impl GymRepository {
     pub async fn is_gym_data_empty_by_id(
         &self,
-        id: &str,
+        id: &GymId,
     ) -> RepositoryResult<(bool, Vec<GymModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<GymModel> = repo
-                .select_where("gym".to_owned(), format!("id = {}", id), "*".to_string())
+                .select_where(Query::select("gym").eq("id", id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -27,17 +34,13 @@ impl GymRepository {
 
     pub async fn is_gym_data_empty(
         &self,
-        user_id: &str,
+        user_id: &UserId,
     ) -> RepositoryResult<(bool, Vec<GymModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<GymModel> = repo
-                .select_where(
-                    "gym".to_owned(),
-                    format!("owner_id =  {}", user_id),
-                    "*".to_string(),
-                )
+                .select_where(Query::select("gym").eq("owner_id", user_id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -45,42 +48,20 @@ impl GymRepository {
         Ok(data_exists)
     }
 
-    pub async fn is_gym_data_empty_by_email(
+    pub fn stage_insert(
         &self,
-        email: &str,
-    ) -> RepositoryResult<(bool, Vec<UserModel>)> {
-        let repo = &self.repo;
-
-        let data_exists = {
-            let data: Vec<UserModel> = repo
-                .select_where(
-                    "user".to_owned(),
-                    format!("email = '{}'", email),
-                    "*".to_string(),
-                )
-                .await?;
-            (data.is_empty(), data)
-        };
-
-        Ok(data_exists)
+        transaction: &mut Transaction,
+        data: GymModel,
+    ) -> RepositoryResult<GymId> {
+        let id = transaction.create(GymId::TABLE, data)?;
+        GymId::new(id)
     }
 
-    pub async fn insert_data(&self, data: GymModel) -> RepositoryResult<Option<GymId>> {
-        let repo = &self.repo;
-        let insert_into_gym_tb: Option<GymId> =
-            repo.insert_record(String::from("gym"), data).await?;
-        Ok(insert_into_gym_tb)
-    }
-
-    pub async fn get_details(&self, user_id: &str) -> RepositoryResult<Vec<GymModel>> {
+    pub async fn get_details(&self, user_id: &UserId) -> RepositoryResult<Vec<GymModel>> {
         let repo = &self.repo;
 
         let data: Vec<GymModel> = repo
-            .select_where(
-                "gym".to_owned(),
-                format!("owner_id =  '{}'", user_id),
-                "*".to_string(),
-            )
+            .select_where(Query::select("gym").eq("owner_id", user_id.clone()))
             .await?;
 
         Ok(data)
@@ -88,13 +69,38 @@ impl GymRepository {
 
     pub async fn update_data(
         &self,
-        gym_id: String,
+        gym_id: &GymId,
         data: RepositoryGymRequest,
     ) -> RepositoryResult<bool> {
         let repo = &self.repo;
 
-        let update_data = repo.update_record(gym_id, "gym".to_string(), data).await?;
+        let version = data.version;
+        let update_data = repo
+            .merge_versioned(gym_id.to_string(), GymId::TABLE.to_string(), data, version)
+            .await?;
 
         Ok(update_data)
     }
+
+    /* Gyms at any of the given locations */
+    pub async fn located_at(
+        &self,
+        location_ids: Vec<LocationId>,
+    ) -> RepositoryResult<Vec<GymModel>> {
+        if location_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let location_ids: Vec<Value> = location_ids.into_iter().map(Value::from).collect();
+        let gyms: Vec<GymModel> = self
+            .repo
+            .select_where(Query::select("gym").filter(Condition::compare(
+                "location_id",
+                Operator::In,
+                location_ids,
+            )))
+            .await?;
+
+        Ok(gyms)
+    }
 }
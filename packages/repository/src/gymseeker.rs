@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use super::{
-    DBClient, GymSeekerId, GymSeekerModel, RepositoryGymSeekerRequest, RepositoryResult, UserModel,
+    DBClient, GymSeekerId, GymSeekerModel, RepositoryGymSeekerRequest, RepositoryResult, UserId,
 };
-use database::interface::DBInterface as _;
+use database::{interface::DBInterface as _, query::Query, transaction::Transaction};
 
 #[derive(Clone, Debug)]
 pub struct GymSeekerRepository {
@@ -13,17 +13,13 @@ pub struct GymSeekerRepository {
 impl GymSeekerRepository {
     pub async fn is_gym_seeker_data_empty_by_user_id(
         &self,
-        user_id: &str,
+        user_id: &UserId,
     ) -> RepositoryResult<(bool, Vec<GymSeekerModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<GymSeekerModel> = repo
-                .select_where(
-                    "gym_seeker".to_owned(),
-                    format!("user_id = {}", user_id),
-                    "*".to_string(),
-                )
+                .select_where(Query::select("gym_seeker").eq("user_id", user_id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -33,17 +29,13 @@ impl GymSeekerRepository {
 
     pub async fn is_gym_seeker_data_empty_by_id(
         &self,
-        id: &str,
+        id: &GymSeekerId,
     ) -> RepositoryResult<(bool, Vec<GymSeekerModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<GymSeekerModel> = repo
-                .select_where(
-                    "gym_seeker".to_owned(),
-                    format!("id = {}", id),
-                    "*".to_string(),
-                )
+                .select_where(Query::select("gym_seeker").eq("id", id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -51,41 +43,29 @@ impl GymSeekerRepository {
         Ok(data_exists)
     }
 
-    pub async fn is_gym_seeker_user_empty_by_email(
+    pub fn stage_insert(
         &self,
-        email: &str,
-    ) -> RepositoryResult<(bool, Vec<UserModel>)> {
-        let repo = self.repo.clone();
-
-        let data_exists = {
-            let data: Vec<UserModel> = repo
-                .select_where(
-                    "user".to_owned(),
-                    format!("email = '{}'", email),
-                    "*".to_string(),
-                )
-                .await?;
-            (data.is_empty(), data)
-        };
-
-        Ok(data_exists)
-    }
-
-    pub async fn insert_data(&self, data: GymSeekerModel) -> RepositoryResult<Option<GymSeekerId>> {
-        let repo = &self.repo;
-        let insert_into_gym_tb: Option<GymSeekerId> =
-            repo.insert_record(String::from("gym_seeker"), data).await?;
-        Ok(insert_into_gym_tb)
+        transaction: &mut Transaction,
+        data: GymSeekerModel,
+    ) -> RepositoryResult<GymSeekerId> {
+        let id = transaction.create(GymSeekerId::TABLE, data)?;
+        GymSeekerId::new(id)
     }
 
     pub async fn update_data(
         &self,
-        gymseeker_id: String,
+        gymseeker_id: &GymSeekerId,
         data: RepositoryGymSeekerRequest,
     ) -> RepositoryResult<bool> {
         let repo = &self.repo;
+        let version = data.version;
         let update_data = repo
-            .update_record(gymseeker_id, "gym_seeker".to_string(), data)
+            .merge_versioned(
+                gymseeker_id.to_string(),
+                GymSeekerId::TABLE.to_string(),
+                data,
+                version,
+            )
             .await?;
 
         Ok(update_data)
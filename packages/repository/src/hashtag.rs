@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use super::{DBClient, PostHashtagModel, RepositoryResult};
+use database::{
+    id::{PostHashtagId, PostId},
+    interface::DBInterface as _,
+    model::PayloadHashtagCount,
+    query::{Condition, Operator, Query},
+    transaction::Transaction,
+};
+use surrealdb::sql::Datetime;
+
+#[derive(Clone, Debug)]
+pub struct HashtagRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl HashtagRepository {
+    /* Make `tags` the only hashtags of the post, the old ones are dropped in the same commit */
+    pub async fn replace(
+        &self,
+        post_id: &PostId,
+        tags: Vec<String>,
+        created_at: Datetime,
+    ) -> RepositoryResult<()> {
+        let mut transaction = Transaction::new();
+        transaction.delete_where(
+            PostHashtagId::TABLE,
+            Condition::eq("post_id", post_id.clone()),
+        );
+        for tag in tags {
+            let data = PostHashtagModel {
+                id: None,
+                post_id: post_id.clone(),
+                tag,
+                created_at: created_at.clone(),
+            };
+            transaction.create(PostHashtagId::TABLE, data)?;
+        }
+
+        self.repo.commit(transaction).await
+    }
+
+    /* Drop every hashtag of the post */
+    pub async fn remove(&self, post_id: &PostId) -> RepositoryResult<()> {
+        let mut transaction = Transaction::new();
+        transaction.delete_where(
+            PostHashtagId::TABLE,
+            Condition::eq("post_id", post_id.clone()),
+        );
+
+        self.repo.commit(transaction).await
+    }
+
+    /* Hashtags used by the most posts created since `since`, ties in alphabetical order */
+    pub async fn trending(
+        &self,
+        since: Datetime,
+        limit: u64,
+    ) -> RepositoryResult<Vec<PayloadHashtagCount>> {
+        let mut counts: Vec<PayloadHashtagCount> = self
+            .repo
+            .select_where(
+                Query::select("post_hashtag")
+                    .filter(Condition::compare("created_at", Operator::Gte, since))
+                    .count_by("tag"),
+            )
+            .await?;
+
+        counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        counts.truncate(limit as usize);
+
+        Ok(counts)
+    }
+}
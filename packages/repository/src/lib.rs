@@ -1,45 +1,244 @@
 use database::{
     db::DatabaseClient,
+    id::{GymId, GymSeekerId, LocationId, PostId, TrainerId, UserId},
+    interface::DBInterface as _,
     model::{
-        Feed, Gym, GymSeeker, Id, Location, PayloadGymRequest, PayloadGymSeekerRequest,
-        PayloadLocationRequest, PayloadPostResponse, PayloadTrainerRequest, Post, Trainer, User,
+        ClassBooking, ClassSession, Comment, Conversation, Count, Feed, Follow, Gym, GymSeeker,
+        Location, Membership, MembershipPlan, Mention, Message, Notification, PayloadGymRequest,
+        PayloadGymSeekerRequest, PayloadLocationRequest, PayloadPostResponse,
+        PayloadTrainerRequest, Post, PostHashtag, Reaction, Trainer, User,
     },
+    query::{Cursor, Query},
 };
 use errors::Result;
 
+pub mod class;
+pub mod comment;
 pub mod feed;
+pub mod follow;
 pub mod gym;
 pub mod gymseeker;
+pub mod hashtag;
 pub mod location;
+pub mod membership;
+pub mod mention;
+pub mod message;
+pub mod notification;
 pub mod post;
+pub mod reaction;
 pub mod trainer;
+pub mod transaction;
 pub mod user;
 
 type DBClient = DatabaseClient;
 type RepositoryResult<T> = Result<T>;
 
 type GymModel = Gym;
-type GymId = Id;
 type RepositoryGymRequest = PayloadGymRequest;
 
 type GymSeekerModel = GymSeeker;
-type GymSeekerId = Id;
 type RepositoryGymSeekerRequest = PayloadGymSeekerRequest;
 
 type UserModel = User;
-type UserId = Id;
 
 type TrainerModel = Trainer;
-type TrainerId = Id;
 type RepositoryTrainerRequests = PayloadTrainerRequest;
 
 type PostModel = Post;
-type PostId = Id;
 type RepositoryPostResponse = PayloadPostResponse;
 //type RepositoryPostRequests = PayloadPostRequest;
 
 type LocationModel = Location;
 type RepositoryLocationRequest = PayloadLocationRequest;
-type LocationId = Id;
 
 type FeedModel = Feed;
+
+type FollowModel = Follow;
+
+type ReactionModel = Reaction;
+
+type CommentModel = Comment;
+
+type PostHashtagModel = PostHashtag;
+type MentionModel = Mention;
+
+type NotificationModel = Notification;
+
+type ConversationModel = Conversation;
+type MessageModel = Message;
+
+type MembershipPlanModel = MembershipPlan;
+type MembershipModel = Membership;
+
+type ClassSessionModel = ClassSession;
+type ClassBookingModel = ClassBooking;
+
+/* Records listed newest first, positioned by their sort timestamp and id */
+trait Paged {
+    fn position(&self) -> Option<Cursor>;
+}
+
+impl Paged for PostModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.updated_at.clone()?,
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for CommentModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.created_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for PostHashtagModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.created_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for NotificationModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.created_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for ConversationModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.last_message_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for MessageModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.created_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for MembershipModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.start_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+impl Paged for FollowModel {
+    fn position(&self) -> Option<Cursor> {
+        Some(Cursor {
+            updated_at: self.created_at.clone(),
+            id: self.id.as_ref()?.thing().clone(),
+        })
+    }
+}
+
+/* Keep the first `limit` records, the extra one fetched only tells whether a next page exists */
+fn paginate<T: Paged>(
+    mut records: Vec<T>,
+    limit: u64,
+) -> RepositoryResult<(Vec<T>, Option<String>)> {
+    if records.len() as u64 <= limit {
+        return Ok((records, None));
+    }
+
+    records.truncate(limit as usize);
+    let last = records.last().ok_or_else(|| {
+        errors::Error::DatabaseError(String::from("Cannot paginate with a limit of 0"))
+    })?;
+
+    // More records exist, so a missing sort key is an error rather than the last page
+    let cursor = last.position().ok_or_else(|| {
+        errors::Error::DatabaseError(String::from(
+            "Record has no sort key to continue pagination from",
+        ))
+    })?;
+
+    Ok((records, Some(cursor.encode())))
+}
+
+/* Number of records matching `query` */
+async fn count(repo: &DBClient, query: Query) -> RepositoryResult<u64> {
+    let counts: Vec<Count> = repo.select_where(query.count()).await?;
+    // SurrealDB returns no row at all when nothing matches
+    Ok(counts.first().map(|count| count.count).unwrap_or_default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::sql::{Datetime, Thing};
+
+    fn post(id: &str, updated_at: Option<Datetime>) -> PostModel {
+        PostModel {
+            id: Some(PostId::new(Thing::from(("post", id))).unwrap()),
+            user_id: None,
+            gym_id: None,
+            gym_seeker_id: None,
+            trainer_id: None,
+            is_gym: None,
+            is_gym_seeker: None,
+            is_trainer: None,
+            content: None,
+            created_at: None,
+            updated_at,
+            version: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn last_page_has_no_cursor() {
+        let posts = vec![post("a", Some(Datetime::default()))];
+        let (posts, next_cursor) = paginate(posts, 2).unwrap();
+        assert_eq!(posts.len(), 1);
+        assert_eq!(next_cursor, None);
+    }
+
+    #[test]
+    fn full_page_points_at_its_last_post() {
+        let updated_at = Datetime::default();
+        let posts = vec![
+            post("a", Some(updated_at.clone())),
+            post("b", Some(updated_at.clone())),
+            post("c", Some(updated_at.clone())),
+        ];
+
+        let (posts, next_cursor) = paginate(posts, 2).unwrap();
+        let cursor = Cursor::decode(&next_cursor.unwrap()).unwrap();
+
+        assert_eq!(posts.len(), 2);
+        assert_eq!(cursor.id, Thing::from(("post", "b")));
+        assert_eq!(cursor.updated_at, updated_at);
+    }
+
+    #[test]
+    fn full_page_without_sort_key_is_an_error() {
+        let posts = vec![
+            post("a", Some(Datetime::default())),
+            post("b", None),
+            post("c", None),
+        ];
+        assert!(matches!(
+            paginate(posts, 2),
+            Err(errors::Error::DatabaseError(_))
+        ));
+    }
+}
@@ -1,9 +1,20 @@
 use std::sync::Arc;
 
 use super::{
-    DBClient, LocationId, LocationModel, RepositoryLocationRequest, RepositoryResult, UserModel,
+    DBClient, LocationId, LocationModel, RepositoryLocationRequest, RepositoryResult, UserId,
+    UserModel,
 };
-use database::interface::DBInterface as _;
+use database::model::Id;
+use database::{
+    interface::DBInterface as _,
+    query::{Condition, Operator, Query},
+    transaction::Transaction,
+};
+
+/// Mean radius of the Earth, in kilometres
+const EARTH_RADIUS_KM: f64 = 6371.0;
+/// Length of one degree of latitude, in kilometres
+const KM_PER_DEGREE: f64 = 111.32;
 
 #[derive(Clone, Debug)]
 pub struct LocationRepository {
@@ -14,39 +25,59 @@ impl LocationRepository {
     pub async fn insert_data(
         &self,
         data: LocationModel,
-        user_id: &str,
+        user_id: &UserId,
     ) -> RepositoryResult<Option<LocationId>> {
         if self.is_user_a_gym(user_id).await?.0 {
-            let location_id: Option<LocationId> = self
+            let created: Option<Id> = self
                 .repo
-                .insert_record("location".to_string(), data)
+                .insert_record(LocationId::TABLE.to_string(), data)
                 .await?;
 
-            return Ok(location_id);
+            return created
+                .map(|created| LocationId::new(created.id))
+                .transpose();
         }
         Ok(None)
     }
 
+    pub fn stage_insert(
+        &self,
+        transaction: &mut Transaction,
+        data: LocationModel,
+    ) -> RepositoryResult<LocationId> {
+        let id = transaction.create(LocationId::TABLE, data)?;
+        LocationId::new(id)
+    }
+
     pub async fn update_data(
         &self,
-        location_id: String,
+        location_id: &LocationId,
         data: RepositoryLocationRequest,
     ) -> RepositoryResult<bool> {
+        let version = data.version;
         let updated = self
             .repo
-            .update_record(location_id, "location".to_string(), data)
+            .merge_versioned(
+                location_id.to_string(),
+                LocationId::TABLE.to_string(),
+                data,
+                version,
+            )
             .await?;
 
         Ok(updated)
     }
 
-    pub async fn is_user_a_gym(&self, user_id: &str) -> RepositoryResult<(bool, Vec<UserModel>)> {
+    pub async fn is_user_a_gym(
+        &self,
+        user_id: &UserId,
+    ) -> RepositoryResult<(bool, Vec<UserModel>)> {
         let users: Vec<UserModel> = self
             .repo
             .select_where(
-                "user".to_string(),
-                format!("id = {} and user_type = 'gym'", user_id),
-                "*".to_string(),
+                Query::select("user")
+                    .eq("id", user_id.clone())
+                    .eq("user_type", "gym"),
             )
             .await?;
 
@@ -56,18 +87,110 @@ impl LocationRepository {
 
     pub async fn get_location(
         &self,
-        location_id: &str,
+        location_id: &LocationId,
     ) -> RepositoryResult<(bool, Vec<LocationModel>)> {
         let location: Vec<LocationModel> = self
             .repo
-            .select_where(
-                "location".to_string(),
-                format!("id = {}", location_id),
-                "*".to_string(),
-            )
+            .select_where(Query::select("location").eq("id", location_id.clone()))
             .await?;
 
         let is_location = !location.is_empty(); // Check if the result set is not empty
         Ok((is_location, location))
     }
+
+    /* Locations within `radius_km` of a point, narrowed by a bounding box in the database first */
+    pub async fn nearby(
+        &self,
+        latitude: f64,
+        longitude: f64,
+        radius_km: f64,
+    ) -> RepositoryResult<Vec<LocationModel>> {
+        let delta_latitude = radius_km / KM_PER_DEGREE;
+        let mut query = Query::select("location")
+            .filter(Condition::compare(
+                "latitude",
+                Operator::Gte,
+                latitude - delta_latitude,
+            ))
+            .filter(Condition::compare(
+                "latitude",
+                Operator::Lte,
+                latitude + delta_latitude,
+            ));
+
+        // A box wrapping around the antimeridian or a pole is only narrowed by latitude
+        if let Some(delta_longitude) = longitude_delta(latitude, longitude, radius_km) {
+            query = query
+                .filter(Condition::compare(
+                    "longitude",
+                    Operator::Gte,
+                    longitude - delta_longitude,
+                ))
+                .filter(Condition::compare(
+                    "longitude",
+                    Operator::Lte,
+                    longitude + delta_longitude,
+                ));
+        }
+
+        let locations: Vec<LocationModel> = self.repo.select_where(query).await?;
+
+        Ok(locations
+            .into_iter()
+            .filter(|location| {
+                distance_km(latitude, longitude, location.latitude, location.longitude) <= radius_km
+            })
+            .collect())
+    }
+}
+
+/* Great-circle distance between two points, in kilometres */
+fn distance_km(latitude: f64, longitude: f64, other_latitude: f64, other_longitude: f64) -> f64 {
+    let delta_latitude = (other_latitude - latitude).to_radians();
+    let delta_longitude = (other_longitude - longitude).to_radians();
+
+    let haversine = (delta_latitude / 2.0).sin().powi(2)
+        + latitude.to_radians().cos()
+            * other_latitude.to_radians().cos()
+            * (delta_longitude / 2.0).sin().powi(2);
+
+    2.0 * EARTH_RADIUS_KM * haversine.sqrt().asin()
+}
+
+/* Half the width of the bounding box in degrees of longitude, None when the box would wrap */
+fn longitude_delta(latitude: f64, longitude: f64, radius_km: f64) -> Option<f64> {
+    let scale = latitude.to_radians().cos();
+    if scale <= f64::EPSILON {
+        return None;
+    }
+
+    let delta = radius_km / (KM_PER_DEGREE * scale);
+    (longitude - delta >= -180.0 && longitude + delta <= 180.0).then_some(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn distances_follow_the_great_circle() {
+        assert_eq!(distance_km(52.0, 13.0, 52.0, 13.0), 0.0);
+
+        // Berlin to Paris is about 878 km
+        let berlin_paris = distance_km(52.5200, 13.4050, 48.8566, 2.3522);
+        assert!((berlin_paris - 878.0).abs() < 5.0);
+
+        // One degree of latitude is the same everywhere
+        assert!((distance_km(10.0, 0.0, 11.0, 0.0) - 111.19).abs() < 0.1);
+    }
+
+    #[test]
+    fn longitude_boxes_widen_away_from_the_equator() {
+        let equator = longitude_delta(0.0, 0.0, 10.0).unwrap();
+        let north = longitude_delta(60.0, 0.0, 10.0).unwrap();
+        assert!((north / equator - 2.0).abs() < 1e-9);
+
+        assert_eq!(longitude_delta(0.0, 179.99, 10.0), None);
+        assert_eq!(longitude_delta(90.0, 0.0, 10.0), None);
+    }
 }
@@ -0,0 +1,267 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{paginate, DBClient, MembershipModel, MembershipPlanModel, RepositoryResult};
+use database::{
+    id::{GymId, GymSeekerId, MembershipId, MembershipPlanId},
+    interface::DBInterface as _,
+    model::{
+        Id, MembershipCancellation, MembershipPlanArchive, MembershipRenewal, Paginated,
+        PayloadMembershipPlanRequest, PayloadMembershipPlanResponse, PayloadMembershipResponse,
+    },
+    query::{Condition, Cursor, Direction, Operator, Query},
+};
+use surrealdb::sql::{Datetime, Thing};
+
+#[derive(Clone, Debug)]
+pub struct MembershipRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl MembershipRepository {
+    pub async fn insert_plan(
+        &self,
+        data: MembershipPlanModel,
+    ) -> RepositoryResult<Option<MembershipPlanId>> {
+        let created: Option<Id> = self
+            .repo
+            .insert_record(MembershipPlanId::TABLE.to_string(), data)
+            .await?;
+        created
+            .map(|created| MembershipPlanId::new(created.id))
+            .transpose()
+    }
+
+    pub async fn get_plan(
+        &self,
+        id: &MembershipPlanId,
+    ) -> RepositoryResult<Option<MembershipPlanModel>> {
+        let plans: Vec<MembershipPlanModel> = self
+            .repo
+            .select_where(Query::select("membership_plan").eq("id", id.clone()))
+            .await?;
+
+        Ok(plans.into_iter().next())
+    }
+
+    pub async fn update_plan(
+        &self,
+        id: &MembershipPlanId,
+        data: PayloadMembershipPlanRequest,
+    ) -> RepositoryResult<bool> {
+        let version = data.version;
+        self.repo
+            .merge_versioned(
+                id.to_string(),
+                MembershipPlanId::TABLE.to_string(),
+                data,
+                version,
+            )
+            .await
+    }
+
+    /* Stop selling the plan, memberships already on it keep running */
+    pub async fn archive_plan(
+        &self,
+        id: &MembershipPlanId,
+        archived_at: Datetime,
+    ) -> RepositoryResult<bool> {
+        let data = MembershipPlanArchive { archived_at };
+
+        self.repo
+            .merge_versioned(
+                id.to_string(),
+                MembershipPlanId::TABLE.to_string(),
+                data,
+                None,
+            )
+            .await
+    }
+
+    /* Plans of the gym, cheapest first */
+    pub async fn plans(
+        &self,
+        gym_id: &GymId,
+        include_archived: bool,
+    ) -> RepositoryResult<Vec<PayloadMembershipPlanResponse>> {
+        let mut query = Query::select("membership_plan")
+            .eq("gym_id", gym_id.clone())
+            .order_by("price_cents", Direction::Asc);
+        if !include_archived {
+            query = query.filter(Condition::is_none("archived_at"));
+        }
+
+        let plans: Vec<MembershipPlanModel> = self.repo.select_where(query).await?;
+
+        Ok(plans.into_iter().map(plan_response).collect())
+    }
+
+    pub async fn get(&self, id: &MembershipId) -> RepositoryResult<Option<MembershipModel>> {
+        let memberships: Vec<MembershipModel> = self
+            .repo
+            .select_where(Query::select("membership").eq("id", id.clone()))
+            .await?;
+
+        Ok(memberships.into_iter().next())
+    }
+
+    /* Membership of the gym seeker in the gym, whatever its status */
+    pub async fn find(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+        gym_id: &GymId,
+    ) -> RepositoryResult<Option<MembershipModel>> {
+        let memberships: Vec<MembershipModel> = self
+            .repo
+            .select_where(
+                Query::select("membership")
+                    .eq("gym_seeker_id", gym_seeker_id.clone())
+                    .eq("gym_id", gym_id.clone()),
+            )
+            .await?;
+
+        Ok(memberships.into_iter().next())
+    }
+
+    /* Store a first membership, failing with DataExist when the pair already has one */
+    pub async fn insert_data(
+        &self,
+        data: MembershipModel,
+    ) -> RepositoryResult<Option<MembershipId>> {
+        let created: Option<Id> = self
+            .repo
+            .insert_record(MembershipId::TABLE.to_string(), data)
+            .await?;
+        created
+            .map(|created| MembershipId::new(created.id))
+            .transpose()
+    }
+
+    /* Start the membership over on a new plan, failing with Conflict when it changed since `version` */
+    pub async fn renew(
+        &self,
+        id: &MembershipId,
+        data: MembershipRenewal,
+        version: i64,
+    ) -> RepositoryResult<bool> {
+        self.repo
+            .merge_versioned(
+                id.to_string(),
+                MembershipId::TABLE.to_string(),
+                data,
+                Some(version),
+            )
+            .await
+    }
+
+    /* Cancel the membership, failing with Conflict when it changed since `version` */
+    pub async fn cancel(
+        &self,
+        id: &MembershipId,
+        cancelled_at: Datetime,
+        version: i64,
+    ) -> RepositoryResult<bool> {
+        let data = MembershipCancellation { cancelled_at };
+
+        self.repo
+            .merge_versioned(
+                id.to_string(),
+                MembershipId::TABLE.to_string(),
+                data,
+                Some(version),
+            )
+            .await
+    }
+
+    /* Members of the gym with their status at `now`, the latest to join first */
+    pub async fn by_gym(
+        &self,
+        gym_id: &GymId,
+        active: bool,
+        now: Datetime,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadMembershipResponse>> {
+        let mut query = Query::select("membership").eq("gym_id", gym_id.clone());
+        if active {
+            query = query
+                .filter(Condition::is_none("cancelled_at"))
+                .filter(Condition::compare("end_at", Operator::Gt, now.clone()));
+        }
+
+        let memberships: Vec<MembershipModel> = self
+            .repo
+            .select_where(query.page_by("start_at", cursor.as_ref(), limit + 1))
+            .await?;
+        let (memberships, next_cursor) = paginate(memberships, limit)?;
+
+        let data = self.responses(memberships, &now).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Memberships of the gym seeker in every gym */
+    pub async fn by_gym_seeker(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+    ) -> RepositoryResult<Vec<MembershipModel>> {
+        self.repo
+            .select_where(Query::select("membership").eq("gym_seeker_id", gym_seeker_id.clone()))
+            .await
+    }
+
+    /* Memberships as listed, with the name of their plan and their status at `now` */
+    pub async fn responses(
+        &self,
+        memberships: Vec<MembershipModel>,
+        now: &Datetime,
+    ) -> RepositoryResult<Vec<PayloadMembershipResponse>> {
+        let plans: HashMap<String, MembershipPlanModel> = self
+            .repo
+            .select_by_ids(
+                MembershipPlanId::TABLE.to_string(),
+                memberships
+                    .iter()
+                    .map(|membership| Thing::from(membership.plan_id.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        Ok(memberships
+            .into_iter()
+            .map(|membership| PayloadMembershipResponse {
+                id: membership
+                    .id
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                status: membership.status(now),
+                gym_id: membership.gym_id.to_string(),
+                gym_seeker_id: membership.gym_seeker_id.to_string(),
+                plan_name: plans
+                    .get(&membership.plan_id.to_string())
+                    .map(|plan| plan.name.clone())
+                    .unwrap_or_default(),
+                plan_id: membership.plan_id.to_string(),
+                start_at: membership.start_at,
+                end_at: membership.end_at,
+                cancelled_at: membership.cancelled_at,
+            })
+            .collect())
+    }
+}
+
+/* Plan as listed */
+pub fn plan_response(plan: MembershipPlanModel) -> PayloadMembershipPlanResponse {
+    PayloadMembershipPlanResponse {
+        id: plan.id.map(|id| id.to_string()).unwrap_or_default(),
+        gym_id: plan.gym_id.to_string(),
+        name: plan.name,
+        price_cents: plan.price_cents,
+        duration_days: plan.duration_days,
+        benefits: plan.benefits,
+        archived: plan.archived_at.is_some(),
+        created_at: plan.created_at,
+        updated_at: plan.updated_at,
+        version: plan.version,
+    }
+}
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use super::{DBClient, MentionModel, RepositoryResult, UserModel};
+use database::{
+    id::{MentionId, PostId, UserId},
+    interface::DBInterface as _,
+    model::Id,
+    query::{Condition, Operator, Query},
+};
+use surrealdb::sql::{Datetime, Value};
+
+#[derive(Clone, Debug)]
+pub struct MentionRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl MentionRepository {
+    /* Record the users named in the post, returning the ones it did not mention before */
+    pub async fn record(
+        &self,
+        post_id: &PostId,
+        author: &UserId,
+        usernames: Vec<String>,
+        created_at: Datetime,
+    ) -> RepositoryResult<Vec<UserId>> {
+        if usernames.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let repo = &self.repo;
+        let usernames: Vec<Value> = usernames.into_iter().map(Value::from).collect();
+        let users: Vec<UserModel> = repo
+            .select_where(Query::select("user").filter(Condition::compare(
+                "username",
+                Operator::In,
+                usernames,
+            )))
+            .await?;
+        let existing: Vec<MentionModel> = repo
+            .select_where(Query::select("mention").eq("post_id", post_id.clone()))
+            .await?;
+
+        // Authors mentioning themselves are not recorded
+        let mentioned: Vec<UserId> = users
+            .into_iter()
+            .filter_map(|user| user.id)
+            .filter(|user_id| user_id != author)
+            .filter(|user_id| !existing.iter().any(|mention| &mention.user_id == user_id))
+            .collect();
+        if mentioned.is_empty() {
+            return Ok(mentioned);
+        }
+
+        let data: Vec<MentionModel> = mentioned
+            .iter()
+            .map(|user_id| MentionModel {
+                id: None,
+                post_id: post_id.clone(),
+                user_id: user_id.clone(),
+                created_at: created_at.clone(),
+            })
+            .collect();
+        let _: Vec<Id> = repo
+            .insert_records(MentionId::TABLE.to_string(), data)
+            .await?;
+
+        Ok(mentioned)
+    }
+}
@@ -0,0 +1,296 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{
+    count, paginate, ConversationModel, DBClient, MessageModel, RepositoryResult, UserModel,
+};
+use database::{
+    id::{ConversationId, MessageId, UserId},
+    interface::DBInterface as _,
+    model::{
+        ConversationActivity, Id, MessageCount, MessageRead, Paginated,
+        PayloadConversationResponse, PayloadMessageResponse,
+    },
+    query::{Condition, Cursor, Operator, Query},
+    transaction::Transaction,
+};
+use surrealdb::sql::{Datetime, Thing, Value};
+
+#[derive(Clone, Debug)]
+pub struct MessageRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl MessageRepository {
+    /* Conversation between the two users, created on first use */
+    pub async fn start(
+        &self,
+        user_id: &UserId,
+        other: &UserId,
+        created_at: Datetime,
+    ) -> RepositoryResult<ConversationModel> {
+        let (first, second) = participants(user_id, other);
+        if let Some(conversation) = self.between(first, second).await? {
+            return Ok(conversation);
+        }
+
+        let data = ConversationModel {
+            id: None,
+            first_user_id: first.clone(),
+            second_user_id: second.clone(),
+            created_at: created_at.clone(),
+            last_message_at: created_at,
+        };
+        let created: RepositoryResult<Option<Id>> = self
+            .repo
+            .insert_record(ConversationId::TABLE.to_string(), data)
+            .await;
+
+        match created {
+            // The pair index rejects the second conversation when two requests race
+            Ok(_) | Err(errors::Error::DataExist(_)) => self
+                .between(first, second)
+                .await?
+                .ok_or_else(|| errors::Error::DataNotAvaliable(other.to_string())),
+            Err(error) => Err(error),
+        }
+    }
+
+    pub async fn get_conversation(
+        &self,
+        id: &ConversationId,
+    ) -> RepositoryResult<Option<ConversationModel>> {
+        let conversations: Vec<ConversationModel> = self
+            .repo
+            .select_where(Query::select("conversation").eq("id", id.clone()))
+            .await?;
+
+        Ok(conversations.into_iter().next())
+    }
+
+    /* Conversations of the user, the one with the latest message first */
+    pub async fn conversations(
+        &self,
+        user_id: &UserId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadConversationResponse>> {
+        let repo = &self.repo;
+
+        let conversations: Vec<ConversationModel> = repo
+            .select_where(
+                Query::select("conversation")
+                    .filter(Condition::Or(vec![
+                        Condition::eq("first_user_id", user_id.clone()),
+                        Condition::eq("second_user_id", user_id.clone()),
+                    ]))
+                    .page_by("last_message_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (conversations, next_cursor) = paginate(conversations, limit)?;
+
+        let users: HashMap<String, UserModel> = repo
+            .select_by_ids(
+                UserId::TABLE.to_string(),
+                conversations
+                    .iter()
+                    .map(|conversation| {
+                        Thing::from(conversation.other_participant(user_id).clone())
+                    })
+                    .collect(),
+            )
+            .await?;
+        let unread = self.unread_counts(&conversations, user_id).await?;
+
+        let data = conversations
+            .into_iter()
+            .map(|conversation| {
+                let id = conversation
+                    .id
+                    .as_ref()
+                    .map(|id| id.to_string())
+                    .unwrap_or_default();
+                let other = conversation.other_participant(user_id);
+                let user = users.get(&other.to_string());
+
+                PayloadConversationResponse {
+                    unread_count: unread.get(&id).copied().unwrap_or_default(),
+                    id,
+                    user_id: other.to_string(),
+                    username: user.map(|user| user.username.clone()).unwrap_or_default(),
+                    user_type: user.map(|user| user.user_type.clone()).unwrap_or_default(),
+                    last_message_at: conversation.last_message_at,
+                }
+            })
+            .collect();
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Store the message and move its conversation to the top of the list in one commit */
+    pub async fn insert_message(&self, data: MessageModel) -> RepositoryResult<Option<MessageId>> {
+        let activity = ConversationActivity {
+            last_message_at: data.created_at.clone(),
+        };
+        let conversation_id = data.conversation_id.clone();
+
+        let mut transaction = Transaction::new();
+        let id = transaction.create(MessageId::TABLE, data)?;
+        transaction.merge(conversation_id.into(), activity)?;
+        self.repo.commit(transaction).await?;
+
+        MessageId::new(id).map(Some)
+    }
+
+    /* Messages of the conversation, most recent first */
+    pub async fn messages(
+        &self,
+        conversation_id: &ConversationId,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadMessageResponse>> {
+        let messages: Vec<MessageModel> = self
+            .repo
+            .select_where(
+                Query::select("message")
+                    .eq("conversation_id", conversation_id.clone())
+                    .page_by("created_at", cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (messages, next_cursor) = paginate(messages, limit)?;
+
+        let data = messages.into_iter().map(response).collect();
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    /* Mark the messages `reader` received in the conversation read, returning how many were unread */
+    pub async fn mark_read(
+        &self,
+        conversation_id: &ConversationId,
+        reader: &UserId,
+        read_at: Datetime,
+    ) -> RepositoryResult<u64> {
+        let repo = &self.repo;
+
+        let unread: Vec<MessageModel> = repo
+            .select_where(
+                Query::select("message")
+                    .eq("conversation_id", conversation_id.clone())
+                    .filter(Condition::compare(
+                        "sender_id",
+                        Operator::NotEq,
+                        reader.clone(),
+                    ))
+                    .filter(Condition::is_none("read_at")),
+            )
+            .await?;
+
+        let mut marked = 0;
+        for id in unread.into_iter().filter_map(|message| message.id) {
+            let read = MessageRead {
+                read_at: read_at.clone(),
+            };
+            if repo
+                .merge_record(id.to_string(), MessageId::TABLE.to_string(), read)
+                .await?
+            {
+                marked += 1;
+            }
+        }
+
+        Ok(marked)
+    }
+
+    pub async fn user_exists(&self, user_id: &UserId) -> RepositoryResult<bool> {
+        let found = count(&self.repo, Query::select("user").eq("id", user_id.clone())).await?;
+
+        Ok(found > 0)
+    }
+
+    async fn between(
+        &self,
+        first: &UserId,
+        second: &UserId,
+    ) -> RepositoryResult<Option<ConversationModel>> {
+        let conversations: Vec<ConversationModel> = self
+            .repo
+            .select_where(
+                Query::select("conversation")
+                    .eq("first_user_id", first.clone())
+                    .eq("second_user_id", second.clone()),
+            )
+            .await?;
+
+        Ok(conversations.into_iter().next())
+    }
+
+    /* Messages `reader` has not read, per conversation */
+    async fn unread_counts(
+        &self,
+        conversations: &[ConversationModel],
+        reader: &UserId,
+    ) -> RepositoryResult<HashMap<String, u64>> {
+        let ids: Vec<Value> = conversations
+            .iter()
+            .filter_map(|conversation| conversation.id.clone().map(Value::from))
+            .collect();
+        if ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+
+        let counts: Vec<MessageCount> = self
+            .repo
+            .select_where(
+                Query::select("message")
+                    .filter(Condition::compare("conversation_id", Operator::In, ids))
+                    .filter(Condition::compare(
+                        "sender_id",
+                        Operator::NotEq,
+                        reader.clone(),
+                    ))
+                    .filter(Condition::is_none("read_at"))
+                    .count_by("conversation_id"),
+            )
+            .await?;
+
+        Ok(counts
+            .into_iter()
+            .map(|count| (count.conversation_id.to_string(), count.count))
+            .collect())
+    }
+}
+
+/* Message as listed and pushed to the participants */
+pub fn response(message: MessageModel) -> PayloadMessageResponse {
+    PayloadMessageResponse {
+        id: message.id.map(|id| id.to_string()).unwrap_or_default(),
+        conversation_id: message.conversation_id.to_string(),
+        sender_id: message.sender_id.to_string(),
+        content: message.content,
+        read_at: message.read_at,
+        created_at: message.created_at,
+    }
+}
+
+/* The pair in the order conversations store it */
+fn participants<'a>(user_id: &'a UserId, other: &'a UserId) -> (&'a UserId, &'a UserId) {
+    if user_id <= other {
+        (user_id, other)
+    } else {
+        (other, user_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn both_participants_find_the_same_conversation() {
+        let a: UserId = "user:a".parse().unwrap();
+        let b: UserId = "user:b".parse().unwrap();
+
+        assert_eq!(participants(&a, &b), (&a, &b));
+        assert_eq!(participants(&b, &a), (&a, &b));
+    }
+}
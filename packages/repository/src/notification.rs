@@ -0,0 +1,173 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{count, paginate, DBClient, NotificationModel, RepositoryResult, UserModel};
+use database::{
+    id::{NotificationId, UserId},
+    interface::DBInterface as _,
+    live::LiveStream,
+    model::{Id, NotificationRead, Paginated, PayloadNotificationResponse},
+    query::{Condition, Cursor, Query},
+};
+use surrealdb::sql::{Datetime, Thing};
+
+#[derive(Clone, Debug)]
+pub struct NotificationRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl NotificationRepository {
+    pub async fn insert_data(&self, data: Vec<NotificationModel>) -> RepositoryResult<()> {
+        if data.is_empty() {
+            return Ok(());
+        }
+
+        let _: Vec<Id> = self
+            .repo
+            .insert_records(NotificationId::TABLE.to_string(), data)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn get(&self, id: &NotificationId) -> RepositoryResult<Option<NotificationModel>> {
+        let notifications: Vec<NotificationModel> = self
+            .repo
+            .select_where(Query::select("notification").eq("id", id.clone()))
+            .await?;
+
+        Ok(notifications.into_iter().next())
+    }
+
+    /* Notifications of the user, most recent first */
+    pub async fn by_user(
+        &self,
+        user_id: &UserId,
+        unread: bool,
+        cursor: Option<Cursor>,
+        limit: u64,
+    ) -> RepositoryResult<Paginated<PayloadNotificationResponse>> {
+        let mut query = Query::select("notification").eq("user_id", user_id.clone());
+        if unread {
+            query = query.filter(Condition::is_none("read_at"));
+        }
+
+        let notifications: Vec<NotificationModel> = self
+            .repo
+            .select_where(query.page_by("created_at", cursor.as_ref(), limit + 1))
+            .await?;
+        let (notifications, next_cursor) = paginate(notifications, limit)?;
+        let data = self.responses(notifications).await?;
+
+        Ok(Paginated { data, next_cursor })
+    }
+
+    pub async fn unread_count(&self, user_id: &UserId) -> RepositoryResult<u64> {
+        count(
+            &self.repo,
+            Query::select("notification")
+                .eq("user_id", user_id.clone())
+                .filter(Condition::is_none("read_at")),
+        )
+        .await
+    }
+
+    /* Mark one notification read, false when it already was */
+    pub async fn mark_read(
+        &self,
+        notification: &NotificationModel,
+        read_at: Datetime,
+    ) -> RepositoryResult<bool> {
+        let Some(id) = notification
+            .id
+            .as_ref()
+            .filter(|_| notification.read_at.is_none())
+        else {
+            return Ok(false);
+        };
+
+        self.repo
+            .merge_record(
+                id.to_string(),
+                NotificationId::TABLE.to_string(),
+                NotificationRead { read_at },
+            )
+            .await
+    }
+
+    /* Mark every unread notification of the user read, returning how many were */
+    pub async fn mark_all_read(
+        &self,
+        user_id: &UserId,
+        read_at: Datetime,
+    ) -> RepositoryResult<u64> {
+        let repo = &self.repo;
+
+        let unread: Vec<NotificationModel> = repo
+            .select_where(
+                Query::select("notification")
+                    .eq("user_id", user_id.clone())
+                    .filter(Condition::is_none("read_at")),
+            )
+            .await?;
+
+        let mut marked = 0;
+        for id in unread
+            .into_iter()
+            .filter_map(|notification| notification.id)
+        {
+            let read = NotificationRead {
+                read_at: read_at.clone(),
+            };
+            if repo
+                .merge_record(id.to_string(), NotificationId::TABLE.to_string(), read)
+                .await?
+            {
+                marked += 1;
+            }
+        }
+
+        Ok(marked)
+    }
+
+    /* Every notification written from now on, for every user */
+    pub async fn live(&self) -> RepositoryResult<LiveStream<NotificationModel>> {
+        self.repo.live(NotificationId::TABLE.to_string()).await
+    }
+
+    /* Notifications with the usernames of their actors */
+    pub async fn responses(
+        &self,
+        notifications: Vec<NotificationModel>,
+    ) -> RepositoryResult<Vec<PayloadNotificationResponse>> {
+        let actors: HashMap<String, UserModel> = self
+            .repo
+            .select_by_ids(
+                UserId::TABLE.to_string(),
+                notifications
+                    .iter()
+                    .map(|notification| Thing::from(notification.actor_id.clone()))
+                    .collect(),
+            )
+            .await?;
+
+        let data = notifications
+            .into_iter()
+            .map(|notification| PayloadNotificationResponse {
+                id: notification.id.map(|id| id.to_string()).unwrap_or_default(),
+                kind: notification.kind,
+                actor_username: actors
+                    .get(&notification.actor_id.to_string())
+                    .map(|user| user.username.clone())
+                    .unwrap_or_default(),
+                actor_id: notification.actor_id.to_string(),
+                profile_id: notification.profile_id.map(|id| id.to_string()),
+                post_id: notification.post_id.map(|id| id.to_string()),
+                comment_id: notification.comment_id.map(|id| id.to_string()),
+                read: notification.read_at.is_some(),
+                created_at: notification.created_at,
+            })
+            .collect();
+
+        Ok(data)
+    }
+}
@@ -1,7 +1,15 @@
-use super::{DBClient, PostId, PostModel, RepositoryPostResponse, RepositoryResult};
-use database::interface::DBInterface as _;
-use database::model::{Id, PostGallery};
-use std::sync::Arc;
+use super::{
+    paginate, DBClient, PostId, PostModel, RepositoryPostResponse, RepositoryResult, UserId,
+};
+use database::model::{
+    Id, Paginated, PostContentUpdate, PostDeletion, PostGallery, PostGalleryForFeed,
+};
+use database::{
+    interface::DBInterface as _,
+    query::{Condition, Cursor, Operator, Query},
+};
+use std::{collections::HashMap, sync::Arc};
+use surrealdb::sql::{Datetime, Value};
 
 #[derive(Clone)]
 pub struct PostRepository {
@@ -9,12 +17,15 @@ pub struct PostRepository {
 }
 
 impl PostRepository {
-    pub async fn is_post_empty_by_id(&self, id: &str) -> RepositoryResult<(bool, Vec<PostModel>)> {
+    pub async fn is_post_empty_by_id(
+        &self,
+        id: &PostId,
+    ) -> RepositoryResult<(bool, Vec<PostModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<PostModel> = repo
-                .select_where("post".to_owned(), format!("id = {}", id), "*".to_string())
+                .select_where(visible_posts().eq("id", id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -22,19 +33,25 @@ impl PostRepository {
         Ok(data_exists)
     }
 
+    /* Look a post up whether or not it is soft deleted, restoring needs the deleted ones */
+    pub async fn get_with_deleted(&self, id: &PostId) -> RepositoryResult<Vec<PostModel>> {
+        let data: Vec<PostModel> = self
+            .repo
+            .select_where(Query::select("post").eq("id", id.clone()))
+            .await?;
+
+        Ok(data)
+    }
+
     pub async fn is_post_empty_by_user_id(
         &self,
-        user_id: &str,
+        user_id: &UserId,
     ) -> RepositoryResult<(bool, Vec<PostModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<PostModel> = repo
-                .select_where(
-                    "post".to_owned(),
-                    format!("user_id = {}", user_id),
-                    "*".to_string(),
-                )
+                .select_where(visible_posts().eq("user_id", user_id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -44,62 +61,151 @@ impl PostRepository {
 
     pub async fn insert_data(&self, data: PostModel) -> RepositoryResult<Option<PostId>> {
         let repo = &self.repo;
-        let insert_into_gym_tb: Option<PostId> =
-            repo.insert_record(String::from("post"), data).await?;
-        Ok(insert_into_gym_tb)
+        let created: Option<Id> = repo.insert_record(PostId::TABLE.to_string(), data).await?;
+        created.map(|created| PostId::new(created.id)).transpose()
     }
 
-    pub async fn insert_post_gallery(&self, data: PostGallery) -> RepositoryResult<Option<Id>> {
+    pub async fn insert_post_galleries(&self, data: Vec<PostGallery>) -> RepositoryResult<Vec<Id>> {
         let repo = &self.repo;
-        let insert_into_ontent_gallery_tb: Option<Id> = repo
-            .insert_record(String::from("post_gallery"), data)
+        let insert_into_content_gallery_tb: Vec<Id> = repo
+            .insert_records(String::from("post_gallery"), data)
             .await?;
-        Ok(insert_into_ontent_gallery_tb)
+        Ok(insert_into_content_gallery_tb)
     }
 
-    pub async fn update_data(&self, post_id: String, data: PostModel) -> RepositoryResult<bool> {
+    pub async fn update_data(
+        &self,
+        post_id: &PostId,
+        data: PostContentUpdate,
+        version: Option<i64>,
+    ) -> RepositoryResult<bool> {
         let repo = &self.repo;
 
         let update_data = repo
-            .update_record(post_id, "post".to_string(), data)
+            .merge_versioned(
+                post_id.to_string(),
+                PostId::TABLE.to_string(),
+                data,
+                version,
+            )
             .await?;
 
         Ok(update_data)
     }
 
-    pub async fn delete_data(&self, post_id: String) -> RepositoryResult<bool> {
-        let repo = &self.repo;
+    /* Mark the post deleted, it and its gallery stay stored so it can be restored */
+    pub async fn delete_data(
+        &self,
+        post_id: &PostId,
+        deleted_at: Datetime,
+    ) -> RepositoryResult<bool> {
+        let data = PostDeletion {
+            deleted_at: Some(deleted_at),
+        };
 
-        let update_data = repo.delete(post_id).await?;
+        let deleted = self
+            .repo
+            .merge_versioned(post_id.to_string(), PostId::TABLE.to_string(), data, None)
+            .await?;
 
-        Ok(update_data)
+        Ok(deleted)
+    }
+
+    /* Clear the deletion marker of a soft deleted post */
+    pub async fn restore_data(&self, post_id: &PostId) -> RepositoryResult<bool> {
+        let data = PostDeletion { deleted_at: None };
+
+        let restored = self
+            .repo
+            .merge_versioned(post_id.to_string(), PostId::TABLE.to_string(), data, None)
+            .await?;
+
+        Ok(restored)
     }
 
     pub async fn get_list(
         &self,
-        page: i64,
-        user_id: String,
-    ) -> RepositoryResult<(bool, Vec<RepositoryPostResponse>)> {
-        // Calculate start page based on given page number
-        let start_page = match page {
-            1 => 0,
-            p if p >= 1 => ((page - 1) * 5) + 1,
-            _ => 0,
-        };
-
+        cursor: Option<Cursor>,
+        limit: u64,
+        user_id: &UserId,
+    ) -> RepositoryResult<Paginated<RepositoryPostResponse>> {
         let repo = &self.repo;
 
-        let data = {
-            let data: Vec<RepositoryPostResponse> = repo
-                .select_where(
-                    "post".to_owned(),
-                    format!("user_id = {} LIMIT 5 START {}", user_id, start_page),
-                    "*, (SELECT id,media_url as link FROM post_gallery WHERE content_id == $parent.id) AS post_gallery ".to_string(),
-                )
-                .await?;
-            (data.is_empty(), data)
-        };
+        let posts: Vec<PostModel> = repo
+            .select_where(
+                visible_posts()
+                    .eq("user_id", user_id.clone())
+                    .page(cursor.as_ref(), limit + 1),
+            )
+            .await?;
+        let (posts, next_cursor) = paginate(posts, limit)?;
+        let mut galleries = galleries_by_post(repo, &posts).await?;
+
+        let data: Vec<RepositoryPostResponse> = posts
+            .into_iter()
+            .map(|post| RepositoryPostResponse {
+                post_gallery: Some(
+                    post.id
+                        .as_ref()
+                        .and_then(|id| galleries.remove(&id.to_string()))
+                        .unwrap_or_default(),
+                ),
+                id: post.id,
+                user_id: post.user_id,
+                gym_id: post.gym_id,
+                gym_seeker_id: post.gym_seeker_id,
+                trainer_id: post.trainer_id,
+                is_gym: post.is_gym,
+                is_gym_seeker: post.is_gym_seeker,
+                is_trainer: post.is_trainer,
+                content: post.content,
+                created_at: post.created_at,
+                updated_at: post.updated_at,
+                version: post.version,
+            })
+            .collect();
+
+        Ok(Paginated { data, next_cursor })
+    }
+}
 
-        Ok(data)
+/* Posts that are not soft deleted, every listing starts from here */
+pub(crate) fn visible_posts() -> Query {
+    Query::select("post").filter(Condition::is_none("deleted_at"))
+}
+
+/* Load the gallery of every given post in one query, keyed by `post:id` */
+pub(crate) async fn galleries_by_post(
+    repo: &DBClient,
+    posts: &[PostModel],
+) -> RepositoryResult<HashMap<String, Vec<PostGalleryForFeed>>> {
+    let post_ids: Vec<Value> = posts
+        .iter()
+        .filter_map(|post| post.id.clone().map(Value::from))
+        .collect();
+    if post_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let galleries: Vec<PostGallery> = repo
+        .select_where(Query::select("post_gallery").filter(Condition::compare(
+            "content_id",
+            Operator::In,
+            post_ids,
+        )))
+        .await?;
+
+    let mut by_post: HashMap<String, Vec<PostGalleryForFeed>> = HashMap::new();
+    for gallery in galleries {
+        if let Some(content_id) = gallery.content_id {
+            by_post
+                .entry(content_id.to_string())
+                .or_default()
+                .push(PostGalleryForFeed {
+                    id: gallery.id,
+                    link: gallery.media_url.unwrap_or_default(),
+                });
+        }
     }
+    Ok(by_post)
 }
@@ -0,0 +1,143 @@
+use std::{collections::HashMap, sync::Arc};
+
+use super::{DBClient, ReactionModel, RepositoryResult};
+use database::{
+    id::{PostId, ReactionId, UserId},
+    interface::DBInterface as _,
+    model::{Id, ReactionCount, ReactionKind, ReactionUpdate, Reactions},
+    query::{Condition, Operator, Query},
+};
+use surrealdb::sql::{Datetime, Value};
+
+#[derive(Clone, Debug)]
+pub struct ReactionRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl ReactionRepository {
+    /* Leave or change the user's reaction, false when it already had this kind */
+    pub async fn react(
+        &self,
+        post_id: &PostId,
+        user_id: &UserId,
+        kind: ReactionKind,
+        created_at: Datetime,
+    ) -> RepositoryResult<bool> {
+        let repo = &self.repo;
+
+        let existing: Option<ReactionModel> = repo
+            .select_where(
+                Query::select("reaction")
+                    .eq("post_id", post_id.clone())
+                    .eq("user_id", user_id.clone()),
+            )
+            .await?
+            .pop();
+
+        match existing {
+            Some(reaction) if reaction.kind == kind => Ok(false),
+            Some(reaction) => {
+                let id = reaction
+                    .id
+                    .ok_or_else(|| errors::Error::DataNotAvaliable(post_id.to_string()))?;
+                repo.merge_record(
+                    id.to_string(),
+                    ReactionId::TABLE.to_string(),
+                    ReactionUpdate { kind, created_at },
+                )
+                .await
+            }
+            None => {
+                let data = ReactionModel {
+                    id: None,
+                    post_id: post_id.clone(),
+                    user_id: user_id.clone(),
+                    kind,
+                    created_at,
+                };
+                let created: RepositoryResult<Option<Id>> = repo
+                    .insert_record(ReactionId::TABLE.to_string(), data)
+                    .await;
+
+                match created {
+                    Ok(_) => Ok(true),
+                    // The pair index rejects the second reaction when two requests race
+                    Err(errors::Error::DataExist(_)) => Ok(false),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+
+    /* Remove the user's reaction, false when there was none */
+    pub async fn unreact(&self, post_id: &PostId, user_id: &UserId) -> RepositoryResult<bool> {
+        let repo = &self.repo;
+
+        let reactions: Vec<ReactionModel> = repo
+            .select_where(
+                Query::select("reaction")
+                    .eq("post_id", post_id.clone())
+                    .eq("user_id", user_id.clone()),
+            )
+            .await?;
+
+        let mut deleted = false;
+        for id in reactions.into_iter().filter_map(|reaction| reaction.id) {
+            deleted |= repo.delete(id.to_string()).await?;
+        }
+
+        Ok(deleted)
+    }
+
+    /* Reaction counts of the posts and the viewer's own reactions, keyed by post id */
+    pub async fn summaries(
+        &self,
+        post_ids: &[PostId],
+        viewer: Option<&UserId>,
+    ) -> RepositoryResult<HashMap<String, Reactions>> {
+        let mut summaries: HashMap<String, Reactions> = HashMap::new();
+        if post_ids.is_empty() {
+            return Ok(summaries);
+        }
+
+        let repo = &self.repo;
+        let posts = || {
+            let ids: Vec<Value> = post_ids.iter().cloned().map(Value::from).collect();
+            Condition::compare("post_id", Operator::In, ids)
+        };
+
+        let counts: Vec<ReactionCount> = repo
+            .select_where(
+                Query::select("reaction")
+                    .filter(posts())
+                    .count_by("post_id")
+                    .count_by("kind"),
+            )
+            .await?;
+        for count in counts {
+            summaries
+                .entry(count.post_id.to_string())
+                .or_default()
+                .counts
+                .insert(count.kind, count.count);
+        }
+
+        if let Some(viewer) = viewer {
+            let mine: Vec<ReactionModel> = repo
+                .select_where(
+                    Query::select("reaction")
+                        .filter(posts())
+                        .eq("user_id", viewer.clone()),
+                )
+                .await?;
+            for reaction in mine {
+                summaries
+                    .entry(reaction.post_id.to_string())
+                    .or_default()
+                    .mine = Some(reaction.kind);
+            }
+        }
+
+        Ok(summaries)
+    }
+}
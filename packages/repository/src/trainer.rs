@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
 use super::{
-    DBClient, RepositoryResult, RepositoryTrainerRequests, TrainerId, TrainerModel, UserModel,
+    DBClient, GymId, RepositoryResult, RepositoryTrainerRequests, TrainerId, TrainerModel, UserId,
 };
-use database::interface::DBInterface as _;
+use database::{interface::DBInterface as _, query::Query, transaction::Transaction};
 
 #[derive(Clone)]
 pub struct TrainerRepository {
@@ -13,17 +13,13 @@ pub struct TrainerRepository {
 impl TrainerRepository {
     pub async fn is_trainer_data_empty_by_id(
         &self,
-        id: &str,
+        id: &TrainerId,
     ) -> RepositoryResult<(bool, Vec<TrainerModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<TrainerModel> = repo
-                .select_where(
-                    "trainer".to_owned(),
-                    format!("id = {}", id),
-                    "*".to_string(),
-                )
+                .select_where(Query::select("trainer").eq("id", id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -33,17 +29,13 @@ impl TrainerRepository {
 
     pub async fn is_trainer_data_empty(
         &self,
-        user_id: &str,
+        user_id: &UserId,
     ) -> RepositoryResult<(bool, Vec<TrainerModel>)> {
         let repo = &self.repo;
 
         let data_exists = {
             let data: Vec<TrainerModel> = repo
-                .select_where(
-                    "trainer".to_owned(),
-                    format!("user_id =  {}", user_id),
-                    "*".to_string(),
-                )
+                .select_where(Query::select("trainer").eq("user_id", user_id.clone()))
                 .await?;
             (data.is_empty(), data)
         };
@@ -51,42 +43,20 @@ impl TrainerRepository {
         Ok(data_exists)
     }
 
-    pub async fn is_trainer_data_empty_by_email(
+    pub fn stage_insert(
         &self,
-        email: &str,
-    ) -> RepositoryResult<(bool, Vec<UserModel>)> {
-        let repo = &self.repo;
-
-        let data_exists = {
-            let data: Vec<UserModel> = repo
-                .select_where(
-                    "user".to_owned(),
-                    format!("email = '{}'", email),
-                    "*".to_string(),
-                )
-                .await?;
-            (data.is_empty(), data)
-        };
-
-        Ok(data_exists)
-    }
-
-    pub async fn insert_data(&self, data: TrainerModel) -> RepositoryResult<Option<TrainerId>> {
-        let repo = &self.repo;
-        let insert_into_gym_tb: Option<TrainerId> =
-            repo.insert_record(String::from("trainer"), data).await?;
-        Ok(insert_into_gym_tb)
+        transaction: &mut Transaction,
+        data: TrainerModel,
+    ) -> RepositoryResult<TrainerId> {
+        let id = transaction.create(TrainerId::TABLE, data)?;
+        TrainerId::new(id)
     }
 
-    pub async fn get_details(&self, id: &str) -> RepositoryResult<Vec<TrainerModel>> {
+    pub async fn get_details(&self, id: &TrainerId) -> RepositoryResult<Vec<TrainerModel>> {
         let repo = &self.repo;
 
         let data: Vec<TrainerModel> = repo
-            .select_where(
-                "trainer".to_owned(),
-                format!("id  =  '{}'", id),
-                "*".to_string(),
-            )
+            .select_where(Query::select("trainer").eq("id", id.clone()))
             .await?;
 
         Ok(data)
@@ -94,15 +64,31 @@ impl TrainerRepository {
 
     pub async fn update_data(
         &self,
-        gym_id: String,
+        trainer_id: &TrainerId,
         data: RepositoryTrainerRequests,
     ) -> RepositoryResult<bool> {
         let repo = &self.repo;
 
+        let version = data.version;
         let update_data = repo
-            .update_record(gym_id, "trainer".to_string(), data)
+            .merge_versioned(
+                trainer_id.to_string(),
+                TrainerId::TABLE.to_string(),
+                data,
+                version,
+            )
             .await?;
 
         Ok(update_data)
     }
+
+    /* Trainers based at the gym */
+    pub async fn by_gym(&self, gym_id: &GymId) -> RepositoryResult<Vec<TrainerModel>> {
+        let data: Vec<TrainerModel> = self
+            .repo
+            .select_where(Query::select("trainer").eq("gym_id", gym_id.clone()))
+            .await?;
+
+        Ok(data)
+    }
 }
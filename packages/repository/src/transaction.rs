@@ -0,0 +1,16 @@
+use std::sync::Arc;
+
+use super::{DBClient, RepositoryResult};
+use database::{interface::DBInterface as _, transaction::Transaction};
+
+#[derive(Clone, Debug)]
+pub struct TransactionRepository {
+    pub repo: Arc<DBClient>,
+}
+
+impl TransactionRepository {
+    /* Apply the writes staged by every repository's `stage_insert` together */
+    pub async fn commit(&self, transaction: Transaction) -> RepositoryResult<()> {
+        self.repo.commit(transaction).await
+    }
+}
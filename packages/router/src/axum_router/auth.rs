@@ -55,7 +55,7 @@ pub async fn login_user(
     let is_valid = match PasswordHash::new(&user.password) {
         Ok(parsed_hash) => Argon2::default()
             .verify_password(body.password.as_bytes(), &parsed_hash)
-            .map_or(false, |_| true),
+            .is_ok(),
         Err(_) => false,
     };
 
@@ -65,13 +65,13 @@ pub async fn login_user(
 
     let user_id = {
         if user.user_type == "gym" {
-            let data = gym_svc.profile_details(user.id.to_string()).await?;
+            let data = gym_svc.profile_details_by_user(&user.id).await?;
             data.id
         } else if user.user_type == "gym_seeker" {
-            let data = gymseeker_svc.profile_details(user.id.to_string()).await?;
+            let data = gymseeker_svc.profile_details_by_user(&user.id).await?;
             data.id
         } else if user.user_type == "trainer" {
-            let data = trainer_svc.profile_details(user.id.to_string()).await?;
+            let data = trainer_svc.profile_details_by_user(&user.id).await?;
             data.id
         } else {
             return Err(errors::Error::DataExist(format!("{} not found", user.id)));
@@ -0,0 +1,166 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::{if_match::IfMatch, jwt_auth::JWTAuthMiddleware};
+use database::{
+    id::{ClassSessionId, GymId},
+    model::{PayloadClassSessionRequest, PayloadIdResponses, ScheduleFilter},
+};
+
+pub async fn create(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Json(payload): Json<PayloadClassSessionRequest>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let session_id = class_svc
+        .create(jwt.entity_id.gym()?, payload)
+        .await?
+        .ok_or_else(|| errors::Error::DatabaseError(String::from("Class was not created")))?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": PayloadIdResponses {
+            id: session_id.to_string(),
+        }
+    })))
+}
+
+pub async fn update(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(session_id): Path<String>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadClassSessionRequest>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+    payload.version = if_match.version(payload.version)?;
+
+    let session_id: ClassSessionId = session_id.parse()?;
+    class_svc
+        .update(jwt.entity_id.gym()?, &session_id, payload)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": {}
+    })))
+}
+
+pub async fn cancel(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let session_id: ClassSessionId = session_id.parse()?;
+    let is_success = class_svc.cancel(jwt.entity_id.gym()?, &session_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
+
+/* Gym seekers booked into one of the caller's classes */
+pub async fn attendees(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let session_id: ClassSessionId = session_id.parse()?;
+    let attendees = class_svc
+        .attendees(jwt.entity_id.gym()?, &session_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": attendees
+    })))
+}
+
+/* Upcoming classes of a gym, marking the ones a gym seeker caller has booked */
+pub async fn schedule(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(gym_id): Path<String>,
+    Query(filter): Query<ScheduleFilter>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let gym_id: GymId = gym_id.parse()?;
+    let days = filter.days();
+    let sessions = class_svc
+        .schedule(&gym_id, jwt.entity_id.gym_seeker().ok(), filter.from, days)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": sessions
+    })))
+}
+
+pub async fn book(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let session_id: ClassSessionId = session_id.parse()?;
+    let booking_id = class_svc
+        .book(jwt.entity_id.gym_seeker()?, &session_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": PayloadIdResponses {
+            id: booking_id.to_string(),
+        }
+    })))
+}
+
+pub async fn cancel_booking(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(session_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let session_id: ClassSessionId = session_id.parse()?;
+    let is_success = class_svc
+        .cancel_booking(jwt.entity_id.gym_seeker()?, &session_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
+
+/* Upcoming classes the caller holds a seat in */
+pub async fn bookings(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+) -> Result<impl IntoResponse> {
+    let class_svc = &app_state.class_services;
+
+    let sessions = class_svc.bookings(jwt.entity_id.gym_seeker()?).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": sessions
+    })))
+}
@@ -0,0 +1,118 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::{if_match::IfMatch, jwt_auth::JWTAuthMiddleware};
+use database::{
+    id::{CommentId, PostId},
+    model::{Pagination, PayloadCommentRequest, PayloadCommentUpdateRequest, PayloadIdResponses},
+};
+
+pub async fn create(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(content_id): Path<String>,
+    Json(payload): Json<PayloadCommentRequest>,
+) -> Result<impl IntoResponse> {
+    let comment_svc = &app_state.comment_services;
+
+    let content_id: PostId = content_id.parse()?;
+    let parent_id: Option<CommentId> = payload.parent_id.as_deref().map(str::parse).transpose()?;
+    let comment_id = comment_svc
+        .create(&jwt.user_id, &content_id, payload.content, parent_id)
+        .await?
+        .ok_or_else(|| errors::Error::DatabaseError(String::from("Comment was not created")))?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": PayloadIdResponses {
+            id: comment_id.to_string(),
+        }
+    })))
+}
+
+pub async fn update(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(comment_id): Path<String>,
+    if_match: IfMatch,
+    Json(payload): Json<PayloadCommentUpdateRequest>,
+) -> Result<impl IntoResponse> {
+    let comment_svc = &app_state.comment_services;
+    let version = if_match.version(payload.version)?;
+
+    let comment_id: CommentId = comment_id.parse()?;
+    comment_svc
+        .update(&jwt.user_id, &comment_id, payload.content, version)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": {}
+    })))
+}
+
+pub async fn delete(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(comment_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let comment_svc = &app_state.comment_services;
+
+    let comment_id: CommentId = comment_id.parse()?;
+    let is_success = comment_svc.delete(&jwt.user_id, &comment_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
+
+pub async fn get(
+    State(app_state): State<Arc<AppState>>,
+    Extension(_jwt): Extension<JWTAuthMiddleware>,
+    Path(content_id): Path<String>,
+    Query(params): Query<Pagination>,
+) -> Result<impl IntoResponse> {
+    let comment_svc = &app_state.comment_services;
+    let limit = params.limit(app_state.environment.page_size);
+
+    let content_id: PostId = content_id.parse()?;
+    let page = comment_svc
+        .get_list(&content_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn replies(
+    State(app_state): State<Arc<AppState>>,
+    Extension(_jwt): Extension<JWTAuthMiddleware>,
+    Path(comment_id): Path<String>,
+    Query(params): Query<Pagination>,
+) -> Result<impl IntoResponse> {
+    let comment_svc = &app_state.comment_services;
+    let limit = params.limit(app_state.environment.page_size);
+
+    let comment_id: CommentId = comment_id.parse()?;
+    let page = comment_svc
+        .replies(&comment_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
@@ -2,12 +2,16 @@ use std::sync::Arc;
 
 use axum::{
     extract::{Query, State},
-    response::IntoResponse,
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
     Extension, Json,
 };
+use futures::StreamExt;
 use serde_json::json;
 
-use database::model::Pagination;
+use database::model::{FeedMode, FeedView, Pagination};
 use state::axum_state::AppState;
 
 use super::midleware::jwt_auth::JWTAuthMiddleware;
@@ -15,21 +19,44 @@ use errors::Result;
 
 pub async fn get_profile(
     State(app_state): State<Arc<AppState>>, // Extract application state
-    Extension(_jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
-    Query(params): Query<Pagination>,       // Extract the `page` query parameter
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
+    Query(view): Query<FeedView>,           // Extract the `mode` query parameter
 ) -> Result<impl IntoResponse> {
     // Retrieve the gym service from the application state
     let svc = &app_state.feed_services;
 
-    // Default to page 0 if `page` parameter is not provided
-    let page = params.page.unwrap_or(0);
+    // Fall back to the configured page size if `limit` is not provided
+    let limit = params.limit(app_state.environment.page_size);
 
-    // Fetch data based on the specified page
-    let data = svc.get_list(page).await?;
+    // Fetch the page following the given cursor, or the first page without one
+    let page = match view.mode {
+        FeedMode::Home => {
+            svc.get_home(&jwt.entity_id, &jwt.user_id, params.cursor, limit)
+                .await?
+        }
+        FeedMode::Explore => svc.get_list(&jwt.user_id, params.cursor, limit).await?,
+    };
 
     // Return a JSON response with the profile data
     Ok(Json(json!({
         "status": "success",
-        "data": data
+        "data": page.data,
+        "next_cursor": page.next_cursor
     })))
 }
+
+pub async fn stream(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(_jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+) -> Result<impl IntoResponse> {
+    // Retrieve the feed service from the application state
+    let svc = &app_state.feed_services;
+
+    // Subscribe to posts created, updated or deleted from now on
+    let events = svc.stream().await?;
+
+    // Send each change as an SSE event named after it, pinging idle connections so proxies keep them open
+    let events = events.map(|event| Event::default().event(event.name()).json_data(event.data()));
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -0,0 +1,89 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::jwt_auth::JWTAuthMiddleware;
+use database::{id::ProfileId, model::Pagination};
+
+pub async fn follow(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(profile_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let follow_svc = &app_state.follow_services;
+
+    let profile_id: ProfileId = profile_id.parse()?;
+    let is_new = follow_svc
+        .follow(&jwt.user_id, &jwt.entity_id, &profile_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_new
+    })))
+}
+
+pub async fn unfollow(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(profile_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let follow_svc = &app_state.follow_services;
+
+    let profile_id: ProfileId = profile_id.parse()?;
+    follow_svc.unfollow(&jwt.entity_id, &profile_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": {}
+    })))
+}
+
+pub async fn followers(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(_jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Path(profile_id): Path<String>,         // Extract the profile whose followers are listed
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
+) -> Result<impl IntoResponse> {
+    let follow_svc = &app_state.follow_services;
+
+    let profile_id: ProfileId = profile_id.parse()?;
+    let limit = params.limit(app_state.environment.page_size);
+    let page = follow_svc
+        .followers(&profile_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn following(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(_jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Path(profile_id): Path<String>, // Extract the profile whose followed profiles are listed
+    Query(params): Query<Pagination>, // Extract the `cursor` and `limit` query parameters
+) -> Result<impl IntoResponse> {
+    let follow_svc = &app_state.follow_services;
+
+    let profile_id: ProfileId = profile_id.parse()?;
+    let limit = params.limit(app_state.environment.page_size);
+    let page = follow_svc
+        .following(&profile_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
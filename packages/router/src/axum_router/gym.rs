@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
-use super::midleware::jwt_auth::JWTAuthMiddleware;
+use super::midleware::{
+    if_match::{etag, IfMatch},
+    jwt_auth::JWTAuthMiddleware,
+};
 use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
-use axum::{extract::State, response::IntoResponse, Extension, Json};
+use axum::{extract::State, http::header, response::IntoResponse, Extension, Json};
 use database::model::{
     PayloadGymProfileResponses, PayloadGymRequest, PayloadIdResponses, PayloadUser, User,
 };
@@ -40,11 +43,11 @@ pub async fn register(
 
     // Register user profile
     let gym_svc = &app_state.gym_services;
-    let user_id = gym_svc.register_profile(user.clone()).await?.unwrap();
+    let user_id = gym_svc.register_profile(user.clone()).await?;
 
     // Create response payload
     let payload_id_responses = PayloadIdResponses {
-        id: format!("{}:{}", user_id.id.tb, user_id.id.id),
+        id: user_id.to_string(),
     };
 
     EmailServices::send_verification_email(&user.username, &user.email, &verify_token).await?;
@@ -62,7 +65,7 @@ pub async fn get_profile(
     // Retrieve the gym service from the application state
     let svc = &app_state.gym_services;
 
-    let (is_empty, _) = svc.is_gym_data_empty_by_id(&jwt.entity_id).await?;
+    let (is_empty, _) = svc.is_gym_data_empty_by_id(jwt.entity_id.gym()?).await?;
 
     if is_empty {
         return Err(errors::Error::DataNotAvaliable(format!(
@@ -72,7 +75,7 @@ pub async fn get_profile(
     }
 
     // Fetch profile details using the entity ID from the JWT
-    let data = svc.profile_details(jwt.entity_id).await?;
+    let data = svc.profile_details(jwt.entity_id.gym()?).await?;
 
     // Construct the response payload with profile details
     let profile = PayloadGymProfileResponses {
@@ -81,32 +84,33 @@ pub async fn get_profile(
         profile_picture: data.profile_picture,
         created_at: data.created_at,
         updated_at: data.updated_at,
+        followers_count: data.followers_count,
+        following_count: data.following_count,
+        version: data.version,
     };
 
     // Return a JSON response with the profile data
-    Ok(Json(json!({
-        "status": "success",
-        "data": profile
-    })))
+    let etag = etag(profile.version);
+    Ok((
+        [(header::ETAG, etag)],
+        Json(json!({
+            "status": "success",
+            "data": profile
+        })),
+    ))
 }
 
 pub async fn update_profile(
     State(app_state): State<Arc<AppState>>,
     Extension(jwt): Extension<JWTAuthMiddleware>,
-    payload: Json<PayloadGymRequest>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadGymRequest>,
 ) -> Result<impl IntoResponse> {
-    let svc = &app_state.gym_services;
-
-    let (is_empty, _) = svc.is_gym_data_empty_by_id(&jwt.entity_id).await?;
+    payload.version = if_match.version(payload.version)?;
 
-    if is_empty {
-        return Err(errors::Error::DataNotAvaliable(format!(
-            "user {} not available",
-            &jwt.entity_id
-        )));
-    }
+    let svc = &app_state.gym_services;
 
-    svc.update_profile(&payload, &jwt.entity_id).await?;
+    svc.update_profile(&payload, jwt.entity_id.gym()?).await?;
 
     Ok(Json(json!({
         "status": "success",
@@ -1,7 +1,7 @@
 use std::sync::Arc;
 
 use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
-use axum::{extract::State, response::IntoResponse, Extension, Json};
+use axum::{extract::State, http::header, response::IntoResponse, Extension, Json};
 use chrono::Utc;
 use rand_core::OsRng;
 use serde_json::json;
@@ -9,7 +9,10 @@ use services::email::EmailServices;
 use state::axum_state::AppState;
 use uuid::Uuid;
 
-use super::midleware::jwt_auth::JWTAuthMiddleware;
+use super::midleware::{
+    if_match::{etag, IfMatch},
+    jwt_auth::JWTAuthMiddleware,
+};
 use database::model::{
     PayloadGymSeekerProfileResponse, PayloadGymSeekerRequest, PayloadIdResponses, PayloadUser, User,
 };
@@ -45,11 +48,11 @@ pub async fn register(
 
     // Register user profile
     let svc = &app_state.gymseeker_services;
-    let user_id = svc.register_profile(&user).await?.unwrap();
+    let user_id = svc.register_profile(&user).await?;
 
     // Create response payload
     let payload_id_responses = PayloadIdResponses {
-        id: format!("{}:{}", user_id.id.tb, user_id.id.id),
+        id: user_id.to_string(),
     };
 
     EmailServices::send_verification_email(&user.username, &user.email, &verify_token).await?;
@@ -67,7 +70,9 @@ pub async fn get_profile(
     // Get profile details
     let svc = &app_state.gymseeker_services;
 
-    let (is_empty, _) = svc.is_gym_seeker_data_empty_by_id(&jwt.entity_id).await?;
+    let (is_empty, _) = svc
+        .is_gym_seeker_data_empty_by_id(jwt.entity_id.gym_seeker()?)
+        .await?;
 
     if is_empty {
         return Err(errors::Error::DataNotAvaliable(format!(
@@ -76,7 +81,7 @@ pub async fn get_profile(
         )));
     }
 
-    let data = svc.profile_details(jwt.entity_id).await?;
+    let data = svc.profile_details(jwt.entity_id.gym_seeker()?).await?;
     let payload = PayloadGymSeekerProfileResponse {
         birth_date: data.birth_date,
         sex: data.sex,
@@ -89,30 +94,33 @@ pub async fn get_profile(
         created_at: data.created_at,
         updated_at: data.updated_at,
         name: data.name,
+        followers_count: data.followers_count,
+        following_count: data.following_count,
+        version: data.version,
     };
 
-    Ok(Json(json!({
-        "status": "success",
-        "data":payload
-    })))
+    let etag = etag(payload.version);
+    Ok((
+        [(header::ETAG, etag)],
+        Json(json!({
+            "status": "success",
+            "data": payload
+        })),
+    ))
 }
 
 pub async fn update_profile(
     State(app_state): State<Arc<AppState>>,
     Extension(jwt): Extension<JWTAuthMiddleware>,
-    payload: Json<PayloadGymSeekerRequest>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadGymSeekerRequest>,
 ) -> Result<impl IntoResponse> {
-    let svc = &app_state.gymseeker_services;
-    let (is_empty, _) = svc.is_gym_seeker_data_empty_by_id(&jwt.entity_id).await?;
+    payload.version = if_match.version(payload.version)?;
 
-    if is_empty {
-        return Err(errors::Error::DataNotAvaliable(format!(
-            "user {} not available",
-            &jwt.entity_id
-        )));
-    }
+    let svc = &app_state.gymseeker_services;
 
-    svc.update_profile(&payload, &jwt.entity_id).await?;
+    svc.update_profile(&payload, jwt.entity_id.gym_seeker()?)
+        .await?;
 
     Ok(Json(json!({
         "status": "success",
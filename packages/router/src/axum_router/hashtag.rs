@@ -0,0 +1,49 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::jwt_auth::JWTAuthMiddleware;
+use database::model::{Pagination, TrendingWindow};
+
+pub async fn trending(
+    State(app_state): State<Arc<AppState>>,
+    Extension(_jwt): Extension<JWTAuthMiddleware>,
+    Query(window): Query<TrendingWindow>,
+) -> Result<impl IntoResponse> {
+    let hashtag_svc = &app_state.hashtag_services;
+
+    let limit = window.limit(app_state.environment.page_size);
+    let hashtags = hashtag_svc.trending(window.hours(), limit).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": hashtags
+    })))
+}
+
+pub async fn posts(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(tag): Path<String>,
+    Query(params): Query<Pagination>,
+) -> Result<impl IntoResponse> {
+    let feed_svc = &app_state.feed_services;
+
+    let limit = params.limit(app_state.environment.page_size);
+    let page = feed_svc
+        .get_by_hashtag(&jwt.user_id, &tag, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
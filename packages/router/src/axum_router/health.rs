@@ -0,0 +1,52 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::IntoResponse,
+    Json,
+};
+use serde_json::json;
+
+use state::axum_state::AppState;
+
+pub async fn check(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let svc = &app_state.health_services;
+
+    // Both dependencies are checked on every call so the response shows which one is down
+    let database = svc.database().await;
+    let redis = svc.redis().await;
+
+    // Error details stay in the logs, this endpoint is reachable without authentication
+    let describe = |dependency: &str, result: &errors::Result<()>| match result {
+        Ok(()) => "ok",
+        Err(error) => {
+            tracing::error!("Health check for {} failed: {}", dependency, error);
+            "unavailable"
+        }
+    };
+
+    let (status_code, status) = if database.is_ok() && redis.is_ok() {
+        (StatusCode::OK, "success")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "failed")
+    };
+
+    (
+        status_code,
+        Json(json!({
+            "status": status,
+            "database": describe("database", &database),
+            "redis": describe("redis", &redis)
+        })),
+    )
+}
+
+pub async fn metrics(State(app_state): State<Arc<AppState>>) -> impl IntoResponse {
+    let svc = &app_state.health_services;
+
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        svc.metrics(),
+    )
+}
@@ -7,33 +7,32 @@ use serde_json::json;
 
 use database::model::PayloadLocationRequest;
 
-use super::midleware::jwt_auth::JWTAuthMiddleware;
+use super::midleware::{if_match::IfMatch, jwt_auth::JWTAuthMiddleware};
 use errors::Result;
 use state::axum_state::AppState;
 
 pub async fn update_location(
     State(app_state): State<Arc<AppState>>,
     Extension(jwt): Extension<JWTAuthMiddleware>,
-    payload: Json<PayloadLocationRequest>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadLocationRequest>,
 ) -> Result<impl IntoResponse> {
+    payload.version = if_match.version(payload.version)?;
+
     let gym_svc = &app_state.gym_services;
     let location_svc = &app_state.location_services;
-    println!("{}", &jwt.entity_id);
+    let gym_id = jwt.entity_id.gym()?;
 
-    let (is_empty, gym_temp) = gym_svc.is_gym_data_empty_by_id(&jwt.entity_id).await?;
+    let (is_empty, _) = gym_svc.is_gym_data_empty_by_id(gym_id).await?;
 
     if is_empty {
         return Err(errors::Error::DataNotAvaliable(format!(
             "user {} not available",
-            &jwt.entity_id
+            gym_id
         )));
     }
 
-    let gym = gym_temp.first().unwrap();
-
-    location_svc
-        .update_location(&payload, &gym.id.clone().unwrap().to_string())
-        .await?;
+    location_svc.update_location(&payload, gym_id).await?;
 
     Ok(Json(json!({
         "status": "success",
@@ -0,0 +1,185 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::{if_match::IfMatch, jwt_auth::JWTAuthMiddleware};
+use database::{
+    id::{GymId, MembershipId, MembershipPlanId},
+    model::{
+        MembershipFilter, Pagination, PayloadIdResponses, PayloadMembershipPlanRequest,
+        PayloadMembershipRequest,
+    },
+};
+
+pub async fn create_plan(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Json(payload): Json<PayloadMembershipPlanRequest>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let plan_id = membership_svc
+        .create_plan(jwt.entity_id.gym()?, payload)
+        .await?
+        .ok_or_else(|| errors::Error::DatabaseError(String::from("Plan was not created")))?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": PayloadIdResponses {
+            id: plan_id.to_string(),
+        }
+    })))
+}
+
+pub async fn update_plan(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(plan_id): Path<String>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadMembershipPlanRequest>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+    payload.version = if_match.version(payload.version)?;
+
+    let plan_id: MembershipPlanId = plan_id.parse()?;
+    membership_svc
+        .update_plan(jwt.entity_id.gym()?, &plan_id, payload)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": {}
+    })))
+}
+
+pub async fn archive_plan(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(plan_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let plan_id: MembershipPlanId = plan_id.parse()?;
+    let is_success = membership_svc
+        .archive_plan(jwt.entity_id.gym()?, &plan_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
+
+/* Plans of the caller's gym, archived ones included */
+pub async fn own_plans(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let plans = membership_svc.plans(jwt.entity_id.gym()?, true).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": plans
+    })))
+}
+
+/* Plans a gym currently sells */
+pub async fn plans(
+    State(app_state): State<Arc<AppState>>,
+    Extension(_jwt): Extension<JWTAuthMiddleware>,
+    Path(gym_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let gym_id: GymId = gym_id.parse()?;
+    let plans = membership_svc.plans(&gym_id, false).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": plans
+    })))
+}
+
+pub async fn members(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Query(params): Query<Pagination>,
+    Query(filter): Query<MembershipFilter>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+    let limit = params.limit(app_state.environment.page_size);
+
+    let page = membership_svc
+        .members(jwt.entity_id.gym()?, filter.active, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn join(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Json(payload): Json<PayloadMembershipRequest>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let plan_id: MembershipPlanId = payload.plan_id.parse()?;
+    let membership_id = membership_svc
+        .join(&jwt.user_id, jwt.entity_id.gym_seeker()?, &plan_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": PayloadIdResponses {
+            id: membership_id.to_string(),
+        }
+    })))
+}
+
+pub async fn memberships(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let memberships = membership_svc
+        .memberships(jwt.entity_id.gym_seeker()?)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": memberships
+    })))
+}
+
+/* Cancel a membership, by the member or by the gym */
+pub async fn cancel(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(membership_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let membership_svc = &app_state.membership_services;
+
+    let membership_id: MembershipId = membership_id.parse()?;
+    let is_success = membership_svc
+        .cancel(&jwt.user_id, &jwt.entity_id, &membership_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
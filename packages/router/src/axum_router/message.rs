@@ -0,0 +1,189 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Path, Query, State,
+    },
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use futures::{stream, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use state::axum_state::AppState;
+
+use super::midleware::jwt_auth::JWTAuthMiddleware;
+use database::{
+    id::{ConversationId, UserId},
+    model::{Pagination, PayloadChatRequest, PayloadConversationRequest},
+};
+use services::message::MessageServices;
+
+pub async fn start(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Json(payload): Json<PayloadConversationRequest>,
+) -> Result<impl IntoResponse> {
+    let message_svc = &app_state.message_services;
+
+    let other: UserId = payload.user_id.parse()?;
+    let conversation_id = message_svc.start(&jwt.user_id, &other).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": { "id": conversation_id.to_string() }
+    })))
+}
+
+pub async fn conversations(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
+) -> Result<impl IntoResponse> {
+    let message_svc = &app_state.message_services;
+
+    let limit = params.limit(app_state.environment.page_size);
+    let page = message_svc
+        .conversations(&jwt.user_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn messages(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Path(conversation_id): Path<String>,    // Extract the conversation whose messages are listed
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
+) -> Result<impl IntoResponse> {
+    let message_svc = &app_state.message_services;
+
+    let conversation_id: ConversationId = conversation_id.parse()?;
+    let limit = params.limit(app_state.environment.page_size);
+    let page = message_svc
+        .messages(&jwt.user_id, &conversation_id, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn mark_read(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(conversation_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let message_svc = &app_state.message_services;
+
+    let conversation_id: ConversationId = conversation_id.parse()?;
+    let marked = message_svc
+        .mark_read(&jwt.user_id, &conversation_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": { "marked": marked }
+    })))
+}
+
+pub async fn socket(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    upgrade: WebSocketUpgrade,              // Extract the WebSocket handshake
+) -> impl IntoResponse {
+    let message_svc = app_state.message_services.clone();
+
+    // The token was checked by the auth middleware before the connection is upgraded
+    upgrade.on_upgrade(move |socket| chat(socket, message_svc, jwt.user_id))
+}
+
+/* Frame read from the client or event to write to it, None once either side is done */
+enum Frame {
+    Client(Option<Message>),
+    Server(Option<Value>),
+}
+
+async fn chat(socket: WebSocket, message_svc: MessageServices, user_id: UserId) {
+    let (mut writer, reader) = socket.split();
+
+    let events = match message_svc.subscribe(&user_id).await {
+        Ok(events) => events,
+        Err(error) => {
+            let _ = writer.send(error_frame(&error)).await;
+            let _ = writer.close().await;
+            return;
+        }
+    };
+
+    // A read error ends the connection like a close frame does
+    let incoming = reader
+        .map(|frame| Frame::Client(frame.ok()))
+        .chain(stream::once(async { Frame::Client(None) }));
+    let outgoing = events
+        .map(|event| Frame::Server(Some(json!(event))))
+        .chain(stream::once(async { Frame::Server(None) }));
+    let mut frames = stream::select(incoming, outgoing).boxed();
+
+    while let Some(frame) = frames.next().await {
+        let reply = match frame {
+            Frame::Client(Some(Message::Text(text))) => {
+                match handle(&message_svc, &user_id, &text).await {
+                    Ok(()) => continue,
+                    Err(error) => error_frame(&error),
+                }
+            }
+            Frame::Client(Some(Message::Close(_)) | None) => break,
+            Frame::Client(Some(_)) => continue,
+            Frame::Server(Some(event)) => Message::Text(event.to_string()),
+            // The shared subscription ended, the client reconnects to get a new one
+            Frame::Server(None) => break,
+        };
+
+        if writer.send(reply).await.is_err() {
+            break;
+        }
+    }
+
+    let _ = writer.close().await;
+}
+
+/* Act on a frame of the client, results reach it as events published to its sockets */
+async fn handle(message_svc: &MessageServices, user_id: &UserId, text: &str) -> Result<()> {
+    let request: PayloadChatRequest = serde_json::from_str(text)
+        .map_err(|error| errors::Error::InvalidRequest(error.to_string()))?;
+
+    match request {
+        PayloadChatRequest::Message {
+            conversation_id,
+            content,
+        } => {
+            message_svc
+                .send(user_id, &conversation_id.parse()?, content)
+                .await?;
+        }
+        PayloadChatRequest::Typing { conversation_id } => {
+            message_svc
+                .typing(user_id, &conversation_id.parse()?)
+                .await?;
+        }
+        PayloadChatRequest::Read { conversation_id } => {
+            message_svc
+                .mark_read(user_id, &conversation_id.parse()?)
+                .await?;
+        }
+    }
+
+    Ok(())
+}
+
+fn error_frame(error: &errors::Error) -> Message {
+    Message::Text(json!({ "type": "error", "error": error.message() }).to_string())
+}
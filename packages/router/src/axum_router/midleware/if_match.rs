@@ -0,0 +1,95 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+
+use errors::{Error, Result};
+
+/* Version the client last read, taken from the `If-Match` header */
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct IfMatch(pub Option<i64>);
+
+impl IfMatch {
+    /* Combine the header with a `version` sent in the body, both must agree when present */
+    pub fn version(self, body: Option<i64>) -> Result<Option<i64>> {
+        match (self.0, body) {
+            (Some(header), Some(body)) if header != body => Err(Error::InvalidVersion(format!(
+                "If-Match {} with body version {}",
+                header, body
+            ))),
+            (header, body) => Ok(header.or(body)),
+        }
+    }
+}
+
+#[async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for IfMatch {
+    type Rejection = Error;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self> {
+        let Some(value) = parts.headers.get(header::IF_MATCH) else {
+            return Ok(IfMatch(None));
+        };
+        let value = value
+            .to_str()
+            .map_err(|error| Error::InvalidVersion(error.to_string()))?;
+
+        parse(value).map(IfMatch)
+    }
+}
+
+/* Parse an entity tag such as `"3"` or `W/"3"`, `*` matches any version */
+fn parse(value: &str) -> Result<Option<i64>> {
+    let value = value.trim();
+    if value == "*" {
+        return Ok(None);
+    }
+
+    let tag = value.strip_prefix("W/").unwrap_or(value);
+    let tag = tag
+        .strip_prefix('"')
+        .and_then(|tag| tag.strip_suffix('"'))
+        .unwrap_or(tag);
+
+    tag.parse::<i64>()
+        .map(Some)
+        .map_err(|_| Error::InvalidVersion(value.to_string()))
+}
+
+/* Entity tag of a version, the counterpart of `parse` */
+pub fn etag(version: i64) -> String {
+    format!("\"{}\"", version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_strong_weak_and_bare_tags() {
+        assert_eq!(parse("\"3\"").unwrap(), Some(3));
+        assert_eq!(parse("W/\"4\"").unwrap(), Some(4));
+        assert_eq!(parse(" 5 ").unwrap(), Some(5));
+        assert_eq!(parse("*").unwrap(), None);
+        assert_eq!(parse(&etag(7)).unwrap(), Some(7));
+    }
+
+    #[test]
+    fn rejects_tags_that_are_not_versions() {
+        assert!(matches!(parse("\"abc\""), Err(Error::InvalidVersion(_))));
+        assert!(matches!(parse(""), Err(Error::InvalidVersion(_))));
+    }
+
+    #[test]
+    fn header_and_body_versions_must_agree() {
+        assert_eq!(IfMatch(Some(2)).version(None).unwrap(), Some(2));
+        assert_eq!(IfMatch(None).version(Some(3)).unwrap(), Some(3));
+        assert_eq!(IfMatch(Some(4)).version(Some(4)).unwrap(), Some(4));
+        assert_eq!(IfMatch(None).version(None).unwrap(), None);
+        assert!(matches!(
+            IfMatch(Some(4)).version(Some(5)),
+            Err(Error::InvalidVersion(_))
+        ));
+    }
+}
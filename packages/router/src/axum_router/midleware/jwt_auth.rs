@@ -10,6 +10,7 @@ use axum::{
 
 use authorization::{self};
 use axum_extra::extract::cookie::CookieJar;
+use database::id::{ProfileId, UserId};
 use environment::Environment;
 use errors::{self, Result};
 use redis::AsyncCommands;
@@ -18,10 +19,10 @@ use state::axum_state::AppState;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct JWTAuthMiddleware {
-    pub entity_id: String,
+    /// Profile the user acts as, its variant carries the user type
+    pub entity_id: ProfileId,
     pub access_token_uuid: uuid::Uuid,
-    pub user_type: String,
-    pub user_id: String,
+    pub user_id: UserId,
 }
 
 pub async fn auth(
@@ -98,13 +99,19 @@ pub async fn auth(
         }
     };
 
-    let user_type = access_token_details.user_type;
-    let user_id = access_token_details.main_user_id;
+    // Ids that do not parse into their tables come from a tampered or stale session
+    let entity_id: ProfileId = entity_id
+        .parse()
+        .map_err(|_| errors::Error::TokenError("fail: Invalid token".to_string()))?;
+    let user_id: UserId = access_token_details
+        .main_user_id
+        .parse()
+        .map_err(|_| errors::Error::TokenError("fail: Invalid token".to_string()))?;
+
     // Insert authenticated user details into request extensions
     req.extensions_mut().insert(JWTAuthMiddleware {
         access_token_uuid,
         entity_id,
-        user_type,
         user_id,
     });
 
@@ -1 +1,2 @@
+pub mod if_match;
 pub mod jwt_auth;
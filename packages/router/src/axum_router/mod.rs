@@ -1,9 +1,18 @@
 pub mod auth;
+pub mod class;
+pub mod comment;
 pub mod feed;
+pub mod follow;
 pub mod gym;
 pub mod gymseeker;
+pub mod hashtag;
+pub mod health;
 pub mod location;
+pub mod membership;
+pub mod message;
 pub mod midleware;
+pub mod notification;
 pub mod post;
+pub mod reaction;
 pub mod trainer;
 pub mod upload;
@@ -0,0 +1,104 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        IntoResponse,
+    },
+    Extension, Json,
+};
+use errors::Result;
+use futures::StreamExt;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::jwt_auth::JWTAuthMiddleware;
+use database::{
+    id::NotificationId,
+    model::{NotificationFilter, Pagination},
+};
+
+pub async fn get(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
+    Query(filter): Query<NotificationFilter>, // Extract the `unread` query parameter
+) -> Result<impl IntoResponse> {
+    let notification_svc = &app_state.notification_services;
+
+    let limit = params.limit(app_state.environment.page_size);
+    let page = notification_svc
+        .get_list(&jwt.user_id, filter.unread, params.cursor, limit)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": page.data,
+        "next_cursor": page.next_cursor
+    })))
+}
+
+pub async fn unread_count(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+) -> Result<impl IntoResponse> {
+    let notification_svc = &app_state.notification_services;
+
+    let count = notification_svc.unread_count(&jwt.user_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": { "unread_count": count }
+    })))
+}
+
+pub async fn mark_read(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(notification_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let notification_svc = &app_state.notification_services;
+
+    let notification_id: NotificationId = notification_id.parse()?;
+    let is_changed = notification_svc
+        .mark_read(&jwt.user_id, &notification_id)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_changed
+    })))
+}
+
+pub async fn mark_all_read(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+) -> Result<impl IntoResponse> {
+    let notification_svc = &app_state.notification_services;
+
+    let marked = notification_svc.mark_all_read(&jwt.user_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": { "marked": marked }
+    })))
+}
+
+pub async fn stream(
+    State(app_state): State<Arc<AppState>>, // Extract application state
+    Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
+) -> Result<impl IntoResponse> {
+    let notification_svc = &app_state.notification_services;
+
+    // Subscribe to the caller's notifications written from now on
+    let notifications = notification_svc.stream(&jwt.user_id).await?;
+
+    // Send each one as a `notification` event, pinging idle connections so proxies keep them open
+    let events = notifications.map(|notification| {
+        Event::default()
+            .event("notification")
+            .json_data(notification)
+    });
+    Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
@@ -9,9 +9,10 @@ use errors::Result;
 use serde_json::json;
 use state::axum_state::AppState;
 
-use super::midleware::jwt_auth::JWTAuthMiddleware;
-use database::model::{
-    Pagination, PayloadIdResponses, PayloadPostRequest, PayloadPostUpdateRequest,
+use super::midleware::{if_match::IfMatch, jwt_auth::JWTAuthMiddleware};
+use database::{
+    id::PostId,
+    model::{Pagination, PayloadIdResponses, PayloadPostRequest, PayloadPostUpdateRequest},
 };
 
 pub async fn create(
@@ -22,14 +23,11 @@ pub async fn create(
     // Register user profile
     let post_svc = &app_state.post_services;
     let content = payload.clone().content.clone().unwrap();
-    let content_id = post_svc
-        .create(jwt.entity_id, &jwt.user_type, content)
-        .await?
-        .unwrap();
+    let content_id = post_svc.create(&jwt.entity_id, content).await?.unwrap();
 
     // Create response payload
     let payload_id_responses = PayloadIdResponses {
-        id: format!("{}:{}", content_id.id.tb, content_id.id.id),
+        id: content_id.to_string(),
     };
 
     Ok(Json(json!({
@@ -41,16 +39,15 @@ pub async fn create(
 pub async fn update(
     State(app_state): State<Arc<AppState>>,
     Extension(jwt): Extension<JWTAuthMiddleware>,
-    payload: Json<PayloadPostUpdateRequest>,
+    if_match: IfMatch,
+    Json(payload): Json<PayloadPostUpdateRequest>,
 ) -> Result<impl IntoResponse> {
     let post_svc = &app_state.post_services;
+    let version = if_match.version(payload.version)?;
+    let content_id: PostId = payload.content_id.parse()?;
 
     post_svc
-        .update(
-            jwt.user_id,
-            payload.content_id.clone(),
-            payload.content.clone(),
-        )
+        .update(&jwt.user_id, &content_id, payload.content, version)
         .await?;
 
     Ok(Json(json!({
@@ -66,7 +63,24 @@ pub async fn delete(
 ) -> Result<impl IntoResponse> {
     let post_svc = &app_state.post_services;
 
-    let is_success = post_svc.delete(jwt.user_id, content_id).await?;
+    let content_id: PostId = content_id.parse()?;
+    let is_success = post_svc.delete(&jwt.user_id, &content_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_success
+    })))
+}
+
+pub async fn restore(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(content_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let post_svc = &app_state.post_services;
+
+    let content_id: PostId = content_id.parse()?;
+    let is_success = post_svc.restore(&jwt.user_id, &content_id).await?;
 
     Ok(Json(json!({
         "status": "success",
@@ -77,20 +91,21 @@ pub async fn delete(
 pub async fn get(
     State(app_state): State<Arc<AppState>>, // Extract application state
     Extension(jwt): Extension<JWTAuthMiddleware>, // Extract JWT authentication details
-    Query(params): Query<Pagination>,       // Extract the `page` query parameter
+    Query(params): Query<Pagination>,       // Extract the `cursor` and `limit` query parameters
 ) -> Result<impl IntoResponse> {
     // Retrieve the gym service from the application state
     let svc = &app_state.post_services;
 
-    // Default to page 0 if `page` parameter is not provided
-    let page = params.page.unwrap_or(0);
+    // Fall back to the configured page size if `limit` is not provided
+    let limit = params.limit(app_state.environment.page_size);
 
-    // Fetch data based on the specified page
-    let data = svc.get_list(jwt.user_id, page).await?;
+    // Fetch the page following the given cursor, or the first page without one
+    let page = svc.get_list(&jwt.user_id, params.cursor, limit).await?;
 
     // Return a JSON response with the profile data
     Ok(Json(json!({
         "status": "success",
-        "data": data
+        "data": page.data,
+        "next_cursor": page.next_cursor
     })))
 }
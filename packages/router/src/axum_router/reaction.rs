@@ -0,0 +1,48 @@
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    response::IntoResponse,
+    Extension, Json,
+};
+use errors::Result;
+use serde_json::json;
+use state::axum_state::AppState;
+
+use super::midleware::jwt_auth::JWTAuthMiddleware;
+use database::{id::PostId, model::PayloadReactionRequest};
+
+pub async fn react(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(content_id): Path<String>,
+    Json(payload): Json<PayloadReactionRequest>,
+) -> Result<impl IntoResponse> {
+    let reaction_svc = &app_state.reaction_services;
+
+    let content_id: PostId = content_id.parse()?;
+    let is_changed = reaction_svc
+        .react(&jwt.user_id, &content_id, payload.kind)
+        .await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": is_changed
+    })))
+}
+
+pub async fn unreact(
+    State(app_state): State<Arc<AppState>>,
+    Extension(jwt): Extension<JWTAuthMiddleware>,
+    Path(content_id): Path<String>,
+) -> Result<impl IntoResponse> {
+    let reaction_svc = &app_state.reaction_services;
+
+    let content_id: PostId = content_id.parse()?;
+    reaction_svc.unreact(&jwt.user_id, &content_id).await?;
+
+    Ok(Json(json!({
+        "status": "success",
+        "data": {}
+    })))
+}
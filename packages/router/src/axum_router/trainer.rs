@@ -1,8 +1,11 @@
 use std::sync::Arc;
 
-use super::midleware::jwt_auth::JWTAuthMiddleware;
+use super::midleware::{
+    if_match::{etag, IfMatch},
+    jwt_auth::JWTAuthMiddleware,
+};
 use argon2::{password_hash::SaltString, Argon2, PasswordHasher};
-use axum::{extract::State, response::IntoResponse, Extension, Json};
+use axum::{extract::State, http::header, response::IntoResponse, Extension, Json};
 use database::model::{
     PayloadIdResponses, PayloadTrainerProfileResponse, PayloadTrainerRequest, PayloadUser, User,
 };
@@ -18,11 +21,9 @@ pub async fn register(
     Extension(jwt): Extension<JWTAuthMiddleware>,
     payload: Json<PayloadUser>,
 ) -> Result<impl IntoResponse> {
-    if jwt.user_type != "gym" {
-        return Err(errors::Error::InvalidUserType(String::from(
-            "Only gym users can register trainers",
-        )));
-    }
+    // Only gym users can register trainers, into their own gym
+    let gym_id = jwt.entity_id.gym()?;
+
     // Generate salt and hash the password
     let salt = SaltString::generate(&mut OsRng);
     let hashed_password = Argon2::default()
@@ -46,14 +47,11 @@ pub async fn register(
 
     // Register user profile
     let trainer_svc = &app_state.trainer_services;
-    let user_id = trainer_svc
-        .register_profile(user.clone(), jwt.entity_id)
-        .await?
-        .unwrap();
+    let user_id = trainer_svc.register_profile(user.clone(), gym_id).await?;
 
     // Create response payload
     let payload_id_responses = PayloadIdResponses {
-        id: format!("{}:{}", user_id.id.tb, user_id.id.id),
+        id: user_id.to_string(),
     };
 
     EmailServices::send_verification_email(&user.username, &user.email, &verify_token).await?;
@@ -70,7 +68,9 @@ pub async fn get_profile(
 ) -> Result<impl IntoResponse> {
     // Retrieve the gym service from the application state
     let svc = &app_state.trainer_services;
-    let (is_empty, _) = svc.is_trainer_data_empty_by_id(&jwt.entity_id).await?;
+    let (is_empty, _) = svc
+        .is_trainer_data_empty_by_id(jwt.entity_id.trainer()?)
+        .await?;
 
     if is_empty {
         return Err(errors::Error::DataNotAvaliable(format!(
@@ -79,7 +79,7 @@ pub async fn get_profile(
         )));
     }
     // Fetch profile details using the entity ID from the JWT
-    let data = svc.profile_details(jwt.entity_id).await?;
+    let data = svc.profile_details(jwt.entity_id.trainer()?).await?;
 
     // Construct the response payload with profile details
     let profile = PayloadTrainerProfileResponse {
@@ -89,32 +89,34 @@ pub async fn get_profile(
         expertise: data.expertise,
         created_at: data.created_at,
         updated_at: data.updated_at,
+        followers_count: data.followers_count,
+        following_count: data.following_count,
+        version: data.version,
     };
 
     // Return a JSON response with the profile data
-    Ok(Json(json!({
-        "status": "success",
-        "data": profile
-    })))
+    let etag = etag(profile.version);
+    Ok((
+        [(header::ETAG, etag)],
+        Json(json!({
+            "status": "success",
+            "data": profile
+        })),
+    ))
 }
 
 pub async fn update_profile(
     State(app_state): State<Arc<AppState>>,
     Extension(jwt): Extension<JWTAuthMiddleware>,
-    payload: Json<PayloadTrainerRequest>,
+    if_match: IfMatch,
+    Json(mut payload): Json<PayloadTrainerRequest>,
 ) -> Result<impl IntoResponse> {
-    let svc = &app_state.trainer_services;
+    payload.version = if_match.version(payload.version)?;
 
-    let (is_empty, _) = svc.is_trainer_data_empty_by_id(&jwt.entity_id).await?;
-
-    if is_empty {
-        return Err(errors::Error::DataNotAvaliable(format!(
-            "user {} not available",
-            &jwt.entity_id
-        )));
-    }
+    let svc = &app_state.trainer_services;
 
-    svc.update_profile(&payload, &jwt.entity_id).await?;
+    svc.update_profile(&payload, jwt.entity_id.trainer()?)
+        .await?;
 
     Ok(Json(json!({
         "status": "success",
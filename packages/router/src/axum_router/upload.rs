@@ -5,15 +5,17 @@ use axum::{
     Extension,
 };
 use chrono::Utc;
-use database::model::{ContentGalery, PayloadGymRequest, PayloadGymSeekerRequest, PostGallery};
+use database::{
+    id::{PostId, ProfileId},
+    model::{ContentGalery, PayloadGymRequest, PayloadGymSeekerRequest, PostGallery},
+};
 use environment::Environment;
 use errors::Result;
 use file_storage::interface::FileStorageInterface;
-use futures::{stream::FuturesUnordered, StreamExt};
 use serde_json::json;
 use state::axum_state::AppState;
 use std::sync::Arc;
-use tracing::{error, info};
+use tracing::info;
 use uuid::Uuid;
 
 pub async fn upload_images(
@@ -25,8 +27,8 @@ pub async fn upload_images(
 ) -> Result<impl IntoResponse> {
     // Retrieve environment and user type
     let environment = Environment::new().env;
-    let user_type = jwt.user_type;
-    let user_id = jwt.entity_id.replace(":", "_");
+    let user_type = jwt.entity_id.user_type();
+    let user_id = jwt.entity_id.to_string().replace(":", "_");
     let id = jwt.entity_id;
     let main_user_id = jwt.user_id;
     let gym_svc = &app_state.gym_services;
@@ -71,84 +73,75 @@ pub async fn upload_images(
     }
 
     let profile_picture = uploaded_files.first().unwrap().to_string();
-    let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
     match gallery_type.as_str() {
-        "profile" => match user_type.as_str() {
-            "gym" => {
-                let gym_profile = gym_svc.profile_details(id.clone()).await?;
+        "profile" => match &id {
+            ProfileId::Gym(gym_id) => {
                 let payload = PayloadGymRequest {
-                    name: Some(gym_profile.name),
-                    description: Some(gym_profile.description),
+                    name: None,
+                    description: None,
                     profile_picture: Some(profile_picture.clone()),
-                    owner_id: gym_profile.owner_id,
-                    location_id: gym_profile.location_id,
-                    created_at: gym_profile.created_at,
-                    updated_at: Some(time_now),
+                    owner_id: None,
+                    location_id: None,
+                    created_at: None,
+                    updated_at: None,
+                    version: None,
                 };
-                gym_svc.update_profile(&payload, &id).await?;
+                gym_svc.update_profile(&payload, gym_id).await?;
             }
-            "gym_seeker" => {
-                let gymseeker_profile = gymseeker_svc.profile_details(id.clone()).await?;
+            ProfileId::GymSeeker(gym_seeker_id) => {
                 let payload = PayloadGymSeekerRequest {
-                    birth_date: gymseeker_profile.birth_date,
-                    sex: Some(gymseeker_profile.sex),
-                    user_id: gymseeker_profile.user_id,
+                    birth_date: None,
+                    sex: None,
+                    user_id: None,
                     profile_picture: Some(profile_picture.clone()),
-                    fitness_goals: Some(gymseeker_profile.fitness_goals),
-                    preferred_workout_time: Some(gymseeker_profile.preferred_workout_time),
-                    gym_preferences: Some(gymseeker_profile.gym_preferences),
-                    membership_status: Some(gymseeker_profile.membership_status),
-                    bio: Some(gymseeker_profile.bio),
-                    created_at: gymseeker_profile.created_at,
-                    updated_at: Some(time_now),
-                    name: Some(gymseeker_profile.name),
+                    fitness_goals: None,
+                    preferred_workout_time: None,
+                    gym_preferences: None,
+                    bio: None,
+                    created_at: None,
+                    updated_at: None,
+                    name: None,
+                    version: None,
                 };
-                gymseeker_svc.update_profile(&payload, &id).await?;
+                gymseeker_svc
+                    .update_profile(&payload, gym_seeker_id)
+                    .await?;
             }
-            _ => (),
+            ProfileId::Trainer(_) => (),
         },
         "gallery" => {
-            let content_id = params.content_id.unwrap_or("".to_string());
+            let content_id: PostId = params.content_id.unwrap_or_default().parse()?;
             let (is_empty, post) = post_svc.is_post_data_empty_by_id(&content_id).await?;
             if is_empty {
                 return Err(errors::Error::DataNotAvaliable(format!(
-                    "post:{} not available",
+                    "{} not available",
                     &content_id
                 )));
             }
 
-            println!("{}", id);
             let post_id = post.first().unwrap().id.clone();
-            let post_user_id = post.first().unwrap().user_id.clone().unwrap().to_string();
+            let post_user_id = post.first().unwrap().user_id.clone();
 
-            if main_user_id != post_user_id {
+            if post_user_id.as_ref() != Some(&main_user_id) {
                 return Err(errors::Error::UserUnauthorized(
                     "You are not authorized to update this post".to_string(),
                 ));
             }
 
-            uploaded_files
+            let post_galleries: Vec<PostGallery> = uploaded_files
                 .iter()
-                .map(|link| {
-                    let post_gallery = PostGallery {
-                        id: None,
-                        content_id: post_id.clone(),
-                        media_url: Some(link.clone()),
-                        created_at: None,
-                    };
-
-                    // Asynchronously insert each `post_gallery` entry
-                    post_svc.insert_post_gallery(post_gallery)
-                })
-                .collect::<FuturesUnordered<_>>() // Collect to handle concurrent futures
-                .for_each(|result| async move {
-                    match result {
-                        Ok(_) => info!("Insert successful"), // Log success
-                        Err(e) => error!("Error inserting post gallery: {:?}", e),
-                    }
+                .map(|link| PostGallery {
+                    id: None,
+                    content_id: post_id.clone(),
+                    media_url: Some(link.clone()),
+                    created_at: None,
                 })
-                .await;
+                .collect();
+
+            // All images of the post are inserted in one round-trip
+            let inserted = post_svc.insert_post_galleries(post_galleries).await?;
+            info!("Inserted {} post gallery entries", inserted.len());
         }
         _ => {
             println!("Unknown type");
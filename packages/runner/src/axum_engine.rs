@@ -2,35 +2,54 @@ use super::{routes::build_routes, FileStorage, StoragePlatform};
 use database::{
     self,
     db::{Connection, Sources},
+    migrations::Migrator,
 };
 use environment::Environment;
 use errors::Result;
 use redis::Client;
 use services::{
-    auth::AuthServices, email::EmailServices, feed::FeedServices, gym::GymServices,
-    gymseeker::GymSeekerServices, location::LocationServices, post::PostServices,
+    auth::AuthServices,
+    cache::Cache,
+    class::ClassServices,
+    comment::CommentServices,
+    email::EmailServices,
+    feed::{FeedEvents, FeedServices},
+    follow::FollowServices,
+    gym::GymServices,
+    gymseeker::GymSeekerServices,
+    hashtag::HashtagServices,
+    health::HealthServices,
+    location::LocationServices,
+    membership::MembershipServices,
+    message::{MessageEvents, MessageServices},
+    notification::{NotificationEvents, NotificationServices},
+    post::PostServices,
+    reaction::ReactionServices,
     trainer::TrainerServices,
 };
 use state::axum_state::AppState;
 use std::sync::Arc;
 
 use repository::{
-    feed::FeedRepository, gym::GymRepository, gymseeker::GymSeekerRepository,
-    location::LocationRepository, post::PostRepository, trainer::TrainerRepository,
-    user::UserRepository,
+    class::ClassRepository, comment::CommentRepository, feed::FeedRepository,
+    follow::FollowRepository, gym::GymRepository, gymseeker::GymSeekerRepository,
+    hashtag::HashtagRepository, location::LocationRepository, membership::MembershipRepository,
+    mention::MentionRepository, message::MessageRepository, notification::NotificationRepository,
+    post::PostRepository, reaction::ReactionRepository, trainer::TrainerRepository,
+    transaction::TransactionRepository, user::UserRepository,
 };
 
 pub async fn run() -> Result<()> {
-    let mut surreal_db = database::db::DatabaseSource {
-        db_type: database::db::DatabaseType::SurrealDB,
+    let environment = Environment::new();
+
+    let mut database_source = database::db::DatabaseSource {
+        db_type: database::db::DatabaseType::from_environment(&environment)?,
     };
 
     let cloud_storage = FileStorage {
         platform: StoragePlatform::Google,
     };
 
-    let environment = Environment::new();
-
     let redis_url = format!(
         "redis://{}:{}@{}:{}",
         environment.redis_username,
@@ -50,16 +69,38 @@ pub async fn run() -> Result<()> {
         }
     };
     // Connect to the database
-    let conn = Arc::new(surreal_db.connect().await?);
-    let ping_db = conn.ping();
+    let conn = Arc::new(database_source.connect().await?);
 
-    if ping_db == *"Pong!" {
-        println!("✅ {} from database!", ping_db);
-    } else {
-        println!("🔥 {} from database!", ping_db);
+    let health_services = HealthServices {
+        repo: conn.clone(),
+        redis_client: redis_client.clone(),
+    };
+
+    match health_services.database().await {
+        Ok(()) => println!("✅ Pong! from database!"),
+        Err(e) => {
+            println!("🔥 {} from database!", e);
+            std::process::exit(1);
+        }
+    }
+
+    if let Err(e) = health_services.redis().await {
+        println!("🔥 Error connecting to Redis: {}", e);
         std::process::exit(1);
     }
 
+    // Bring the schema up to date before any request can touch it
+    match conn.migrate().await {
+        Ok(versions) => println!("✅ Applied {} database migration(s)!", versions.len()),
+        Err(e) => {
+            println!("🔥 Error applying database migrations: {}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // Keep the connection alive for the lifetime of the server
+    conn.supervise();
+
     let gym_repository = GymRepository { repo: conn.clone() };
     let user_repository = UserRepository { repo: conn.clone() };
     let gymseeker_repository = GymSeekerRepository { repo: conn.clone() };
@@ -67,16 +108,39 @@ pub async fn run() -> Result<()> {
     let trainer_repository = TrainerRepository { repo: conn.clone() };
     let post_repository = PostRepository { repo: conn.clone() };
     let feed_repository = FeedRepository { repo: conn.clone() };
+    let follow_repository = FollowRepository { repo: conn.clone() };
+    let reaction_repository = ReactionRepository { repo: conn.clone() };
+    let comment_repository = CommentRepository { repo: conn.clone() };
+    let hashtag_repository = HashtagRepository { repo: conn.clone() };
+    let mention_repository = MentionRepository { repo: conn.clone() };
+    let notification_repository = NotificationRepository { repo: conn.clone() };
+    let message_repository = MessageRepository { repo: conn.clone() };
+    let membership_repository = MembershipRepository { repo: conn.clone() };
+    let class_repository = ClassRepository { repo: conn.clone() };
+    let transaction_repository = TransactionRepository { repo: conn.clone() };
+
+    let cache = Cache {
+        redis_client: redis_client.clone(),
+        profile_ttl: environment.profile_cache_ttl,
+        feed_ttl: environment.feed_cache_ttl,
+    };
 
     let gym_services = GymServices {
         gym_repository: gym_repository.clone(),
         user_repository: user_repository.clone(),
+        transaction_repository: transaction_repository.clone(),
         location_repository: location_repository.clone(),
+        follow_repository: follow_repository.clone(),
+        cache: cache.clone(),
     };
 
     let gymseeker_services = GymSeekerServices {
         repository: gymseeker_repository.clone(),
         user_repository: user_repository.clone(),
+        transaction_repository: transaction_repository.clone(),
+        follow_repository: follow_repository.clone(),
+        membership_repository: membership_repository.clone(),
+        cache: cache.clone(),
     };
     let auth_services = AuthServices {
         repo: conn.clone(),
@@ -87,12 +151,16 @@ pub async fn run() -> Result<()> {
         location_repository: location_repository.clone(),
         user_repository: user_repository.clone(),
         gym_repository: gym_repository.clone(),
+        cache: cache.clone(),
     };
 
     let trainer_services = TrainerServices {
         trainer_repository: trainer_repository.clone(),
         user_repository: user_repository.clone(),
+        transaction_repository,
         gym_repository: gym_repository.clone(),
+        follow_repository: follow_repository.clone(),
+        cache: cache.clone(),
     };
 
     let post_services = PostServices {
@@ -102,9 +170,67 @@ pub async fn run() -> Result<()> {
         gym_seeker_repository: gymseeker_repository.clone(),
         post_repository: post_repository.clone(),
         location_repository: location_repository.clone(),
+        reaction_repository: reaction_repository.clone(),
+        hashtag_repository: hashtag_repository.clone(),
+        mention_repository,
+        notification_repository: notification_repository.clone(),
+        cache: cache.clone(),
     };
 
-    let feed_services = FeedServices { feed_repository };
+    let hashtag_services = HashtagServices { hashtag_repository };
+
+    let reaction_services = ReactionServices {
+        reaction_repository: reaction_repository.clone(),
+        post_repository: post_repository.clone(),
+    };
+
+    let comment_services = CommentServices {
+        comment_repository: comment_repository.clone(),
+        post_repository,
+        notification_repository: notification_repository.clone(),
+    };
+
+    let follow_services = FollowServices {
+        follow_repository: follow_repository.clone(),
+        notification_repository: notification_repository.clone(),
+        cache: cache.clone(),
+    };
+
+    let class_services = ClassServices {
+        class_repository,
+        trainer_repository: trainer_repository.clone(),
+        membership_repository: membership_repository.clone(),
+    };
+
+    let membership_services = MembershipServices {
+        membership_repository,
+        follow_repository: follow_repository.clone(),
+        notification_repository: notification_repository.clone(),
+    };
+
+    let notification_services = NotificationServices {
+        notification_repository,
+        events: NotificationEvents::default(),
+    };
+
+    let message_services = MessageServices {
+        message_repository,
+        redis_client: redis_client.clone(),
+        events: MessageEvents::default(),
+    };
+
+    let feed_services = FeedServices {
+        feed_repository,
+        follow_repository,
+        gym_repository: gym_repository.clone(),
+        trainer_repository: trainer_repository.clone(),
+        location_repository: location_repository.clone(),
+        reaction_repository,
+        comment_repository,
+        cache,
+        events: FeedEvents::default(),
+        nearby_radius_km: environment.nearby_radius_km,
+    };
 
     let environment_cloned = environment.clone();
 
@@ -117,7 +243,16 @@ pub async fn run() -> Result<()> {
         trainer_services,
         post_services,
         feed_services,
+        follow_services,
+        reaction_services,
+        comment_services,
+        hashtag_services,
+        notification_services,
+        message_services,
+        membership_services,
+        class_services,
         email_services,
+        health_services,
         redis_client,
         environment: environment_cloned,
         cloud_storage,
@@ -4,7 +4,8 @@ use axum::{
     Router,
 };
 use router::axum_router::{
-    auth, feed, gym, gymseeker, location, midleware::jwt_auth::auth, post, trainer, upload,
+    auth, class, comment, feed, follow, gym, gymseeker, hashtag, health, location, membership,
+    message, midleware::jwt_auth::auth, notification, post, reaction, trainer, upload,
 };
 use std::sync::Arc;
 use tower_http::trace::TraceLayer;
@@ -80,6 +81,23 @@ pub fn content_routes(app_state: Arc<AppState>) -> Router {
                 .get(post::get),
         )
         .route("/api/v1/content/:content_id", delete(post::delete))
+        .route("/api/v1/content/:content_id/restore", post(post::restore))
+        .route(
+            "/api/v1/content/:content_id/reaction",
+            put(reaction::react).delete(reaction::unreact),
+        )
+        .route(
+            "/api/v1/content/:content_id/comments",
+            post(comment::create).get(comment::get),
+        )
+        .route(
+            "/api/v1/comments/:comment_id",
+            put(comment::update).delete(comment::delete),
+        )
+        .route(
+            "/api/v1/comments/:comment_id/replies",
+            get(comment::replies),
+        )
         .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
         .with_state(app_state)
 }
@@ -87,6 +105,111 @@ pub fn content_routes(app_state: Arc<AppState>) -> Router {
 pub fn feed_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
         .route("/api/v1/feed", get(feed::get_profile))
+        .route("/api/v1/feed/stream", get(feed::stream))
+        .route("/api/v1/hashtags/trending", get(hashtag::trending))
+        .route("/api/v1/hashtags/:tag/posts", get(hashtag::posts))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
+        .with_state(app_state)
+}
+
+pub fn follow_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/api/v1/follow/:profile_id",
+            post(follow::follow).delete(follow::unfollow),
+        )
+        .route(
+            "/api/v1/profile/:profile_id/followers",
+            get(follow::followers),
+        )
+        .route(
+            "/api/v1/profile/:profile_id/following",
+            get(follow::following),
+        )
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
+        .with_state(app_state)
+}
+
+pub fn notification_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/notifications", get(notification::get))
+        .route(
+            "/api/v1/notifications/read",
+            put(notification::mark_all_read),
+        )
+        .route(
+            "/api/v1/notifications/unread_count",
+            get(notification::unread_count),
+        )
+        .route("/api/v1/notifications/stream", get(notification::stream))
+        .route(
+            "/api/v1/notifications/:notification_id/read",
+            put(notification::mark_read),
+        )
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
+        .with_state(app_state)
+}
+
+pub fn message_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/api/v1/conversations",
+            get(message::conversations).post(message::start),
+        )
+        .route(
+            "/api/v1/conversations/:conversation_id/messages",
+            get(message::messages),
+        )
+        .route(
+            "/api/v1/conversations/:conversation_id/read",
+            put(message::mark_read),
+        )
+        .route("/api/v1/messages/socket", get(message::socket))
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
+        .with_state(app_state)
+}
+
+pub fn membership_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route(
+            "/api/v1/gym/plans",
+            get(membership::own_plans).post(membership::create_plan),
+        )
+        .route(
+            "/api/v1/gym/plans/:plan_id",
+            put(membership::update_plan).delete(membership::archive_plan),
+        )
+        .route("/api/v1/gym/members", get(membership::members))
+        .route("/api/v1/gyms/:gym_id/plans", get(membership::plans))
+        .route(
+            "/api/v1/memberships",
+            get(membership::memberships).post(membership::join),
+        )
+        .route(
+            "/api/v1/memberships/:membership_id",
+            delete(membership::cancel),
+        )
+        .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
+        .with_state(app_state)
+}
+
+pub fn class_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/gym/classes", post(class::create))
+        .route(
+            "/api/v1/gym/classes/:session_id",
+            put(class::update).delete(class::cancel),
+        )
+        .route(
+            "/api/v1/gym/classes/:session_id/bookings",
+            get(class::attendees),
+        )
+        .route("/api/v1/gyms/:gym_id/classes", get(class::schedule))
+        .route("/api/v1/classes/bookings", get(class::bookings))
+        .route(
+            "/api/v1/classes/:session_id/booking",
+            post(class::book).delete(class::cancel_booking),
+        )
         .route_layer(middleware::from_fn_with_state(app_state.clone(), auth))
         .with_state(app_state)
 }
@@ -98,8 +221,16 @@ pub fn location_routes(app_state: Arc<AppState>) -> Router {
         .with_state(app_state)
 }
 
+pub fn health_routes(app_state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/api/v1/health", get(health::check))
+        .route("/api/v1/metrics", get(health::metrics))
+        .with_state(app_state)
+}
+
 pub fn build_routes(app_state: Arc<AppState>) -> Router {
     Router::new()
+        .merge(health_routes(app_state.clone()))
         .merge(gym_routes(app_state.clone()))
         .merge(auth_routes(app_state.clone()))
         .merge(gymnast_routes(app_state.clone()))
@@ -107,6 +238,11 @@ pub fn build_routes(app_state: Arc<AppState>) -> Router {
         .merge(trainer_routes(app_state.clone()))
         .merge(content_routes(app_state.clone()))
         .merge(feed_routes(app_state.clone()))
+        .merge(follow_routes(app_state.clone()))
+        .merge(notification_routes(app_state.clone()))
+        .merge(message_routes(app_state.clone()))
+        .merge(membership_routes(app_state.clone()))
+        .merge(class_routes(app_state.clone()))
         .merge(location_routes(app_state))
         .layer(TraceLayer::new_for_http())
 }
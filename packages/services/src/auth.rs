@@ -3,6 +3,7 @@ use database::{
     db::DatabaseClient,
     interface::DBInterface,
     model::{PayloadUserResponse, User},
+    query::Query,
 };
 
 use errors::Result;
@@ -23,11 +24,7 @@ impl AuthServices {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
         let vect_data: Vec<PayloadUserResponse> = repo
-            .select_where(
-                "user".to_owned(),
-                format!("verified_token = '{}'", token),
-                "*".to_string(),
-            )
+            .select_where(Query::select("user").eq("verified_token", token.clone()))
             .await?;
 
         if vect_data.is_empty() {
@@ -72,11 +69,7 @@ impl AuthServices {
         let repo = &self.repo;
 
         let vect_data: Vec<PayloadUserResponse> = repo
-            .select_where(
-                "user".to_owned(),
-                format!("email = '{}'", email),
-                "*".to_string(),
-            )
+            .select_where(Query::select("user").eq("email", email))
             .await?;
 
         if vect_data.is_empty() {
@@ -0,0 +1,204 @@
+use std::{fmt::Display, future::Future};
+
+use errors::Result;
+use redis::{AsyncCommands, Client};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Counter every feed page key embeds, bumping it drops all cached pages at once
+const FEED_GENERATION_KEY: &str = "cache:feed:generation";
+
+/* Read-through cache in Redis, a Redis failure falls back to the database instead of failing the request */
+#[derive(Clone, Debug)]
+pub struct Cache {
+    pub redis_client: Client,
+    /// Seconds a cached profile is served before it is read again
+    pub profile_ttl: u64,
+    /// Seconds a cached feed page is served before it is read again
+    pub feed_ttl: u64,
+}
+
+impl Cache {
+    /* Cached profile of `id`, loaded and stored on a miss */
+    pub async fn profile<T, F, Fut>(&self, id: &impl Display, load: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        self.get_or_load(&profile_key(id), self.profile_ttl, load)
+            .await
+    }
+
+    /* Drop the cached profile of `id` after it changed */
+    pub async fn invalidate_profile(&self, id: &impl Display) {
+        let key = profile_key(id);
+        if let Err(error) = self.delete(&key).await {
+            tracing::warn!("Invalidating cache key {} failed: {}", key, error);
+        }
+    }
+
+    /* Cached feed page, loaded and stored on a miss */
+    pub async fn feed<T, F, Fut>(&self, cursor: Option<&str>, limit: u64, load: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        let generation = match self.feed_generation().await {
+            Ok(generation) => generation,
+            Err(error) => {
+                tracing::warn!("Reading the feed cache generation failed: {}", error);
+                return load().await;
+            }
+        };
+
+        self.get_or_load(&feed_key(generation, cursor, limit), self.feed_ttl, load)
+            .await
+    }
+
+    /* Drop every cached feed page after a post or an author shown in the feed changed */
+    pub async fn invalidate_feed(&self) {
+        let bumped: Result<()> = async {
+            let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+            let _: u64 = connection.incr(FEED_GENERATION_KEY, 1).await?;
+            Ok(())
+        }
+        .await;
+
+        if let Err(error) = bumped {
+            tracing::warn!("Invalidating the feed cache failed: {}", error);
+        }
+    }
+
+    async fn get_or_load<T, F, Fut>(&self, key: &str, ttl: u64, load: F) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T>>,
+    {
+        match self.get(key).await {
+            Ok(Some(value)) => return Ok(value),
+            Ok(None) => {}
+            Err(error) => tracing::warn!("Reading cache key {} failed: {}", key, error),
+        }
+
+        // Errors are not cached, a missing record is looked up again on the next request
+        let value = load().await?;
+
+        if let Err(error) = self.set(key, &value, ttl).await {
+            tracing::warn!("Writing cache key {} failed: {}", key, error);
+        }
+
+        Ok(value)
+    }
+
+    async fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>> {
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let cached: Option<String> = connection.get(key).await?;
+
+        // An entry written by an older payload shape counts as a miss and is replaced
+        Ok(cached.and_then(|cached| serde_json::from_str(&cached).ok()))
+    }
+
+    async fn set<T: Serialize>(&self, key: &str, value: &T, ttl: u64) -> Result<()> {
+        let payload = serde_json::to_string(value)
+            .map_err(|error| errors::Error::DatabaseError(error.to_string()))?;
+
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let _: () = connection.set_ex(key, payload, ttl).await?;
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let _: () = connection.del(key).await?;
+        Ok(())
+    }
+
+    async fn feed_generation(&self) -> Result<u64> {
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let generation: Option<u64> = connection.get(FEED_GENERATION_KEY).await?;
+        Ok(generation.unwrap_or_default())
+    }
+}
+
+/* Key of a cached profile, the record id already names its table */
+fn profile_key(id: &impl Display) -> String {
+    format!("cache:profile:{}", id)
+}
+
+/* Key of a cached feed page within a feed generation */
+fn feed_key(generation: u64, cursor: Option<&str>, limit: u64) -> String {
+    format!(
+        "cache:feed:{}:{}:{}",
+        generation,
+        limit,
+        cursor.unwrap_or("first")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use database::{
+        id::GymId,
+        model::{Paginated, PayloadFeed, PayloadGymResponses, ReactionKind},
+    };
+
+    #[test]
+    fn keys_separate_records_pages_and_generations() {
+        let gym: GymId = "gym:abc".parse().unwrap();
+        assert_eq!(profile_key(&gym), "cache:profile:gym:abc");
+
+        assert_eq!(feed_key(3, None, 5), "cache:feed:3:5:first");
+        assert_ne!(feed_key(3, None, 5), feed_key(4, None, 5));
+        assert_ne!(feed_key(3, None, 5), feed_key(3, None, 10));
+        assert_ne!(feed_key(3, None, 5), feed_key(3, Some("abc"), 5));
+    }
+
+    #[test]
+    fn cached_payloads_round_trip() {
+        let profile = PayloadGymResponses {
+            id: String::from("gym:abc"),
+            name: String::from("Iron"),
+            description: String::from(""),
+            profile_picture: String::from(""),
+            location_id: Some("location:abc".parse().unwrap()),
+            owner_id: Some("user:abc".parse().unwrap()),
+            created_at: Some(surrealdb::sql::Datetime::default()),
+            updated_at: None,
+            followers_count: 2,
+            following_count: 1,
+            version: 4,
+        };
+        let cached = serde_json::to_string(&profile).unwrap();
+        assert_eq!(
+            serde_json::from_str::<PayloadGymResponses>(&cached).unwrap(),
+            profile
+        );
+
+        let page = Paginated {
+            data: vec![PayloadFeed {
+                id: String::from("post:abc"),
+                content: String::from("hello"),
+                gym_name: None,
+                location: None,
+                profile_picture: None,
+                updated_at: Some(surrealdb::sql::Datetime::default()),
+                post_gallery: Some(Vec::new()),
+                user_type: String::from("gym"),
+                username: String::from("iron"),
+                reaction_counts: [(ReactionKind::Like, 3)].into(),
+                reacted_by_me: Some(ReactionKind::Like),
+                comment_count: 2,
+            }],
+            next_cursor: Some(String::from("abc")),
+        };
+        let cached = serde_json::to_string(&page).unwrap();
+        let back: Paginated<PayloadFeed> = serde_json::from_str(&cached).unwrap();
+        assert_eq!(back.next_cursor, page.next_cursor);
+        assert_eq!(back.data[0].updated_at, page.data[0].updated_at);
+        assert_eq!(back.data[0].reaction_counts, page.data[0].reaction_counts);
+        assert_eq!(back.data[0].reacted_by_me, Some(ReactionKind::Like));
+    }
+}
@@ -0,0 +1,484 @@
+use chrono::{Duration, Utc};
+
+use database::{
+    id::{ClassBookingId, ClassSessionId, GymId, GymSeekerId, TrainerId},
+    model::{
+        ClassBooking, ClassSession, ClassSessionUpdate, MembershipStatus,
+        PayloadClassBookingResponse, PayloadClassSessionRequest, PayloadClassSessionResponse,
+    },
+};
+use errors::Result;
+use repository::{
+    class::{session_response, ClassRepository},
+    membership::MembershipRepository,
+    trainer::TrainerRepository,
+};
+use surrealdb::sql::Datetime;
+
+/// Longest class a gym may publish, in minutes
+const MAX_CLASS_MINUTES: i64 = 600;
+/// Most seats a single class may have
+const MAX_CLASS_CAPACITY: i64 = 500;
+/// Times a booking is committed again after losing a race with another write to the class
+const SEAT_ATTEMPTS: usize = 5;
+
+#[derive(Clone)]
+pub struct ClassServices {
+    pub class_repository: ClassRepository,
+    pub trainer_repository: TrainerRepository,
+    pub membership_repository: MembershipRepository,
+}
+
+impl ClassServices {
+    /* Publish a class for the gym, optionally led by one of its trainers */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn create(
+        &self,
+        gym_id: &GymId,
+        payload: PayloadClassSessionRequest,
+    ) -> Result<Option<ClassSessionId>> {
+        let now = Datetime::from(Utc::now());
+        check_class(&payload, &now)?;
+        let trainer_id = self
+            .gym_trainer(gym_id, payload.trainer_id.as_deref())
+            .await?;
+
+        let (Some(title), Some(start_at), Some(duration_minutes), Some(capacity)) = (
+            payload.title,
+            payload.start_at,
+            payload.duration_minutes,
+            payload.capacity,
+        ) else {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "A class needs a title, a start time, a duration and a capacity",
+            )));
+        };
+
+        let data = ClassSession {
+            id: None,
+            gym_id: gym_id.clone(),
+            trainer_id,
+            title,
+            description: payload.description.unwrap_or_default(),
+            start_at,
+            duration_minutes,
+            capacity,
+            location: payload.location.unwrap_or_default(),
+            booked_count: 0,
+            cancelled_at: None,
+            created_at: now.clone(),
+            updated_at: now,
+            version: 0,
+        };
+
+        self.class_repository.insert_session(data).await
+    }
+
+    /* Change the fields sent of one of the gym's upcoming classes */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update(
+        &self,
+        gym_id: &GymId,
+        id: &ClassSessionId,
+        payload: PayloadClassSessionRequest,
+    ) -> Result<bool> {
+        let now = Datetime::from(Utc::now());
+        check_class(&payload, &now)?;
+        let session = self.owned_session(gym_id, id).await?;
+        if session.cancelled_at.is_some() {
+            return Err(errors::Error::InvalidRequest(format!(
+                "{} is cancelled",
+                id
+            )));
+        }
+        if payload
+            .capacity
+            .is_some_and(|capacity| capacity < session.booked_count)
+        {
+            return Err(errors::Error::InvalidRequest(format!(
+                "{} already has {} seats booked",
+                id, session.booked_count
+            )));
+        }
+        let trainer_id = self
+            .gym_trainer(gym_id, payload.trainer_id.as_deref())
+            .await?;
+
+        let data = ClassSessionUpdate {
+            title: payload.title,
+            description: payload.description,
+            trainer_id,
+            start_at: payload.start_at,
+            duration_minutes: payload.duration_minutes,
+            capacity: payload.capacity,
+            location: payload.location,
+            cancelled_at: None,
+            updated_at: now,
+        };
+
+        // Written against the version the capacity was checked at, so a booking in between fails it
+        let version = payload.version.unwrap_or(session.version);
+        self.class_repository
+            .update_session(id, data, Some(version))
+            .await
+    }
+
+    /* Cancel one of the gym's classes, its bookings stay so members see it was cancelled */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn cancel(&self, gym_id: &GymId, id: &ClassSessionId) -> Result<bool> {
+        let session = self.owned_session(gym_id, id).await?;
+        if session.cancelled_at.is_some() {
+            return Ok(false);
+        }
+
+        let now = Datetime::from(Utc::now());
+        let data = ClassSessionUpdate {
+            cancelled_at: Some(now.clone()),
+            updated_at: now,
+            ..Default::default()
+        };
+        self.class_repository.update_session(id, data, None).await
+    }
+
+    /* Classes of the gym starting within `days` of `from`, with the seats `viewer` holds marked */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn schedule(
+        &self,
+        gym_id: &GymId,
+        viewer: Option<&GymSeekerId>,
+        from: Option<Datetime>,
+        days: i64,
+    ) -> Result<Vec<PayloadClassSessionResponse>> {
+        let from = from.unwrap_or_else(|| Datetime::from(Utc::now()));
+        let to = Datetime::from(from.0 + Duration::days(days));
+
+        let sessions = self.class_repository.schedule(gym_id, from, to).await?;
+        let booked = match viewer {
+            Some(gym_seeker_id) => {
+                self.class_repository
+                    .booked(&sessions, gym_seeker_id)
+                    .await?
+            }
+            None => Default::default(),
+        };
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| {
+                let booked_by_me = session
+                    .id
+                    .as_ref()
+                    .is_some_and(|id| booked.contains(&id.to_string()));
+                session_response(session, booked_by_me)
+            })
+            .collect())
+    }
+
+    /* Gym seekers booked into one of the gym's classes */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn attendees(
+        &self,
+        gym_id: &GymId,
+        id: &ClassSessionId,
+    ) -> Result<Vec<PayloadClassBookingResponse>> {
+        self.owned_session(gym_id, id).await?;
+        self.class_repository.attendees(id).await
+    }
+
+    /* Classes the gym seeker holds a seat in that have not ended, the earliest first */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn bookings(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+    ) -> Result<Vec<PayloadClassSessionResponse>> {
+        let bookings = self.class_repository.bookings(gym_seeker_id).await?;
+        let sessions = self
+            .class_repository
+            .sessions(
+                bookings
+                    .into_iter()
+                    .map(|booking| booking.session_id)
+                    .collect(),
+            )
+            .await?;
+
+        let now = Utc::now();
+        let mut sessions: Vec<ClassSession> = sessions
+            .into_values()
+            .filter(|session| end_of(session) > now)
+            .collect();
+        sessions.sort_by(|a, b| a.start_at.cmp(&b.start_at));
+
+        Ok(sessions
+            .into_iter()
+            .map(|session| session_response(session, true))
+            .collect())
+    }
+
+    /* Take a seat in an upcoming class of a gym the seeker is an active member of */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn book(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+        id: &ClassSessionId,
+    ) -> Result<ClassBookingId> {
+        let session = self.session(id).await?;
+        let now = Datetime::from(Utc::now());
+        check_bookable(&session, &now)?;
+
+        let membership = self
+            .membership_repository
+            .find(gym_seeker_id, &session.gym_id)
+            .await?;
+        if membership.map(|membership| membership.status(&now)) != Some(MembershipStatus::Active) {
+            return Err(errors::Error::UserNotVerified(format!(
+                "An active membership of {} is needed to book its classes",
+                session.gym_id
+            )));
+        }
+
+        // The pair index turns a second booking of the same class into DataExist
+        for _ in 0..SEAT_ATTEMPTS {
+            let booking = ClassBooking {
+                id: None,
+                session_id: id.clone(),
+                gym_seeker_id: gym_seeker_id.clone(),
+                created_at: now.clone(),
+            };
+
+            match self.class_repository.insert_booking(booking).await {
+                // The class filled up or another write to it won the commit, reading it tells which
+                Err(errors::Error::Conflict(_)) => {
+                    check_bookable(&self.session(id).await?, &Datetime::from(Utc::now()))?;
+                }
+                result => return result,
+            }
+        }
+
+        Err(errors::Error::Conflict(id.to_string()))
+    }
+
+    /* Give up the seat in a class that has not started yet */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn cancel_booking(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+        id: &ClassSessionId,
+    ) -> Result<bool> {
+        let session = self.session(id).await?;
+        if session.start_at <= Datetime::from(Utc::now()) {
+            return Err(errors::Error::InvalidRequest(format!(
+                "{} has already started",
+                id
+            )));
+        }
+
+        // The seat is given back in the commit that removes the booking, so only one request frees it
+        for _ in 0..SEAT_ATTEMPTS {
+            match self
+                .class_repository
+                .delete_booking(id, gym_seeker_id)
+                .await
+            {
+                Ok(true) => return Ok(true),
+                Ok(false) => {
+                    return Err(errors::Error::DataNotAvaliable(format!(
+                        "booking of {}",
+                        id
+                    )))
+                }
+                Err(errors::Error::Conflict(_)) => continue,
+                Err(error) => return Err(error),
+            }
+        }
+
+        Err(errors::Error::Conflict(id.to_string()))
+    }
+
+    async fn session(&self, id: &ClassSessionId) -> Result<ClassSession> {
+        self.class_repository
+            .get_session(id)
+            .await?
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))
+    }
+
+    /* The class when it belongs to the gym, another gym's class is reported missing */
+    async fn owned_session(&self, gym_id: &GymId, id: &ClassSessionId) -> Result<ClassSession> {
+        let session = self.session(id).await?;
+        if &session.gym_id != gym_id {
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
+        }
+
+        Ok(session)
+    }
+
+    /* Trainer sent for a class, which has to be based at the gym holding it */
+    async fn gym_trainer(
+        &self,
+        gym_id: &GymId,
+        trainer_id: Option<&str>,
+    ) -> Result<Option<TrainerId>> {
+        let Some(trainer_id) = trainer_id else {
+            return Ok(None);
+        };
+
+        let trainer_id: TrainerId = trainer_id.parse()?;
+        let trainers = self.trainer_repository.get_details(&trainer_id).await?;
+        if !trainers.iter().any(|trainer| &trainer.gym_id == gym_id) {
+            return Err(errors::Error::DataNotAvaliable(trainer_id.to_string()));
+        }
+
+        Ok(Some(trainer_id))
+    }
+}
+
+/* Time the class ends */
+fn end_of(session: &ClassSession) -> chrono::DateTime<Utc> {
+    session.start_at.0 + Duration::minutes(session.duration_minutes)
+}
+
+/* Reject class fields that were sent with a value no class can have */
+fn check_class(payload: &PayloadClassSessionRequest, now: &Datetime) -> Result<()> {
+    if payload
+        .title
+        .as_ref()
+        .is_some_and(|title| title.trim().is_empty())
+    {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "A class title cannot be empty",
+        )));
+    }
+    if payload
+        .start_at
+        .as_ref()
+        .is_some_and(|start_at| start_at <= now)
+    {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "A class has to start in the future",
+        )));
+    }
+    if payload
+        .duration_minutes
+        .is_some_and(|minutes| !(1..=MAX_CLASS_MINUTES).contains(&minutes))
+    {
+        return Err(errors::Error::InvalidRequest(format!(
+            "A class lasts between 1 and {} minutes",
+            MAX_CLASS_MINUTES
+        )));
+    }
+    if payload
+        .capacity
+        .is_some_and(|capacity| !(1..=MAX_CLASS_CAPACITY).contains(&capacity))
+    {
+        return Err(errors::Error::InvalidRequest(format!(
+            "A class has between 1 and {} seats",
+            MAX_CLASS_CAPACITY
+        )));
+    }
+    Ok(())
+}
+
+/* Whether a seat can still be taken in the class at `now` */
+fn check_bookable(session: &ClassSession, now: &Datetime) -> Result<()> {
+    let id = session
+        .id
+        .as_ref()
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+
+    if session.cancelled_at.is_some() {
+        return Err(errors::Error::InvalidRequest(format!(
+            "{} is cancelled",
+            id
+        )));
+    }
+    if &session.start_at <= now {
+        return Err(errors::Error::InvalidRequest(format!(
+            "{} has already started",
+            id
+        )));
+    }
+    if session.booked_count >= session.capacity {
+        return Err(errors::Error::InvalidRequest(format!("{} is full", id)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(booked_count: i64, capacity: i64, starts_in_minutes: i64) -> ClassSession {
+        ClassSession {
+            id: Some("class_session:a".parse().unwrap()),
+            gym_id: "gym:a".parse().unwrap(),
+            trainer_id: None,
+            title: String::from("Spin"),
+            description: String::new(),
+            start_at: Datetime::from(Utc::now() + Duration::minutes(starts_in_minutes)),
+            duration_minutes: 45,
+            capacity,
+            location: String::new(),
+            booked_count,
+            cancelled_at: None,
+            created_at: Datetime::default(),
+            updated_at: Datetime::default(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn only_upcoming_classes_with_free_seats_are_bookable() {
+        let now = Datetime::from(Utc::now());
+
+        assert!(check_bookable(&session(1, 2, 60), &now).is_ok());
+        assert!(check_bookable(&session(2, 2, 60), &now).is_err());
+        assert!(check_bookable(&session(0, 2, -10), &now).is_err());
+
+        let cancelled = ClassSession {
+            cancelled_at: Some(now.clone()),
+            ..session(0, 2, 60)
+        };
+        assert!(check_bookable(&cancelled, &now).is_err());
+    }
+
+    #[test]
+    fn class_fields_are_checked_when_sent() {
+        let now = Datetime::from(Utc::now());
+        let payload = PayloadClassSessionRequest {
+            title: None,
+            description: None,
+            trainer_id: None,
+            start_at: None,
+            duration_minutes: None,
+            capacity: None,
+            location: None,
+            version: None,
+        };
+        assert!(check_class(&payload, &now).is_ok());
+
+        for invalid in [
+            PayloadClassSessionRequest {
+                title: Some(String::from(" ")),
+                ..payload.clone()
+            },
+            PayloadClassSessionRequest {
+                start_at: Some(Datetime::from(Utc::now() - Duration::hours(1))),
+                ..payload.clone()
+            },
+            PayloadClassSessionRequest {
+                duration_minutes: Some(0),
+                ..payload.clone()
+            },
+            PayloadClassSessionRequest {
+                capacity: Some(MAX_CLASS_CAPACITY + 1),
+                ..payload.clone()
+            },
+        ] {
+            assert!(matches!(
+                check_class(&invalid, &now),
+                Err(errors::Error::InvalidRequest(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use chrono::Utc;
+
+use crate::notification;
+use database::{
+    id::{CommentId, PostId, UserId},
+    model::{
+        Comment, CommentContentUpdate, Notification, NotificationKind, Paginated,
+        PayloadCommentResponse, Post,
+    },
+    query::Cursor,
+};
+use errors::Result;
+use repository::{
+    comment::CommentRepository, notification::NotificationRepository, post::PostRepository,
+};
+
+#[derive(Clone)]
+pub struct CommentServices {
+    pub comment_repository: CommentRepository,
+    pub post_repository: PostRepository,
+    pub notification_repository: NotificationRepository,
+}
+
+impl CommentServices {
+    /* Comment on a visible post, or reply to one of its top-level comments */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn create(
+        &self,
+        user_id: &UserId,
+        post_id: &PostId,
+        content: String,
+        parent_id: Option<CommentId>,
+    ) -> Result<Option<CommentId>> {
+        if content.trim().is_empty() {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "A comment cannot be empty",
+            )));
+        }
+
+        let (is_empty, posts) = self.post_repository.is_post_empty_by_id(post_id).await?;
+        if is_empty {
+            return Err(errors::Error::DataNotAvaliable(post_id.to_string()));
+        }
+
+        let parent = match &parent_id {
+            Some(parent_id) => {
+                let parent = self
+                    .comment_repository
+                    .get(parent_id)
+                    .await?
+                    .ok_or_else(|| errors::Error::DataNotAvaliable(parent_id.to_string()))?;
+                check_reply_target(&parent, post_id)?;
+                Some(parent)
+            }
+            None => None,
+        };
+
+        let time_now = surrealdb::sql::Datetime::from(Utc::now());
+        let data = Comment {
+            id: None,
+            post_id: post_id.clone(),
+            parent_id,
+            user_id: user_id.clone(),
+            content,
+            created_at: time_now.clone(),
+            updated_at: time_now,
+            version: 0,
+        };
+
+        let comment_id = self.comment_repository.insert_data(data).await?;
+
+        let notifications = recipient(posts.first(), parent.as_ref())
+            .and_then(|(recipient, kind)| notification::notification(recipient, user_id, kind))
+            .map(|notification| Notification {
+                post_id: Some(post_id.clone()),
+                comment_id: comment_id.clone(),
+                ..notification
+            });
+        notification::send(
+            &self.notification_repository,
+            Ok(notifications.into_iter().collect()),
+        )
+        .await;
+
+        Ok(comment_id)
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update(
+        &self,
+        user_id: &UserId,
+        id: &CommentId,
+        content: String,
+        version: Option<i64>,
+    ) -> Result<bool> {
+        let comment = self.get(id).await?;
+
+        // Only the author edits a comment
+        if &comment.user_id != user_id {
+            return Err(errors::Error::UserNotVerified(format!(
+                "user {} not authorized to update",
+                id
+            )));
+        }
+
+        let data = CommentContentUpdate {
+            content,
+            updated_at: surrealdb::sql::Datetime::from(Utc::now()),
+        };
+
+        self.comment_repository.update_data(id, data, version).await
+    }
+
+    /* Delete a comment and its replies, allowed to its author and to the owner of the post */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn delete(&self, user_id: &UserId, id: &CommentId) -> Result<bool> {
+        let comment = self.get(id).await?;
+        let post = self
+            .post_repository
+            .get_with_deleted(&comment.post_id)
+            .await?
+            .into_iter()
+            .next();
+
+        if !can_remove(user_id, &comment, post.as_ref()) {
+            return Err(errors::Error::UserNotVerified(format!(
+                "user {} not authorized to delete",
+                id
+            )));
+        }
+
+        self.comment_repository.delete_data(id).await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn get_list(
+        &self,
+        post_id: &PostId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadCommentResponse>> {
+        let (is_empty, _) = self.post_repository.is_post_empty_by_id(post_id).await?;
+        if is_empty {
+            return Err(errors::Error::DataNotAvaliable(post_id.to_string()));
+        }
+
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.comment_repository
+            .by_post(post_id, cursor, limit)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn replies(
+        &self,
+        id: &CommentId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadCommentResponse>> {
+        self.get(id).await?;
+
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.comment_repository.replies(id, cursor, limit).await
+    }
+
+    async fn get(&self, id: &CommentId) -> Result<Comment> {
+        self.comment_repository
+            .get(id)
+            .await?
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))
+    }
+}
+
+/* Replies stay one level deep and on the post of the comment they answer */
+fn check_reply_target(parent: &Comment, post_id: &PostId) -> Result<()> {
+    if parent.parent_id.is_some() || &parent.post_id != post_id {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "Replies can only answer a top-level comment of the same post",
+        )));
+    }
+
+    Ok(())
+}
+
+/* A reply notifies the author of the comment it answers, a comment the author of the post */
+fn recipient<'a>(
+    post: Option<&'a Post>,
+    parent: Option<&'a Comment>,
+) -> Option<(&'a UserId, NotificationKind)> {
+    match parent {
+        Some(parent) => Some((&parent.user_id, NotificationKind::Reply)),
+        None => post
+            .and_then(|post| post.user_id.as_ref())
+            .map(|user_id| (user_id, NotificationKind::Comment)),
+    }
+}
+
+/* The author of a comment and the owner of the post it is on may remove it */
+fn can_remove(user_id: &UserId, comment: &Comment, post: Option<&Post>) -> bool {
+    &comment.user_id == user_id || post.is_some_and(|post| post.user_id.as_ref() == Some(user_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(post: &str, parent: Option<&str>, user: &str) -> Comment {
+        Comment {
+            id: Some("comment:c".parse().unwrap()),
+            post_id: post.parse().unwrap(),
+            parent_id: parent.map(|id| id.parse().unwrap()),
+            user_id: user.parse().unwrap(),
+            content: String::from("nice"),
+            created_at: surrealdb::sql::Datetime::default(),
+            updated_at: surrealdb::sql::Datetime::default(),
+            version: 0,
+        }
+    }
+
+    fn post(owner: &str) -> Post {
+        Post {
+            id: Some("post:p".parse().unwrap()),
+            user_id: Some(owner.parse().unwrap()),
+            gym_id: None,
+            gym_seeker_id: None,
+            trainer_id: None,
+            is_gym: None,
+            is_gym_seeker: None,
+            is_trainer: None,
+            content: None,
+            created_at: None,
+            updated_at: None,
+            version: 0,
+            deleted_at: None,
+        }
+    }
+
+    #[test]
+    fn replies_only_answer_top_level_comments_of_the_same_post() {
+        let post_id: PostId = "post:p".parse().unwrap();
+
+        assert!(check_reply_target(&comment("post:p", None, "user:a"), &post_id).is_ok());
+        assert!(
+            check_reply_target(&comment("post:p", Some("comment:top"), "user:a"), &post_id)
+                .is_err()
+        );
+        assert!(check_reply_target(&comment("post:other", None, "user:a"), &post_id).is_err());
+    }
+
+    #[test]
+    fn replies_notify_the_comment_author_and_comments_the_post_owner() {
+        let post = post("user:owner");
+        let parent = comment("post:p", None, "user:author");
+
+        let user = |id: &str| -> UserId { id.parse().unwrap() };
+        assert_eq!(
+            recipient(Some(&post), None),
+            Some((&user("user:owner"), NotificationKind::Comment))
+        );
+        assert_eq!(
+            recipient(Some(&post), Some(&parent)),
+            Some((&user("user:author"), NotificationKind::Reply))
+        );
+        assert_eq!(recipient(None, None), None);
+    }
+
+    #[test]
+    fn comments_are_removed_by_their_author_or_the_post_owner() {
+        let comment = comment("post:p", None, "user:author");
+        let post = post("user:owner");
+
+        let user = |id: &str| -> UserId { id.parse().unwrap() };
+        assert!(can_remove(&user("user:author"), &comment, Some(&post)));
+        assert!(can_remove(&user("user:owner"), &comment, Some(&post)));
+        assert!(!can_remove(&user("user:stranger"), &comment, Some(&post)));
+        assert!(can_remove(&user("user:author"), &comment, None));
+        assert!(!can_remove(&user("user:owner"), &comment, None));
+    }
+}
@@ -0,0 +1,101 @@
+/// Longest hashtag that is indexed, longer ones are ignored
+const MAX_HASHTAG_LENGTH: usize = 64;
+
+/* Hashtags of a post, lowercased and without duplicates, in order of appearance */
+pub fn hashtags(content: &str) -> Vec<String> {
+    let tags = tokens(content, '#', |c| c.is_alphanumeric() || c == '_')
+        .into_iter()
+        // A bare number such as "#1" is a ranking, not a tag
+        .filter(|tag| tag.chars().count() <= MAX_HASHTAG_LENGTH)
+        .filter(|tag| !tag.chars().all(|c| c.is_ascii_digit()))
+        .map(|tag| tag.to_lowercase());
+
+    unique(tags)
+}
+
+/* Usernames mentioned with `@username`, without duplicates, in order of appearance */
+pub fn mentions(content: &str) -> Vec<String> {
+    let usernames = tokens(content, '@', |c| {
+        c.is_alphanumeric() || c == '_' || c == '.' || c == '-'
+    })
+    .into_iter()
+    // A mention closing a sentence keeps its full stop out of the username
+    .map(|username| username.trim_end_matches('.').to_string())
+    .filter(|username| !username.is_empty());
+
+    unique(usernames)
+}
+
+/* Words following `sigil` at the start of the content or after a separator */
+fn tokens(content: &str, sigil: char, allowed: impl Fn(char) -> bool) -> Vec<String> {
+    let chars: Vec<char> = content.chars().collect();
+    let mut tokens = Vec::new();
+
+    let mut index = 0;
+    while index < chars.len() {
+        // `a@b.c` is an address and `page/#top` or `&#39;` a link or entity, not a tag
+        let starts_word = index == 0 || {
+            let previous = chars[index - 1];
+            !(previous.is_alphanumeric()
+                || matches!(previous, '_' | '/' | '&')
+                || previous == sigil)
+        };
+        if chars[index] != sigil || !starts_word {
+            index += 1;
+            continue;
+        }
+
+        let end = chars[index + 1..]
+            .iter()
+            .position(|&c| !allowed(c))
+            .map_or(chars.len(), |length| index + 1 + length);
+        if end > index + 1 {
+            tokens.push(chars[index + 1..end].iter().collect());
+        }
+        index = end.max(index + 1);
+    }
+
+    tokens
+}
+
+fn unique(values: impl Iterator<Item = String>) -> Vec<String> {
+    let mut unique: Vec<String> = Vec::new();
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashtags_are_lowercased_and_deduplicated() {
+        assert_eq!(
+            hashtags("#LegDay at the gym, #legday again! #squats_101 (#PR)"),
+            vec!["legday", "squats_101", "pr"]
+        );
+        assert_eq!(hashtags("Café #crème"), vec!["crème"]);
+    }
+
+    #[test]
+    fn hashtags_skip_numbers_links_and_entities() {
+        assert!(hashtags("We are #1 in town").is_empty());
+        assert!(hashtags("see example.com/#pricing and &#39;").is_empty());
+        assert!(hashtags("abc#def # alone").is_empty());
+        assert!(hashtags(&format!("#{}", "a".repeat(MAX_HASHTAG_LENGTH + 1))).is_empty());
+        assert_eq!(hashtags("##double"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn mentions_keep_the_username_and_skip_addresses() {
+        assert_eq!(
+            mentions("Thanks @coach.mike and @Iron_Gym. Mail me at me@example.com, @coach.mike!"),
+            vec!["coach.mike", "Iron_Gym"]
+        );
+        assert!(mentions("@ nobody").is_empty());
+    }
+}
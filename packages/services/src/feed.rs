@@ -1,44 +1,393 @@
-use futures::{stream, StreamExt};
+use std::sync::Arc;
 
-use database::model::{PayloadFeed, PostGalleryForFeedDeserialize};
+use futures::{stream, Stream, StreamExt};
+use serde_json::{json, Value};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+use database::{
+    id::{GymId, PostId, ProfileId, UserId},
+    live::{Change, LiveStream},
+    model::{Feed, Paginated, PayloadFeed, Post, PostGalleryForFeedDeserialize, Reactions},
+    query::Cursor,
+};
 use errors::Result;
-use repository::feed::FeedRepository;
+use repository::{
+    comment::CommentRepository,
+    feed::{FeedRepository, FeedSources},
+    follow::FollowRepository,
+    gym::GymRepository,
+    location::LocationRepository,
+    reaction::ReactionRepository,
+    trainer::TrainerRepository,
+};
+
+use crate::{cache::Cache, hashtag};
+
+/// Events buffered for a subscriber that falls behind before it starts skipping them
+const FEED_EVENT_CAPACITY: usize = 256;
 
 #[derive(Clone)]
 pub struct FeedServices {
     pub feed_repository: FeedRepository,
+    pub follow_repository: FollowRepository,
+    pub gym_repository: GymRepository,
+    pub trainer_repository: TrainerRepository,
+    pub location_repository: LocationRepository,
+    pub reaction_repository: ReactionRepository,
+    pub comment_repository: CommentRepository,
+    pub cache: Cache,
+    pub events: FeedEvents,
+    /// Distance from the viewer's gym within which other gyms count as nearby, in kilometres
+    pub nearby_radius_km: f64,
+}
+
+/* Change of the feed pushed to stream subscribers */
+#[derive(Clone, Debug)]
+pub enum FeedEvent {
+    Created(PayloadFeed),
+    Updated(PayloadFeed),
+    /// Id of a post that was deleted or soft deleted
+    Deleted(String),
+}
+
+/* One live query on the post table shared by every stream subscriber, started by the first one */
+#[derive(Clone, Default)]
+pub struct FeedEvents {
+    sender: Arc<Mutex<Option<broadcast::Sender<FeedEvent>>>>,
+}
+
+impl FeedEvent {
+    /* Name the event is sent under */
+    pub fn name(&self) -> &'static str {
+        match self {
+            FeedEvent::Created(_) => "created",
+            FeedEvent::Updated(_) => "updated",
+            FeedEvent::Deleted(_) => "deleted",
+        }
+    }
+
+    /* Body of the event, the feed entry or the id of the post that left the feed */
+    pub fn data(&self) -> Value {
+        match self {
+            FeedEvent::Created(feed) | FeedEvent::Updated(feed) => json!(feed),
+            FeedEvent::Deleted(id) => json!({ "id": id }),
+        }
+    }
 }
 
 impl FeedServices {
-    pub async fn get_list(&self, page: i64) -> Result<Vec<PayloadFeed>> {
-        let feeds = self.feed_repository.get_list(page).await?;
-
-        let deserialized_feeds = stream::iter(feeds)
-            .map(|payload| {
-                let post_gallery_deserialized: Vec<PostGalleryForFeedDeserialize> = payload
-                    .clone()
-                    .post_gallery
-                    .unwrap()
-                    .iter()
-                    .map(|gallery| PostGalleryForFeedDeserialize {
-                        id: gallery.clone().id.unwrap().to_string(),
-                        link: gallery.clone().link,
-                    })
-                    .collect();
-
-                PayloadFeed {
-                    content: payload.content,
-                    gym_name: payload.gym_name,
-                    location: payload.location,
-                    profile_picture: payload.profile_picture,
-                    updated_at: payload.updated_at,
-                    post_gallery: Some(post_gallery_deserialized),
-                    user_type: payload.user_type,
-                    username: payload.username,
-                }
+    pub async fn get_list(
+        &self,
+        viewer: &UserId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadFeed>> {
+        let page = self
+            .cache
+            .feed(cursor.as_deref(), limit, || {
+                self.load_page(cursor.as_deref(), limit)
             })
-            .collect::<Vec<_>>()
-            .await;
-        Ok(deserialized_feeds)
+            .await?;
+
+        // Reactions and comments change far more often than posts and reactions differ per viewer,
+        // so neither is cached
+        self.with_engagement(page, Some(viewer)).await
+    }
+
+    /* Posts from the profiles the viewer follows, its gym and the gyms around it,
+    falling back to the explore feed for a viewer with none of them */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn get_home(
+        &self,
+        viewer: &ProfileId,
+        user_id: &UserId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadFeed>> {
+        let sources = self.sources(viewer).await?;
+        if sources.is_empty() {
+            return self.get_list(user_id, cursor, limit).await;
+        }
+
+        // Home pages differ per viewer and are not cached
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        let feeds = self
+            .feed_repository
+            .get_home(&sources, cursor, limit)
+            .await?;
+        let page = Paginated {
+            data: feeds.data.into_iter().map(payload).collect(),
+            next_cursor: feeds.next_cursor,
+        };
+
+        self.with_engagement(page, Some(user_id)).await
+    }
+
+    /* Posts using the hashtag, newest first */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn get_by_hashtag(
+        &self,
+        viewer: &UserId,
+        tag: &str,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadFeed>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        let feeds = self
+            .feed_repository
+            .get_by_hashtag(&hashtag::normalize(tag), cursor, limit)
+            .await?;
+        let page = Paginated {
+            data: feeds.data.into_iter().map(payload).collect(),
+            next_cursor: feeds.next_cursor,
+        };
+
+        self.with_engagement(page, Some(viewer)).await
+    }
+
+    /* Feed events from now on, the stream ends when the shared live query does */
+    pub async fn stream(&self) -> Result<impl Stream<Item = FeedEvent>> {
+        let mut sender = self.events.sender.lock().await;
+        let receiver = match sender.as_ref() {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let changes = self.feed_repository.live().await?;
+                let (publisher, receiver) = broadcast::channel(FEED_EVENT_CAPACITY);
+                *sender = Some(publisher.clone());
+                tokio::spawn(self.clone().publish(changes, publisher));
+                receiver
+            }
+        };
+
+        Ok(stream::unfold(receiver, |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!("Feed stream subscriber skipped {} events", skipped)
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }))
+    }
+
+    /* Fill in the reaction and comment counts of every entry and the viewer's own reactions */
+    async fn with_engagement(
+        &self,
+        mut page: Paginated<PayloadFeed>,
+        viewer: Option<&UserId>,
+    ) -> Result<Paginated<PayloadFeed>> {
+        let post_ids: Vec<PostId> = page
+            .data
+            .iter()
+            .filter_map(|feed| feed.id.parse().ok())
+            .collect();
+        let mut summaries = self
+            .reaction_repository
+            .summaries(&post_ids, viewer)
+            .await?;
+        let comment_counts = self.comment_repository.counts(&post_ids).await?;
+
+        for feed in &mut page.data {
+            let Reactions { counts, mine } = summaries.remove(&feed.id).unwrap_or_default();
+            feed.reaction_counts = counts;
+            feed.reacted_by_me = mine;
+            feed.comment_count = comment_counts.get(&feed.id).copied().unwrap_or_default();
+        }
+
+        Ok(page)
+    }
+
+    async fn sources(&self, viewer: &ProfileId) -> Result<FeedSources> {
+        let own_gym = match viewer {
+            ProfileId::Gym(id) => Some(id.clone()),
+            ProfileId::Trainer(id) => self
+                .trainer_repository
+                .get_details(id)
+                .await?
+                .pop()
+                .map(|trainer| trainer.gym_id),
+            ProfileId::GymSeeker(_) => None,
+        };
+
+        let (gym_trainers, nearby_gyms) = match &own_gym {
+            Some(gym_id) => (
+                self.trainer_repository
+                    .by_gym(gym_id)
+                    .await?
+                    .into_iter()
+                    .filter_map(|trainer| trainer.id)
+                    .collect(),
+                self.nearby_gyms(gym_id).await?,
+            ),
+            None => (Vec::new(), Vec::new()),
+        };
+
+        Ok(FeedSources {
+            followed: self.follow_repository.followed_by(viewer).await?,
+            own_gym,
+            gym_trainers,
+            nearby_gyms,
+        })
+    }
+
+    /* Other gyms within the nearby radius of the gym's location */
+    async fn nearby_gyms(&self, gym_id: &GymId) -> Result<Vec<GymId>> {
+        let (_, gyms) = self.gym_repository.is_gym_data_empty_by_id(gym_id).await?;
+        let Some(location_id) = gyms.into_iter().next().and_then(|gym| gym.location_id) else {
+            return Ok(Vec::new());
+        };
+
+        let (_, locations) = self.location_repository.get_location(&location_id).await?;
+        let Some(location) = locations.into_iter().next() else {
+            return Ok(Vec::new());
+        };
+        // Locations are created at 0, 0 until the gym sets them
+        if location.latitude == 0.0 && location.longitude == 0.0 {
+            return Ok(Vec::new());
+        }
+
+        let nearby = self
+            .location_repository
+            .nearby(location.latitude, location.longitude, self.nearby_radius_km)
+            .await?
+            .into_iter()
+            .filter_map(|location| location.id)
+            .collect();
+        let gyms = self.gym_repository.located_at(nearby).await?;
+
+        Ok(gyms
+            .into_iter()
+            .filter_map(|gym| gym.id)
+            .filter(|id| id != gym_id)
+            .collect())
+    }
+
+    async fn load_page(&self, cursor: Option<&str>, limit: u64) -> Result<Paginated<PayloadFeed>> {
+        let cursor = cursor.map(Cursor::decode).transpose()?;
+        let feeds = self.feed_repository.get_list(cursor, limit).await?;
+
+        Ok(Paginated {
+            data: feeds.data.into_iter().map(payload).collect(),
+            next_cursor: feeds.next_cursor,
+        })
+    }
+
+    /* Turn every post change into a feed event until the live query ends */
+    async fn publish(self, mut changes: LiveStream<Post>, sender: broadcast::Sender<FeedEvent>) {
+        while let Some(change) = changes.next().await {
+            match self.event(change).await {
+                // Sending only fails while nobody is subscribed, which is not an error
+                Ok(event) => {
+                    let _ = sender.send(event);
+                }
+                Err(error) => tracing::warn!("Dropping a feed event: {}", error),
+            }
+        }
+
+        // Subscribers see their stream end and reconnect, which starts a new live query
+        tracing::warn!("Feed live query ended");
+        self.events.sender.lock().await.take();
+    }
+
+    async fn event(&self, change: Result<Change<Post>>) -> Result<FeedEvent> {
+        let (post, created) = match change? {
+            Change::Create(post) => (post, true),
+            Change::Update(post) => (post, false),
+            Change::Delete(id) => return Ok(FeedEvent::Deleted(id.to_string())),
+        };
+
+        // A soft deleted post leaves the feed just like a deleted one
+        if post.deleted_at.is_some() {
+            let id = post.id.map(|id| id.to_string()).unwrap_or_default();
+            return Ok(FeedEvent::Deleted(id));
+        }
+
+        let entry = self
+            .feed_repository
+            .entries(vec![post])
+            .await?
+            .into_iter()
+            .map(payload)
+            .collect();
+        // Events go to every subscriber, so they carry the counts but nobody's own reaction
+        let page = Paginated {
+            data: entry,
+            next_cursor: None,
+        };
+        let feed = self
+            .with_engagement(page, None)
+            .await?
+            .data
+            .pop()
+            .ok_or_else(|| errors::Error::DatabaseError(String::from("Post has no feed entry")))?;
+
+        Ok(if created {
+            FeedEvent::Created(feed)
+        } else {
+            FeedEvent::Updated(feed)
+        })
+    }
+}
+
+/* Feed entry as sent to clients, with gallery ids rendered as strings */
+fn payload(feed: Feed) -> PayloadFeed {
+    let post_gallery: Vec<PostGalleryForFeedDeserialize> = feed
+        .post_gallery
+        .unwrap_or_default()
+        .into_iter()
+        .map(|gallery| PostGalleryForFeedDeserialize {
+            id: gallery.id.map(|id| id.to_string()).unwrap_or_default(),
+            link: gallery.link,
+        })
+        .collect();
+
+    PayloadFeed {
+        id: feed.id.map(|id| id.to_string()).unwrap_or_default(),
+        content: feed.content,
+        gym_name: feed.gym_name,
+        location: feed.location,
+        profile_picture: feed.profile_picture,
+        updated_at: feed.updated_at,
+        post_gallery: Some(post_gallery),
+        user_type: feed.user_type,
+        username: feed.username,
+        reaction_counts: Default::default(),
+        reacted_by_me: None,
+        comment_count: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_are_named_after_the_change() {
+        let feed = PayloadFeed {
+            id: String::from("post:abc"),
+            content: String::from("hello"),
+            gym_name: None,
+            location: None,
+            profile_picture: None,
+            updated_at: None,
+            post_gallery: Some(Vec::new()),
+            user_type: String::from("gym"),
+            username: String::from("iron"),
+            reaction_counts: Default::default(),
+            reacted_by_me: None,
+            comment_count: 0,
+        };
+
+        let created = FeedEvent::Created(feed.clone());
+        assert_eq!(created.name(), "created");
+        assert_eq!(created.data()["id"], "post:abc");
+        assert_eq!(created.data()["content"], "hello");
+        assert_eq!(FeedEvent::Updated(feed).name(), "updated");
+
+        let deleted = FeedEvent::Deleted(String::from("post:abc"));
+        assert_eq!(deleted.name(), "deleted");
+        assert_eq!(deleted.data(), json!({ "id": "post:abc" }));
     }
 }
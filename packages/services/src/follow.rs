@@ -0,0 +1,118 @@
+use chrono::Utc;
+
+use crate::{cache::Cache, notification};
+use database::{
+    id::{ProfileId, UserId},
+    model::{Follow, Notification, NotificationKind, Paginated, PayloadFollowResponse},
+    query::Cursor,
+};
+use errors::Result;
+use repository::{follow::FollowRepository, notification::NotificationRepository};
+
+#[derive(Clone, Debug)]
+pub struct FollowServices {
+    pub follow_repository: FollowRepository,
+    pub notification_repository: NotificationRepository,
+    pub cache: Cache,
+}
+
+impl FollowServices {
+    /* Follow a gym, trainer or gym seeker, false when it was already followed */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn follow(
+        &self,
+        user_id: &UserId,
+        follower: &ProfileId,
+        followee: &ProfileId,
+    ) -> Result<bool> {
+        if follower == followee {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "A profile cannot follow itself",
+            )));
+        }
+
+        if !self.follow_repository.profile_exists(followee).await? {
+            return Err(errors::Error::DataNotAvaliable(followee.to_string()));
+        }
+
+        let data = Follow {
+            id: None,
+            follower_id: follower.clone(),
+            followee_id: followee.clone(),
+            created_at: surrealdb::sql::Datetime::from(Utc::now()),
+        };
+
+        let created = self.follow_repository.insert(data).await?;
+        if created {
+            self.invalidate_counts(follower, followee).await;
+            let notifications = self.notifications(user_id, follower, followee).await;
+            notification::send(&self.notification_repository, notifications).await;
+        }
+
+        Ok(created)
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn unfollow(&self, follower: &ProfileId, followee: &ProfileId) -> Result<()> {
+        let deleted = self.follow_repository.delete(follower, followee).await?;
+        if !deleted {
+            return Err(errors::Error::DataNotAvaliable(followee.to_string()));
+        }
+
+        self.invalidate_counts(follower, followee).await;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn followers(
+        &self,
+        profile: &ProfileId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadFollowResponse>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.follow_repository
+            .followers(profile, cursor, limit)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn following(
+        &self,
+        profile: &ProfileId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadFollowResponse>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.follow_repository
+            .following(profile, cursor, limit)
+            .await
+    }
+
+    /* The owner of the followed profile learns who follows it now */
+    async fn notifications(
+        &self,
+        user_id: &UserId,
+        follower: &ProfileId,
+        followee: &ProfileId,
+    ) -> Result<Vec<Notification>> {
+        let Some(owner) = self.follow_repository.profile_owner(followee).await? else {
+            return Ok(Vec::new());
+        };
+
+        let notifications = notification::notification(&owner, user_id, NotificationKind::Follow)
+            .map(|notification| Notification {
+                profile_id: Some(follower.clone()),
+                ..notification
+            });
+
+        Ok(notifications.into_iter().collect())
+    }
+
+    /* Both profiles show the counts of this edge */
+    async fn invalidate_counts(&self, follower: &ProfileId, followee: &ProfileId) {
+        self.cache.invalidate_profile(follower).await;
+        self.cache.invalidate_profile(followee).await;
+    }
+}
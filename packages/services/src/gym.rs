@@ -1,75 +1,51 @@
 use chrono::prelude::*;
-use database::model::{Gym, Id, Location, PayloadGymRequest, PayloadGymResponses, User};
+
+use crate::cache::Cache;
+use database::{
+    id::{GymId, ProfileId, UserId},
+    model::{Gym, Location, PayloadGymRequest, PayloadGymResponses, User},
+    transaction::Transaction,
+};
 use errors::Result;
-use repository::{gym::GymRepository, location::LocationRepository, user::UserRepository};
+use repository::{
+    follow::FollowRepository, gym::GymRepository, location::LocationRepository,
+    transaction::TransactionRepository, user::UserRepository,
+};
 
 #[derive(Clone)]
 pub struct GymServices {
     pub gym_repository: GymRepository,
+    pub follow_repository: FollowRepository,
     pub user_repository: UserRepository,
+    pub transaction_repository: TransactionRepository,
     pub location_repository: LocationRepository,
+    pub cache: Cache,
 }
 
 impl GymServices {
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<Gym>)> {
+    pub async fn is_gym_data_empty_by_id(&self, id: &GymId) -> Result<(bool, Vec<Gym>)> {
         let data_exists = self.gym_repository.is_gym_data_empty_by_id(id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_user_empty(&self, user_id: &str) -> Result<(bool, Vec<Gym>)> {
+    pub async fn is_gym_user_empty(&self, user_id: &UserId) -> Result<(bool, Vec<Gym>)> {
         let data_exists = self.gym_repository.is_gym_data_empty(user_id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    async fn is_user_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self
-            .gym_repository
-            .is_gym_data_empty_by_email(&data.email)
-            .await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    async fn is_username_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self.user_repository.is_data_empty_by_username(data).await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    pub async fn register_profile(&self, mut data: User) -> Result<Option<Id>> {
+    pub async fn register_profile(&self, mut data: User) -> Result<GymId> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
-        let (is_user_empty, _) = self.is_user_empty(&data).await?;
-        if !is_user_empty {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
-
-        let (is_username_empty, _) = self.is_username_empty(&data).await?;
-        if !is_username_empty {
-            return Err(errors::Error::DataExist(format!(
-                "username:{}",
-                data.username
-            )));
-        }
-
         data.created_at = Some(time_now.clone());
-        data.updated_at = Some(time_now);
+        data.updated_at = Some(time_now.clone());
 
-        let insert_into_user_tb: Option<Id> =
-            self.user_repository.insert_data(data.clone()).await?;
-        let user_id = insert_into_user_tb.unwrap();
+        // User, location and gym are written together so a failure never leaves an orphan user
+        let mut transaction = Transaction::new();
 
-        let (not_exists, _) = self
-            .is_gym_user_empty(user_id.id.to_string().as_ref())
-            .await?;
-        if !not_exists {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
-
-        let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
+        let user_id = self.user_repository.stage_insert(&mut transaction, data)?;
 
         let location = Location {
             id: None,
@@ -78,98 +54,107 @@ impl GymServices {
             updated_at: None,
             latitude: 0.0,
             longitude: 0.0,
+            version: 0,
         };
 
-        let insert_into_location_tb: Option<Id> = self
+        let location_id = self
             .location_repository
-            .insert_data(location, user_id.id.to_string().as_str())
-            .await?;
+            .stage_insert(&mut transaction, location)?;
 
         let gym_data = Gym {
             id: None,
             name: String::from(""),
             description: String::from(""),
             profile_picture: String::from(""),
-            location_id: Some(insert_into_location_tb.unwrap().id),
-            owner_id: user_id.id,
+            location_id: Some(location_id),
+            owner_id: user_id,
             created_at: Some(time_now.clone()),
-            updated_at: Some(time_now.clone()),
+            updated_at: Some(time_now),
+            version: 0,
         };
 
-        let insert_into_gym_tb: Option<Id> = self.gym_repository.insert_data(gym_data).await?;
-        Ok(insert_into_gym_tb)
+        let gym_id = self
+            .gym_repository
+            .stage_insert(&mut transaction, gym_data)?;
+
+        // A taken email or username fails here with DataExist from the unique indexes
+        self.transaction_repository.commit(transaction).await?;
+        Ok(gym_id)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn profile_details(&self, id: String) -> Result<PayloadGymResponses> {
-        let (is_empty, temp_data) = match id.split(':').next() {
-            Some("gym") => self.is_gym_data_empty_by_id(&id).await?,
-            _ => self.is_gym_user_empty(&id).await?,
-        };
-
-        if is_empty {
-            return Err(errors::Error::DataNotAvaliable((id).to_string()));
-        }
-
-        let data_array: Vec<PayloadGymResponses> = temp_data
-            .into_iter()
-            .map(|gym| PayloadGymResponses {
-                id: gym.id.unwrap().to_string(),
-                name: gym.name,
-                description: gym.description,
-                profile_picture: gym.profile_picture,
-                location_id: gym.location_id,
-                created_at: gym.created_at,
-                updated_at: gym.updated_at,
-                owner_id: Some(gym.owner_id),
+    pub async fn profile_details(&self, id: &GymId) -> Result<PayloadGymResponses> {
+        self.cache
+            .profile(id, || async {
+                let (_, temp_data) = self.is_gym_data_empty_by_id(id).await?;
+                let profile = first_profile(temp_data, id.to_string())?;
+                self.counted(profile).await
             })
-            .collect();
-
-        let data = data_array
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-            .clone();
-
-        Ok(data)
+            .await
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn update_profile(&self, payload: &PayloadGymRequest, id: &str) -> Result<()> {
-        let (not_exists, existing_data) = self.gym_repository.is_gym_data_empty_by_id(id).await?;
-        if not_exists {
-            return Err(errors::Error::DataNotAvaliable(id.to_string()));
-        }
+    pub async fn profile_details_by_user(&self, user_id: &UserId) -> Result<PayloadGymResponses> {
+        let (_, temp_data) = self.is_gym_user_empty(user_id).await?;
+        let profile = first_profile(temp_data, user_id.to_string())?;
+        self.counted(profile).await
+    }
 
-        let existing_record = existing_data
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update_profile(&self, payload: &PayloadGymRequest, id: &GymId) -> Result<()> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
+        // Only fields sent by the client are written, ownership and location are left untouched
         let data = PayloadGymRequest {
-            name: payload
-                .name
-                .clone()
-                .or_else(|| Some(existing_record.name.clone())),
-            description: payload
-                .description
-                .clone()
-                .or_else(|| Some(existing_record.description.clone())),
-            profile_picture: payload
-                .profile_picture
-                .clone()
-                .or_else(|| Some(existing_record.profile_picture.clone())),
-            location_id: existing_record.location_id.clone(),
-            created_at: existing_record.created_at.clone(),
-            owner_id: Some(existing_record.owner_id.clone()),
+            name: payload.name.clone(),
+            description: payload.description.clone(),
+            profile_picture: payload.profile_picture.clone(),
+            location_id: None,
+            created_at: None,
+            owner_id: None,
             updated_at: Some(time_now),
+            version: payload.version,
         };
 
-        let gym_id = existing_record.clone().id.unwrap().to_string();
-        let update_data = self.gym_repository.update_data(gym_id, data).await?;
+        let update_data = self.gym_repository.update_data(id, data).await?;
         if !update_data {
-            return Err(errors::Error::DatabaseError(id.to_string()));
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
         }
 
+        // The feed shows the gym name and picture next to its posts
+        self.cache.invalidate_profile(id).await;
+        self.cache.invalidate_feed().await;
+
         Ok(())
     }
+
+    /* Fill in the follow counts, cached with the profile and dropped on follow and unfollow */
+    async fn counted(&self, mut profile: PayloadGymResponses) -> Result<PayloadGymResponses> {
+        let id = ProfileId::Gym(profile.id.parse()?);
+        (profile.followers_count, profile.following_count) =
+            self.follow_repository.counts(&id).await?;
+        Ok(profile)
+    }
+}
+
+/* Response of the first gym found, `id` names the lookup in the error when there is none */
+fn first_profile(temp_data: Vec<Gym>, id: String) -> Result<PayloadGymResponses> {
+    let gym = temp_data
+        .into_iter()
+        .next()
+        .ok_or(errors::Error::DataNotAvaliable(id))?;
+
+    Ok(PayloadGymResponses {
+        id: gym.id.map(|id| id.to_string()).unwrap_or_default(),
+        name: gym.name,
+        description: gym.description,
+        profile_picture: gym.profile_picture,
+        location_id: gym.location_id,
+        created_at: gym.created_at,
+        updated_at: gym.updated_at,
+        owner_id: Some(gym.owner_id),
+        followers_count: 0,
+        following_count: 0,
+        version: gym.version,
+    })
 }
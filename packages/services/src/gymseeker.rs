@@ -1,18 +1,35 @@
 use chrono::Utc;
 
-use database::model::{GymSeeker, Id, PayloadGymSeekerRequest, PayloadGymSeekerResponse, User};
+use crate::{cache::Cache, membership::derived_status};
+
+use database::{
+    id::{GymSeekerId, ProfileId, UserId},
+    model::{GymSeeker, PayloadGymSeekerRequest, PayloadGymSeekerResponse, User},
+    transaction::Transaction,
+};
 use errors::Result;
-use repository::{gymseeker::GymSeekerRepository, user::UserRepository};
+use repository::{
+    follow::FollowRepository, gymseeker::GymSeekerRepository, membership::MembershipRepository,
+    transaction::TransactionRepository, user::UserRepository,
+};
+use surrealdb::sql::Datetime;
 
 #[derive(Clone, Debug)]
 pub struct GymSeekerServices {
     pub repository: GymSeekerRepository,
+    pub follow_repository: FollowRepository,
+    pub membership_repository: MembershipRepository,
     pub user_repository: UserRepository,
+    pub transaction_repository: TransactionRepository,
+    pub cache: Cache,
 }
 
 impl GymSeekerServices {
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_seeker_user_empty(&self, user_id: &str) -> Result<(bool, Vec<GymSeeker>)> {
+    pub async fn is_gym_seeker_user_empty(
+        &self,
+        user_id: &UserId,
+    ) -> Result<(bool, Vec<GymSeeker>)> {
         let data_exists = self
             .repository
             .is_gym_seeker_data_empty_by_user_id(user_id)
@@ -21,55 +38,24 @@ impl GymSeekerServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_seeker_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<GymSeeker>)> {
+    pub async fn is_gym_seeker_data_empty_by_id(
+        &self,
+        id: &GymSeekerId,
+    ) -> Result<(bool, Vec<GymSeeker>)> {
         let data_exists = self.repository.is_gym_seeker_data_empty_by_id(id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    async fn is_user_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self
-            .repository
-            .is_gym_seeker_user_empty_by_email(&data.email)
-            .await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    async fn is_username_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self.user_repository.is_data_empty_by_username(data).await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    pub async fn register_profile(&self, data: &User) -> Result<Option<Id>> {
+    pub async fn register_profile(&self, data: &User) -> Result<GymSeekerId> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
-        let (is_user_empty, _) = self.is_user_empty(data).await?;
-        if !is_user_empty {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
-
-        let (is_username_empty, _) = self.is_username_empty(data).await?;
-        if !is_username_empty {
-            return Err(errors::Error::DataExist(format!(
-                "username:{}",
-                data.username
-            )));
-        }
+        // User and gym seeker are written together so a failure never leaves an orphan user
+        let mut transaction = Transaction::new();
 
-        let insert_into_user_tb: Option<Id> =
-            self.user_repository.insert_data(data.clone()).await?;
-
-        let user_id = insert_into_user_tb.unwrap();
-
-        let (not_exists, _) = self
-            .is_gym_seeker_user_empty(user_id.id.to_string().as_str())
-            .await?;
-
-        if !not_exists {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
+        let user_id = self
+            .user_repository
+            .stage_insert(&mut transaction, data.clone())?;
 
         let gym_seeker_data = GymSeeker {
             id: None,
@@ -78,123 +64,132 @@ impl GymSeekerServices {
             updated_at: Some(time_now.clone()),
             birth_date: Some(time_now.clone()),
             sex: "".to_string(),
-            user_id: Some(user_id.id),
+            user_id: Some(user_id),
             profile_picture: "".to_string(),
             fitness_goals: "".to_string(),
             preferred_workout_time: "".to_string(),
             gym_preferences: "".to_string(),
-            membership_status: "".to_string(),
             bio: "".to_string(),
+            version: 0,
         };
 
-        let insert_into_gym_tb: Option<Id> = self.repository.insert_data(gym_seeker_data).await?;
+        let gym_seeker_id = self
+            .repository
+            .stage_insert(&mut transaction, gym_seeker_data)?;
 
-        Ok(insert_into_gym_tb)
+        // A taken email or username fails here with DataExist from the unique indexes
+        self.transaction_repository.commit(transaction).await?;
+        Ok(gym_seeker_id)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn profile_details(&self, id: String) -> Result<PayloadGymSeekerResponse> {
-        let (is_empty, temp_gym_seeker_user) = match id.split(':').next() {
-            Some("gym_seeker") => self.is_gym_seeker_data_empty_by_id(&id).await?,
-            _ => self.is_gym_seeker_user_empty(&id).await?,
-        };
-
-        if is_empty {
-            return Err(errors::Error::DataNotAvaliable(id.to_string()));
-        }
-
-        let data_array = temp_gym_seeker_user
-            .first()
-            .map(|gym_seeker| PayloadGymSeekerResponse {
-                id: gym_seeker.clone().id.unwrap().to_string(),
-                name: gym_seeker.clone().name,
-                user_id: gym_seeker.clone().user_id,
-                birth_date: gym_seeker.clone().birth_date,
-                sex: gym_seeker.clone().sex,
-                profile_picture: gym_seeker.clone().profile_picture,
-                fitness_goals: gym_seeker.clone().fitness_goals,
-                preferred_workout_time: gym_seeker.clone().preferred_workout_time,
-                gym_preferences: gym_seeker.clone().gym_preferences,
-                membership_status: gym_seeker.clone().membership_status,
-                bio: gym_seeker.clone().bio,
-                created_at: gym_seeker.clone().created_at,
-                updated_at: gym_seeker.clone().updated_at,
-            });
-
-        let data = match data_array {
-            Some(data) => data.to_owned(),
-            None => {
-                return Err(errors::Error::DataNotAvaliable(id.to_string()));
-            }
-        };
-
-        Ok(data)
+    pub async fn profile_details(&self, id: &GymSeekerId) -> Result<PayloadGymSeekerResponse> {
+        let profile = self
+            .cache
+            .profile(id, || async {
+                let (_, temp_gym_seeker_user) = self.is_gym_seeker_data_empty_by_id(id).await?;
+                let profile = first_profile(temp_gym_seeker_user, id.to_string())?;
+                self.counted(profile).await
+            })
+            .await?;
+        self.with_membership_status(profile).await
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn update_profile(&self, payload: &PayloadGymSeekerRequest, id: &str) -> Result<()> {
-        let (not_exists, existing_data) = self.is_gym_seeker_data_empty_by_id(id).await?;
-
-        if not_exists {
-            return Err(errors::Error::DataNotAvaliable(id.to_string()));
-        }
+    pub async fn profile_details_by_user(
+        &self,
+        user_id: &UserId,
+    ) -> Result<PayloadGymSeekerResponse> {
+        let (_, temp_gym_seeker_user) = self.is_gym_seeker_user_empty(user_id).await?;
+        let profile = first_profile(temp_gym_seeker_user, user_id.to_string())?;
+        let profile = self.counted(profile).await?;
+        self.with_membership_status(profile).await
+    }
 
-        let existing_record = existing_data
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-            .clone();
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update_profile(
+        &self,
+        payload: &PayloadGymSeekerRequest,
+        id: &GymSeekerId,
+    ) -> Result<()> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
+        // Only fields sent by the client are written, the owning user is left untouched
         let data = PayloadGymSeekerRequest {
-            created_at: existing_record.created_at.clone(),
+            created_at: None,
             updated_at: Some(time_now),
-            birth_date: payload
-                .birth_date
-                .clone()
-                .or_else(|| existing_record.birth_date.clone()),
-            sex: payload
-                .sex
-                .clone()
-                .or_else(|| Some(existing_record.sex.clone())),
-            user_id: existing_record.user_id.clone(),
-            profile_picture: payload
-                .profile_picture
-                .clone()
-                .or_else(|| Some(existing_record.profile_picture.clone())),
-            fitness_goals: payload
-                .fitness_goals
-                .clone()
-                .or_else(|| Some(existing_record.fitness_goals.clone())),
-            preferred_workout_time: payload
-                .preferred_workout_time
-                .clone()
-                .or_else(|| Some(existing_record.preferred_workout_time.clone())),
-            gym_preferences: payload
-                .gym_preferences
-                .clone()
-                .or_else(|| Some(existing_record.gym_preferences.clone())),
-            membership_status: payload
-                .membership_status
-                .clone()
-                .or_else(|| Some(existing_record.membership_status.clone())),
-            bio: payload
-                .bio
-                .clone()
-                .or_else(|| Some(existing_record.sex.clone())),
-            name: payload
-                .name
-                .clone()
-                .or_else(|| Some(existing_record.name.clone())),
+            birth_date: payload.birth_date.clone(),
+            sex: payload.sex.clone(),
+            user_id: None,
+            profile_picture: payload.profile_picture.clone(),
+            fitness_goals: payload.fitness_goals.clone(),
+            preferred_workout_time: payload.preferred_workout_time.clone(),
+            gym_preferences: payload.gym_preferences.clone(),
+            bio: payload.bio.clone(),
+            name: payload.name.clone(),
+            version: payload.version,
         };
 
-        let gym_seeker_id = existing_record.clone().id.unwrap().to_string();
-
-        let update_data = self.repository.update_data(gym_seeker_id, data).await?;
-
+        let update_data = self.repository.update_data(id, data).await?;
         if !update_data {
-            return Err(errors::Error::DatabaseError(id.to_string()));
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
         }
 
+        // The feed shows the gym seeker picture next to their posts
+        self.cache.invalidate_profile(id).await;
+        self.cache.invalidate_feed().await;
+
         Ok(())
     }
+
+    /* Fill in the follow counts, cached with the profile and dropped on follow and unfollow */
+    async fn counted(
+        &self,
+        mut profile: PayloadGymSeekerResponse,
+    ) -> Result<PayloadGymSeekerResponse> {
+        let id = ProfileId::GymSeeker(profile.id.parse()?);
+        (profile.followers_count, profile.following_count) =
+            self.follow_repository.counts(&id).await?;
+        Ok(profile)
+    }
+
+    /* Fill in the membership status, left out of the cache since memberships expire on their own */
+    async fn with_membership_status(
+        &self,
+        mut profile: PayloadGymSeekerResponse,
+    ) -> Result<PayloadGymSeekerResponse> {
+        let memberships = self
+            .membership_repository
+            .by_gym_seeker(&profile.id.parse()?)
+            .await?;
+        profile.membership_status = derived_status(&memberships, &Datetime::from(Utc::now()));
+        Ok(profile)
+    }
+}
+
+/* Response of the first gym seeker found, `id` names the lookup in the error when there is none */
+fn first_profile(temp_data: Vec<GymSeeker>, id: String) -> Result<PayloadGymSeekerResponse> {
+    let gym_seeker = temp_data
+        .into_iter()
+        .next()
+        .ok_or(errors::Error::DataNotAvaliable(id))?;
+
+    Ok(PayloadGymSeekerResponse {
+        id: gym_seeker.id.map(|id| id.to_string()).unwrap_or_default(),
+        name: gym_seeker.name,
+        user_id: gym_seeker.user_id,
+        birth_date: gym_seeker.birth_date,
+        sex: gym_seeker.sex,
+        profile_picture: gym_seeker.profile_picture,
+        fitness_goals: gym_seeker.fitness_goals,
+        preferred_workout_time: gym_seeker.preferred_workout_time,
+        gym_preferences: gym_seeker.gym_preferences,
+        membership_status: None,
+        bio: gym_seeker.bio,
+        created_at: gym_seeker.created_at,
+        updated_at: gym_seeker.updated_at,
+        followers_count: 0,
+        following_count: 0,
+        version: gym_seeker.version,
+    })
 }
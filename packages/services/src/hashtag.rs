@@ -0,0 +1,37 @@
+use chrono::{Duration, Utc};
+
+use database::model::PayloadHashtagCount;
+use errors::Result;
+use repository::hashtag::HashtagRepository;
+
+#[derive(Clone, Debug)]
+pub struct HashtagServices {
+    pub hashtag_repository: HashtagRepository,
+}
+
+impl HashtagServices {
+    /* Hashtags used by the most posts created within the last `hours` */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn trending(&self, hours: u64, limit: u64) -> Result<Vec<PayloadHashtagCount>> {
+        let since = Utc::now() - Duration::hours(hours as i64);
+        self.hashtag_repository
+            .trending(surrealdb::sql::Datetime::from(since), limit)
+            .await
+    }
+}
+
+/* Hashtag as stored, without its `#` and lowercased */
+pub fn normalize(tag: &str) -> String {
+    tag.trim().trim_start_matches('#').to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashtags_are_looked_up_like_they_are_stored() {
+        assert_eq!(normalize("#LegDay"), "legday");
+        assert_eq!(normalize(" squats "), "squats");
+    }
+}
@@ -0,0 +1,29 @@
+use database::db::{Connection, DatabaseClient};
+use errors::Result;
+use redis::Client;
+use std::sync::Arc;
+
+#[derive(Clone)]
+pub struct HealthServices {
+    pub repo: Arc<DatabaseClient>,
+    pub redis_client: Client,
+}
+
+impl HealthServices {
+    #[tracing::instrument(err, skip_all)]
+    pub async fn database(&self) -> Result<()> {
+        self.repo.ping().await
+    }
+
+    /* Per-table query latency histograms in the Prometheus text format */
+    pub fn metrics(&self) -> String {
+        self.repo.metrics().render()
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn redis(&self) -> Result<()> {
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let _: String = redis::cmd("PING").query_async(&mut connection).await?;
+        Ok(())
+    }
+}
@@ -1,8 +1,19 @@
 pub mod auth;
+pub mod cache;
+pub mod class;
+pub mod comment;
+pub mod content;
 pub mod email;
 pub mod feed;
+pub mod follow;
 pub mod gym;
 pub mod gymseeker;
+pub mod hashtag;
+pub mod health;
 pub mod location;
+pub mod membership;
+pub mod message;
+pub mod notification;
 pub mod post;
+pub mod reaction;
 pub mod trainer;
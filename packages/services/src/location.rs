@@ -1,8 +1,13 @@
 use chrono::Utc;
 
+use crate::cache::Cache;
+
 use repository::{gym::GymRepository, location::LocationRepository, user::UserRepository};
 
-use database::model::{Id, Location, PayloadLocationRequest};
+use database::{
+    id::{GymId, LocationId, UserId},
+    model::{Location, PayloadLocationRequest},
+};
 use errors::Result;
 
 #[derive(Clone)]
@@ -10,12 +15,16 @@ pub struct LocationServices {
     pub location_repository: LocationRepository,
     pub user_repository: UserRepository,
     pub gym_repository: GymRepository,
+    pub cache: Cache,
 }
 
 impl LocationServices {
     #[tracing::instrument(err, skip_all)]
-    #[tracing::instrument(err, skip_all)]
-    pub async fn register_location(&self, data: &Location, user_id: &str) -> Result<Option<Id>> {
+    pub async fn register_location(
+        &self,
+        data: &Location,
+        user_id: &UserId,
+    ) -> Result<Option<LocationId>> {
         let is_gym = self.location_repository.is_user_a_gym(user_id).await?.0;
 
         if !is_gym {
@@ -25,7 +34,7 @@ impl LocationServices {
             )));
         }
 
-        let insert_into_location_tb: Option<Id> = self
+        let insert_into_location_tb: Option<LocationId> = self
             .location_repository
             .insert_data(data.clone(), user_id)
             .await?;
@@ -36,7 +45,7 @@ impl LocationServices {
     pub async fn update_location(
         &self,
         payload: &PayloadLocationRequest,
-        gym_id: &str,
+        gym_id: &GymId,
     ) -> Result<bool> {
         let is_gym = self.gym_repository.is_gym_data_empty_by_id(gym_id).await?;
 
@@ -49,32 +58,34 @@ impl LocationServices {
 
         let location_id = is_gym
             .1
-            .first()
-            .unwrap()
-            .location_id
-            .clone()
-            .unwrap()
-            .to_string();
-
-        let (_, existing_data) = self.location_repository.get_location(&location_id).await?;
+            .into_iter()
+            .next()
+            .and_then(|gym| gym.location_id)
+            .ok_or_else(|| errors::Error::DataNotAvaliable(format!("location of {}", gym_id)))?;
 
-        let existing_record = existing_data
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(location_id.clone()))?;
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
+        // The creation timestamp is left untouched, only the coordinates and address are written
         let data = PayloadLocationRequest {
             address: payload.address.clone(),
             latitude: payload.latitude,
             longitude: payload.longitude,
-            created_at: existing_record.created_at.clone(),
+            created_at: None,
             updated_at: Some(time_now),
+            version: payload.version,
         };
 
         let updated_data = self
             .location_repository
-            .update_data(location_id, data)
+            .update_data(&location_id, data)
             .await?;
+        if !updated_data {
+            return Err(errors::Error::DataNotAvaliable(location_id.to_string()));
+        }
+
+        // The feed shows the gym location next to its posts
+        self.cache.invalidate_feed().await;
+
         Ok(updated_data)
     }
 }
@@ -0,0 +1,428 @@
+use chrono::{Duration, Utc};
+
+use crate::notification;
+use database::{
+    id::{GymId, GymSeekerId, MembershipId, MembershipPlanId, ProfileId, UserId},
+    model::{
+        Membership, MembershipPlan, MembershipRenewal, MembershipStatus, Notification,
+        NotificationKind, Paginated, PayloadMembershipPlanRequest, PayloadMembershipPlanResponse,
+        PayloadMembershipResponse,
+    },
+    query::Cursor,
+};
+use errors::Result;
+use repository::{
+    follow::FollowRepository, membership::MembershipRepository,
+    notification::NotificationRepository,
+};
+use surrealdb::sql::Datetime;
+
+/// Longest a single membership plan may run
+const MAX_PLAN_DURATION_DAYS: i64 = 3660;
+
+#[derive(Clone, Debug)]
+pub struct MembershipServices {
+    pub membership_repository: MembershipRepository,
+    pub follow_repository: FollowRepository,
+    pub notification_repository: NotificationRepository,
+}
+
+impl MembershipServices {
+    #[tracing::instrument(err, skip_all)]
+    pub async fn create_plan(
+        &self,
+        gym_id: &GymId,
+        payload: PayloadMembershipPlanRequest,
+    ) -> Result<Option<MembershipPlanId>> {
+        check_plan(&payload)?;
+        let (Some(name), Some(price_cents), Some(duration_days)) =
+            (payload.name, payload.price_cents, payload.duration_days)
+        else {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "A plan needs a name, a price and a duration",
+            )));
+        };
+
+        let time_now = Datetime::from(Utc::now());
+        let data = MembershipPlan {
+            id: None,
+            gym_id: gym_id.clone(),
+            name,
+            price_cents,
+            duration_days,
+            benefits: payload.benefits.unwrap_or_default(),
+            archived_at: None,
+            created_at: time_now.clone(),
+            updated_at: time_now,
+            version: 0,
+        };
+
+        self.membership_repository.insert_plan(data).await
+    }
+
+    /* Change the fields sent of one of the gym's plans */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update_plan(
+        &self,
+        gym_id: &GymId,
+        id: &MembershipPlanId,
+        payload: PayloadMembershipPlanRequest,
+    ) -> Result<bool> {
+        check_plan(&payload)?;
+        let plan = self.owned_plan(gym_id, id).await?;
+        // Archived plans are kept only for the memberships already on them
+        if plan.archived_at.is_some() {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "An archived plan cannot be updated",
+            )));
+        }
+
+        let data = PayloadMembershipPlanRequest {
+            updated_at: Some(Datetime::from(Utc::now())),
+            ..payload
+        };
+        self.membership_repository.update_plan(id, data).await
+    }
+
+    /* Stop selling one of the gym's plans */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn archive_plan(&self, gym_id: &GymId, id: &MembershipPlanId) -> Result<bool> {
+        self.owned_plan(gym_id, id).await?;
+
+        let archived_at = Datetime::from(Utc::now());
+        self.membership_repository
+            .archive_plan(id, archived_at)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn plans(
+        &self,
+        gym_id: &GymId,
+        include_archived: bool,
+    ) -> Result<Vec<PayloadMembershipPlanResponse>> {
+        self.membership_repository
+            .plans(gym_id, include_archived)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn members(
+        &self,
+        gym_id: &GymId,
+        active: bool,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadMembershipResponse>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        let now = Datetime::from(Utc::now());
+        self.membership_repository
+            .by_gym(gym_id, active, now, cursor, limit)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn memberships(
+        &self,
+        gym_seeker_id: &GymSeekerId,
+    ) -> Result<Vec<PayloadMembershipResponse>> {
+        let memberships = self
+            .membership_repository
+            .by_gym_seeker(gym_seeker_id)
+            .await?;
+        let now = Datetime::from(Utc::now());
+        self.membership_repository
+            .responses(memberships, &now)
+            .await
+    }
+
+    /* Join the gym selling the plan, or join it again once the previous membership ended */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn join(
+        &self,
+        user_id: &UserId,
+        gym_seeker_id: &GymSeekerId,
+        plan_id: &MembershipPlanId,
+    ) -> Result<MembershipId> {
+        let plan = self
+            .membership_repository
+            .get_plan(plan_id)
+            .await?
+            .filter(|plan| plan.archived_at.is_none())
+            .ok_or_else(|| errors::Error::DataNotAvaliable(plan_id.to_string()))?;
+
+        let start_at = Utc::now();
+        let renewal = MembershipRenewal {
+            plan_id: plan_id.clone(),
+            start_at: Datetime::from(start_at),
+            end_at: Datetime::from(start_at + Duration::days(plan.duration_days)),
+            cancelled_at: None,
+        };
+
+        let existing = self
+            .membership_repository
+            .find(gym_seeker_id, &plan.gym_id)
+            .await?;
+        let membership_id = match existing {
+            Some(membership) => {
+                if membership.status(&renewal.start_at) == MembershipStatus::Active {
+                    return Err(errors::Error::DataExist(format!(
+                        "membership in {}",
+                        plan.gym_id
+                    )));
+                }
+                let id = membership.id.ok_or_else(|| {
+                    errors::Error::DatabaseError(String::from("Membership has no id"))
+                })?;
+                // A concurrent join or cancel bumps the version first and this one fails with Conflict
+                self.membership_repository
+                    .renew(&id, renewal, membership.version)
+                    .await?;
+                id
+            }
+            None => {
+                let data = Membership {
+                    id: None,
+                    gym_id: plan.gym_id.clone(),
+                    gym_seeker_id: gym_seeker_id.clone(),
+                    plan_id: renewal.plan_id,
+                    start_at: renewal.start_at.clone(),
+                    end_at: renewal.end_at,
+                    cancelled_at: None,
+                    created_at: renewal.start_at,
+                    version: 0,
+                };
+                // The pair index fails a concurrent first join with DataExist
+                self.membership_repository
+                    .insert_data(data)
+                    .await?
+                    .ok_or_else(|| {
+                        errors::Error::DatabaseError(String::from("Membership was not created"))
+                    })?
+            }
+        };
+
+        let gym = ProfileId::Gym(plan.gym_id);
+        let member = ProfileId::GymSeeker(gym_seeker_id.clone());
+        self.notify(&gym, user_id, member, NotificationKind::MembershipJoined)
+            .await;
+
+        Ok(membership_id)
+    }
+
+    /* Cancel an active membership, allowed to the member and to the gym */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn cancel(
+        &self,
+        user_id: &UserId,
+        profile: &ProfileId,
+        id: &MembershipId,
+    ) -> Result<bool> {
+        // A membership of someone else is reported missing rather than revealed
+        let membership = self
+            .membership_repository
+            .get(id)
+            .await?
+            .filter(|membership| is_party(profile, membership))
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
+
+        let cancelled_at = Datetime::from(Utc::now());
+        if membership.status(&cancelled_at) != MembershipStatus::Active {
+            return Err(errors::Error::InvalidRequest(format!(
+                "{} is not active",
+                id
+            )));
+        }
+
+        let cancelled = self
+            .membership_repository
+            .cancel(id, cancelled_at, membership.version)
+            .await?;
+
+        let other = match profile {
+            ProfileId::Gym(_) => ProfileId::GymSeeker(membership.gym_seeker_id),
+            _ => ProfileId::Gym(membership.gym_id),
+        };
+        self.notify(
+            &other,
+            user_id,
+            profile.clone(),
+            NotificationKind::MembershipCancelled,
+        )
+        .await;
+
+        Ok(cancelled)
+    }
+
+    /* The plan when it belongs to the gym, another gym's plan is reported missing */
+    async fn owned_plan(&self, gym_id: &GymId, id: &MembershipPlanId) -> Result<MembershipPlan> {
+        self.membership_repository
+            .get_plan(id)
+            .await?
+            .filter(|plan| &plan.gym_id == gym_id)
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))
+    }
+
+    /* Tell the owner of `recipient` what the profile `actor` acts as did */
+    async fn notify(
+        &self,
+        recipient: &ProfileId,
+        user_id: &UserId,
+        actor: ProfileId,
+        kind: NotificationKind,
+    ) {
+        let notifications = self
+            .follow_repository
+            .profile_owner(recipient)
+            .await
+            .map(|owner| {
+                owner
+                    .and_then(|owner| notification::notification(&owner, user_id, kind))
+                    .map(|notification| Notification {
+                        profile_id: Some(actor),
+                        ..notification
+                    })
+                    .into_iter()
+                    .collect()
+            });
+        notification::send(&self.notification_repository, notifications).await;
+    }
+}
+
+/* Reject plan fields that were sent with a value no plan can have */
+fn check_plan(payload: &PayloadMembershipPlanRequest) -> Result<()> {
+    if payload
+        .name
+        .as_ref()
+        .is_some_and(|name| name.trim().is_empty())
+    {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "A plan name cannot be empty",
+        )));
+    }
+    if payload.price_cents.is_some_and(|price| price < 0) {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "A plan price cannot be negative",
+        )));
+    }
+    if payload
+        .duration_days
+        .is_some_and(|days| !(1..=MAX_PLAN_DURATION_DAYS).contains(&days))
+    {
+        return Err(errors::Error::InvalidRequest(format!(
+            "A plan lasts between 1 and {} days",
+            MAX_PLAN_DURATION_DAYS
+        )));
+    }
+    Ok(())
+}
+
+/* Whether the profile is the member or the gym of the membership */
+fn is_party(profile: &ProfileId, membership: &Membership) -> bool {
+    match profile {
+        ProfileId::Gym(gym_id) => gym_id == &membership.gym_id,
+        ProfileId::GymSeeker(gym_seeker_id) => gym_seeker_id == &membership.gym_seeker_id,
+        ProfileId::Trainer(_) => false,
+    }
+}
+
+/* Most relevant status of the memberships: active in any gym, else expired, else cancelled */
+pub fn derived_status(memberships: &[Membership], now: &Datetime) -> Option<MembershipStatus> {
+    memberships
+        .iter()
+        .map(|membership| membership.status(now))
+        .max()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn membership(end_at: Datetime, cancelled_at: Option<Datetime>) -> Membership {
+        Membership {
+            id: None,
+            gym_id: "gym:a".parse().unwrap(),
+            gym_seeker_id: "gym_seeker:a".parse().unwrap(),
+            plan_id: "membership_plan:a".parse().unwrap(),
+            start_at: Datetime::from(Utc::now() - Duration::days(30)),
+            end_at,
+            cancelled_at,
+            created_at: Datetime::default(),
+            version: 0,
+        }
+    }
+
+    #[test]
+    fn status_follows_the_dates() {
+        let now = Datetime::from(Utc::now());
+        let later = Datetime::from(Utc::now() + Duration::days(1));
+        let earlier = Datetime::from(Utc::now() - Duration::days(1));
+
+        assert_eq!(
+            membership(later.clone(), None).status(&now),
+            MembershipStatus::Active
+        );
+        assert_eq!(
+            membership(earlier, None).status(&now),
+            MembershipStatus::Expired
+        );
+        assert_eq!(
+            membership(later, Some(now.clone())).status(&now),
+            MembershipStatus::Cancelled
+        );
+    }
+
+    #[test]
+    fn an_active_membership_wins_over_ended_ones() {
+        let now = Datetime::from(Utc::now());
+        let later = Datetime::from(Utc::now() + Duration::days(1));
+        let earlier = Datetime::from(Utc::now() - Duration::days(1));
+
+        let cancelled = membership(later.clone(), Some(now.clone()));
+        let expired = membership(earlier, None);
+        let active = membership(later, None);
+
+        assert_eq!(derived_status(&[], &now), None);
+        assert_eq!(
+            derived_status(&[cancelled.clone(), expired.clone()], &now),
+            Some(MembershipStatus::Expired)
+        );
+        assert_eq!(
+            derived_status(&[cancelled, active, expired], &now),
+            Some(MembershipStatus::Active)
+        );
+    }
+
+    #[test]
+    fn plan_fields_are_checked_when_sent() {
+        let payload = PayloadMembershipPlanRequest {
+            name: None,
+            price_cents: None,
+            duration_days: None,
+            benefits: None,
+            updated_at: None,
+            version: None,
+        };
+        assert!(check_plan(&payload).is_ok());
+
+        for invalid in [
+            PayloadMembershipPlanRequest {
+                name: Some(String::from(" ")),
+                ..payload.clone()
+            },
+            PayloadMembershipPlanRequest {
+                price_cents: Some(-1),
+                ..payload.clone()
+            },
+            PayloadMembershipPlanRequest {
+                duration_days: Some(0),
+                ..payload.clone()
+            },
+        ] {
+            assert!(matches!(
+                check_plan(&invalid),
+                Err(errors::Error::InvalidRequest(_))
+            ));
+        }
+    }
+}
@@ -0,0 +1,331 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use futures::{stream, Stream, StreamExt};
+use redis::{AsyncCommands, Client};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+use database::{
+    id::{ConversationId, UserId},
+    model::{
+        Conversation, Message, Paginated, PayloadConversationResponse, PayloadMessageResponse,
+    },
+    query::Cursor,
+};
+use errors::Result;
+use repository::message::{self, MessageRepository};
+
+/// Redis channel every server instance publishes direct message events on and listens to
+const MESSAGE_CHANNEL: &str = "direct_messages";
+/// Events buffered for a socket that falls behind before it starts skipping them
+const MESSAGE_EVENT_CAPACITY: usize = 256;
+/// Longest message that is accepted, in characters
+pub const MAX_MESSAGE_LENGTH: usize = 4000;
+
+#[derive(Clone)]
+pub struct MessageServices {
+    pub message_repository: MessageRepository,
+    pub redis_client: Client,
+    pub events: MessageEvents,
+}
+
+/* Event pushed to the sockets of the participants of a conversation */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MessageEvent {
+    Message(PayloadMessageResponse),
+    Typing {
+        conversation_id: String,
+        user_id: String,
+    },
+    /// The user read every message sent to them in the conversation so far
+    Read {
+        conversation_id: String,
+        user_id: String,
+        read_at: surrealdb::sql::Datetime,
+    },
+}
+
+/* Event together with the users it goes to, as published on Redis */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct Envelope {
+    recipients: Vec<String>,
+    event: MessageEvent,
+}
+
+/* One Redis subscription shared by every socket of this instance, started by the first one */
+#[derive(Clone, Default)]
+pub struct MessageEvents {
+    sender: Arc<Mutex<Option<broadcast::Sender<Envelope>>>>,
+}
+
+impl MessageServices {
+    /* Conversation with another user, started when they have none yet */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn start(&self, user_id: &UserId, other: &UserId) -> Result<ConversationId> {
+        if user_id == other {
+            return Err(errors::Error::InvalidRequest(String::from(
+                "A user cannot message themselves",
+            )));
+        }
+
+        if !self.message_repository.user_exists(other).await? {
+            return Err(errors::Error::DataNotAvaliable(other.to_string()));
+        }
+
+        let created_at = surrealdb::sql::Datetime::from(Utc::now());
+        let conversation = self
+            .message_repository
+            .start(user_id, other, created_at)
+            .await?;
+
+        conversation
+            .id
+            .ok_or_else(|| errors::Error::DataNotAvaliable(other.to_string()))
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn conversations(
+        &self,
+        user_id: &UserId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadConversationResponse>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.message_repository
+            .conversations(user_id, cursor, limit)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn messages(
+        &self,
+        user_id: &UserId,
+        id: &ConversationId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadMessageResponse>> {
+        self.conversation(user_id, id).await?;
+
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.message_repository.messages(id, cursor, limit).await
+    }
+
+    /* Store the message and push it to both participants */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn send(
+        &self,
+        user_id: &UserId,
+        id: &ConversationId,
+        content: String,
+    ) -> Result<PayloadMessageResponse> {
+        check_content(&content)?;
+        let conversation = self.conversation(user_id, id).await?;
+
+        let mut data = Message {
+            id: None,
+            conversation_id: id.clone(),
+            sender_id: user_id.clone(),
+            content,
+            read_at: None,
+            created_at: surrealdb::sql::Datetime::from(Utc::now()),
+        };
+        data.id = self.message_repository.insert_message(data.clone()).await?;
+
+        let message = message::response(data);
+        self.publish(
+            participants(&conversation),
+            MessageEvent::Message(message.clone()),
+        )
+        .await;
+
+        Ok(message)
+    }
+
+    /* Tell the other participant the user is typing */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn typing(&self, user_id: &UserId, id: &ConversationId) -> Result<()> {
+        let conversation = self.conversation(user_id, id).await?;
+
+        let event = MessageEvent::Typing {
+            conversation_id: id.to_string(),
+            user_id: user_id.to_string(),
+        };
+        self.publish(
+            vec![conversation.other_participant(user_id).to_string()],
+            event,
+        )
+        .await;
+
+        Ok(())
+    }
+
+    /* Mark the messages the user received in the conversation read, returning how many were unread */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn mark_read(&self, user_id: &UserId, id: &ConversationId) -> Result<u64> {
+        let conversation = self.conversation(user_id, id).await?;
+
+        let read_at = surrealdb::sql::Datetime::from(Utc::now());
+        let marked = self
+            .message_repository
+            .mark_read(id, user_id, read_at.clone())
+            .await?;
+
+        // The sender sees the receipt, the reader's other sockets clear their unread badge
+        if marked > 0 {
+            let event = MessageEvent::Read {
+                conversation_id: id.to_string(),
+                user_id: user_id.to_string(),
+                read_at,
+            };
+            self.publish(participants(&conversation), event).await;
+        }
+
+        Ok(marked)
+    }
+
+    /* Events for the user from now on, the stream ends when the shared Redis subscription does */
+    pub async fn subscribe(&self, user_id: &UserId) -> Result<impl Stream<Item = MessageEvent>> {
+        let mut sender = self.events.sender.lock().await;
+        let receiver = match sender.as_ref() {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let mut subscription = self.redis_client.get_async_pubsub().await?;
+                subscription.subscribe(MESSAGE_CHANNEL).await?;
+                let (publisher, receiver) = broadcast::channel(MESSAGE_EVENT_CAPACITY);
+                *sender = Some(publisher.clone());
+                tokio::spawn(
+                    self.clone()
+                        .forward(subscription.into_on_message().boxed(), publisher),
+                );
+                receiver
+            }
+        };
+
+        let user_id = user_id.to_string();
+        Ok(stream::unfold(receiver, move |mut receiver| {
+            let user_id = user_id.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(envelope) if envelope.recipients.contains(&user_id) => {
+                            return Some((envelope.event, receiver))
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => {
+                            tracing::warn!("Message socket skipped {} events", skipped)
+                        }
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        }))
+    }
+
+    /* Conversation the user takes part in, others are reported missing rather than revealed */
+    async fn conversation(&self, user_id: &UserId, id: &ConversationId) -> Result<Conversation> {
+        self.message_repository
+            .get_conversation(id)
+            .await?
+            .filter(|conversation| conversation.has_participant(user_id))
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))
+    }
+
+    /* Hand the event to every instance, a failure is logged as the message is already stored */
+    async fn publish(&self, recipients: Vec<String>, event: MessageEvent) {
+        let envelope = Envelope { recipients, event };
+        let published = match serde_json::to_string(&envelope) {
+            Ok(payload) => self.publish_payload(payload).await,
+            Err(error) => Err(errors::Error::DatabaseError(error.to_string())),
+        };
+
+        if let Err(error) = published {
+            tracing::warn!("Dropping a message event: {}", error);
+        }
+    }
+
+    async fn publish_payload(&self, payload: String) -> Result<()> {
+        let mut connection = self.redis_client.get_multiplexed_async_connection().await?;
+        let _: () = connection.publish(MESSAGE_CHANNEL, payload).await?;
+        Ok(())
+    }
+
+    async fn forward(
+        self,
+        mut messages: stream::BoxStream<'static, redis::Msg>,
+        sender: broadcast::Sender<Envelope>,
+    ) {
+        while let Some(message) = messages.next().await {
+            let envelope = message
+                .get_payload::<String>()
+                .map_err(|error| error.to_string())
+                .and_then(|payload| {
+                    serde_json::from_str::<Envelope>(&payload).map_err(|error| error.to_string())
+                });
+
+            match envelope {
+                // Sending only fails while no socket is open, which is not an error
+                Ok(envelope) => {
+                    let _ = sender.send(envelope);
+                }
+                Err(error) => tracing::warn!("Dropping a message event: {}", error),
+            }
+        }
+
+        // Sockets see their stream end and reconnect, which subscribes again
+        tracing::warn!("Direct message subscription ended");
+        self.events.sender.lock().await.take();
+    }
+}
+
+/* Both participants of the conversation */
+fn participants(conversation: &Conversation) -> Vec<String> {
+    vec![
+        conversation.first_user_id.to_string(),
+        conversation.second_user_id.to_string(),
+    ]
+}
+
+/* Messages carry some text and stay within MAX_MESSAGE_LENGTH */
+fn check_content(content: &str) -> Result<()> {
+    if content.trim().is_empty() {
+        return Err(errors::Error::InvalidRequest(String::from(
+            "A message cannot be empty",
+        )));
+    }
+
+    if content.chars().count() > MAX_MESSAGE_LENGTH {
+        return Err(errors::Error::InvalidRequest(format!(
+            "A message cannot be longer than {} characters",
+            MAX_MESSAGE_LENGTH
+        )));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn messages_need_text_within_the_length_limit() {
+        assert!(check_content("hi").is_ok());
+        assert!(check_content(" \n ").is_err());
+        assert!(check_content(&"a".repeat(MAX_MESSAGE_LENGTH)).is_ok());
+        assert!(check_content(&"a".repeat(MAX_MESSAGE_LENGTH + 1)).is_err());
+    }
+
+    #[test]
+    fn events_are_tagged_with_their_type() {
+        let event = MessageEvent::Typing {
+            conversation_id: String::from("conversation:c"),
+            user_id: String::from("user:u"),
+        };
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["type"], "typing");
+        assert_eq!(serde_json::from_value::<MessageEvent>(json).unwrap(), event);
+    }
+}
@@ -0,0 +1,214 @@
+use std::sync::Arc;
+
+use chrono::Utc;
+use futures::{stream, Stream, StreamExt};
+use tokio::sync::{broadcast, broadcast::error::RecvError, Mutex};
+
+use database::{
+    id::{NotificationId, UserId},
+    live::{Change, LiveStream},
+    model::{Notification, NotificationKind, Paginated, PayloadNotificationResponse},
+    query::Cursor,
+};
+use errors::Result;
+use repository::notification::NotificationRepository;
+
+/// Notifications buffered for a subscriber that falls behind before it starts skipping them
+const NOTIFICATION_EVENT_CAPACITY: usize = 256;
+
+#[derive(Clone)]
+pub struct NotificationServices {
+    pub notification_repository: NotificationRepository,
+    pub events: NotificationEvents,
+}
+
+/* Notification pushed to the stream of the user it is for */
+#[derive(Clone, Debug)]
+pub struct NotificationEvent {
+    pub user_id: UserId,
+    pub notification: PayloadNotificationResponse,
+}
+
+/* One live query on the notification table shared by every stream subscriber, started by the first one */
+#[derive(Clone, Default)]
+pub struct NotificationEvents {
+    sender: Arc<Mutex<Option<broadcast::Sender<NotificationEvent>>>>,
+}
+
+impl NotificationServices {
+    #[tracing::instrument(err, skip_all)]
+    pub async fn get_list(
+        &self,
+        user_id: &UserId,
+        unread: bool,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadNotificationResponse>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        self.notification_repository
+            .by_user(user_id, unread, cursor, limit)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn unread_count(&self, user_id: &UserId) -> Result<u64> {
+        self.notification_repository.unread_count(user_id).await
+    }
+
+    /* Mark one of the user's notifications read, false when it already was */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn mark_read(&self, user_id: &UserId, id: &NotificationId) -> Result<bool> {
+        // Another user's notification is reported missing rather than revealed
+        let notification = self
+            .notification_repository
+            .get(id)
+            .await?
+            .filter(|notification| &notification.user_id == user_id)
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
+
+        let read_at = surrealdb::sql::Datetime::from(Utc::now());
+        self.notification_repository
+            .mark_read(&notification, read_at)
+            .await
+    }
+
+    /* Mark every notification of the user read, returning how many were unread */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn mark_all_read(&self, user_id: &UserId) -> Result<u64> {
+        let read_at = surrealdb::sql::Datetime::from(Utc::now());
+        self.notification_repository
+            .mark_all_read(user_id, read_at)
+            .await
+    }
+
+    /* Notifications of the user from now on, the stream ends when the shared live query does */
+    pub async fn stream(
+        &self,
+        user_id: &UserId,
+    ) -> Result<impl Stream<Item = PayloadNotificationResponse>> {
+        let mut sender = self.events.sender.lock().await;
+        let receiver = match sender.as_ref() {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let changes = self.notification_repository.live().await?;
+                let (publisher, receiver) = broadcast::channel(NOTIFICATION_EVENT_CAPACITY);
+                *sender = Some(publisher.clone());
+                tokio::spawn(self.clone().publish(changes, publisher));
+                receiver
+            }
+        };
+
+        let user_id = user_id.clone();
+        Ok(stream::unfold(receiver, move |mut receiver| {
+            let user_id = user_id.clone();
+            async move {
+                loop {
+                    match receiver.recv().await {
+                        Ok(event) if event.user_id == user_id => {
+                            return Some((event.notification, receiver))
+                        }
+                        Ok(_) => {}
+                        Err(RecvError::Lagged(skipped)) => tracing::warn!(
+                            "Notification stream subscriber skipped {} events",
+                            skipped
+                        ),
+                        Err(RecvError::Closed) => return None,
+                    }
+                }
+            }
+        }))
+    }
+
+    async fn publish(
+        self,
+        mut changes: LiveStream<Notification>,
+        sender: broadcast::Sender<NotificationEvent>,
+    ) {
+        while let Some(change) = changes.next().await {
+            // Only new notifications are pushed, reading them is not news to anyone
+            let notification = match change {
+                Ok(Change::Create(notification)) => notification,
+                Ok(_) => continue,
+                Err(error) => {
+                    tracing::warn!("Dropping a notification event: {}", error);
+                    continue;
+                }
+            };
+
+            let user_id = notification.user_id.clone();
+            match self
+                .notification_repository
+                .responses(vec![notification])
+                .await
+            {
+                // Sending only fails while nobody is subscribed, which is not an error
+                Ok(responses) => {
+                    for notification in responses {
+                        let _ = sender.send(NotificationEvent {
+                            user_id: user_id.clone(),
+                            notification,
+                        });
+                    }
+                }
+                Err(error) => tracing::warn!("Dropping a notification event: {}", error),
+            }
+        }
+
+        // Subscribers see their stream end and reconnect, which starts a new live query
+        tracing::warn!("Notification live query ended");
+        self.events.sender.lock().await.take();
+    }
+}
+
+/* Notification of `recipient` about what `actor` did, None when they are the same user */
+pub fn notification(
+    recipient: &UserId,
+    actor: &UserId,
+    kind: NotificationKind,
+) -> Option<Notification> {
+    if recipient == actor {
+        return None;
+    }
+
+    Some(Notification {
+        id: None,
+        user_id: recipient.clone(),
+        actor_id: actor.clone(),
+        kind,
+        profile_id: None,
+        post_id: None,
+        comment_id: None,
+        read_at: None,
+        created_at: surrealdb::sql::Datetime::from(Utc::now()),
+    })
+}
+
+/* Store the notifications, a failure is logged instead of failing the action that caused them */
+pub async fn send(repository: &NotificationRepository, notifications: Result<Vec<Notification>>) {
+    let stored = match notifications {
+        Ok(notifications) => repository.insert_data(notifications).await,
+        Err(error) => Err(error),
+    };
+
+    if let Err(error) = stored {
+        tracing::warn!("Dropping notifications: {}", error);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn users_are_not_notified_about_themselves() {
+        let user = |id: &str| -> UserId { id.parse().unwrap() };
+
+        assert!(notification(&user("user:a"), &user("user:a"), NotificationKind::Follow).is_none());
+
+        let notification =
+            notification(&user("user:a"), &user("user:b"), NotificationKind::Comment).unwrap();
+        assert_eq!(notification.user_id, user("user:a"));
+        assert_eq!(notification.actor_id, user("user:b"));
+        assert!(notification.read_at.is_none());
+    }
+}
@@ -1,14 +1,23 @@
-use chrono::Utc;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::{cache::Cache, content, notification};
 use futures::{stream, StreamExt};
 
 use repository::{
-    gym::GymRepository, gymseeker::GymSeekerRepository, location::LocationRepository,
-    post::PostRepository, trainer::TrainerRepository, user::UserRepository,
+    gym::GymRepository, gymseeker::GymSeekerRepository, hashtag::HashtagRepository,
+    location::LocationRepository, mention::MentionRepository, notification::NotificationRepository,
+    post::PostRepository, reaction::ReactionRepository, trainer::TrainerRepository,
+    user::UserRepository,
 };
 
-use database::model::{
-    Gym, GymSeeker, Id, PayloadPostResponseDeserilize, Post, PostGallery,
-    PostGalleryForFeedDeserialize, Trainer,
+use database::{
+    id::{GymId, GymSeekerId, PostId, ProfileId, TrainerId, UserId},
+    model::{
+        Gym, GymSeeker, Id, Notification, NotificationKind, Paginated,
+        PayloadPostResponseDeserilize, Post, PostContentUpdate, PostGallery,
+        PostGalleryForFeedDeserialize, Trainer,
+    },
+    query::Cursor,
 };
 use errors::Result;
 
@@ -20,17 +29,25 @@ pub struct PostServices {
     pub trainer_repository: TrainerRepository,
     pub post_repository: PostRepository,
     pub location_repository: LocationRepository,
+    pub reaction_repository: ReactionRepository,
+    pub hashtag_repository: HashtagRepository,
+    pub mention_repository: MentionRepository,
+    pub notification_repository: NotificationRepository,
+    pub cache: Cache,
 }
 
 impl PostServices {
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_post_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<Post>)> {
+    pub async fn is_post_data_empty_by_id(&self, id: &PostId) -> Result<(bool, Vec<Post>)> {
         let data_exists = self.post_repository.is_post_empty_by_id(id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_post_data_empty_by_user_id(&self, user_id: &str) -> Result<(bool, Vec<Post>)> {
+    pub async fn is_post_data_empty_by_user_id(
+        &self,
+        user_id: &UserId,
+    ) -> Result<(bool, Vec<Post>)> {
         let data_exists = self
             .post_repository
             .is_post_empty_by_user_id(user_id)
@@ -39,19 +56,22 @@ impl PostServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<Gym>)> {
+    pub async fn is_gym_data_empty_by_id(&self, id: &GymId) -> Result<(bool, Vec<Gym>)> {
         let data_exists = self.gym_repository.is_gym_data_empty_by_id(id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_user_empty(&self, user_id: &str) -> Result<(bool, Vec<Gym>)> {
+    pub async fn is_gym_user_empty(&self, user_id: &UserId) -> Result<(bool, Vec<Gym>)> {
         let data_exists = self.gym_repository.is_gym_data_empty(user_id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_seeker_user_empty(&self, user_id: &str) -> Result<(bool, Vec<GymSeeker>)> {
+    pub async fn is_gym_seeker_user_empty(
+        &self,
+        user_id: &UserId,
+    ) -> Result<(bool, Vec<GymSeeker>)> {
         let data_exists = self
             .gym_seeker_repository
             .is_gym_seeker_data_empty_by_user_id(user_id)
@@ -60,7 +80,10 @@ impl PostServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_gym_seeker_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<GymSeeker>)> {
+    pub async fn is_gym_seeker_data_empty_by_id(
+        &self,
+        id: &GymSeekerId,
+    ) -> Result<(bool, Vec<GymSeeker>)> {
         let data_exists = self
             .gym_seeker_repository
             .is_gym_seeker_data_empty_by_id(id)
@@ -69,7 +92,10 @@ impl PostServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_trainer_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<Trainer>)> {
+    pub async fn is_trainer_data_empty_by_id(
+        &self,
+        id: &TrainerId,
+    ) -> Result<(bool, Vec<Trainer>)> {
         let data_exists = self
             .trainer_repository
             .is_trainer_data_empty_by_id(id)
@@ -78,7 +104,7 @@ impl PostServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_trainer_user_empty(&self, user_id: &str) -> Result<(bool, Vec<Trainer>)> {
+    pub async fn is_trainer_user_empty(&self, user_id: &UserId) -> Result<(bool, Vec<Trainer>)> {
         let data_exists = self
             .trainer_repository
             .is_trainer_data_empty(user_id)
@@ -86,178 +112,260 @@ impl PostServices {
         Ok(data_exists)
     }
     #[tracing::instrument(err, skip_all)]
-    pub async fn insert_post_gallery(&self, payload: PostGallery) -> Result<Option<Id>> {
+    pub async fn insert_post_galleries(&self, payload: Vec<PostGallery>) -> Result<Vec<Id>> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
-        let mut data = payload;
-        data.created_at = Some(time_now.clone());
+        // Every image of the upload is written with one insert
+        let data: Vec<PostGallery> = payload
+            .into_iter()
+            .map(|mut gallery| {
+                gallery.created_at = Some(time_now.clone());
+                gallery
+            })
+            .collect();
 
-        let result = self.post_repository.insert_post_gallery(data).await?;
+        let result = self.post_repository.insert_post_galleries(data).await?;
+        self.cache.invalidate_feed().await;
         Ok(result)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn create(&self, id: String, user_type: &str, content: String) -> Result<Option<Id>> {
+    pub async fn create(&self, id: &ProfileId, content: String) -> Result<Option<PostId>> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
-        let (_, gym_temp_data) = match id.split(':').next() {
-            Some("gym") => self.is_gym_data_empty_by_id(&id).await?,
-            _ => self.is_gym_user_empty(&id).await?,
-        };
-
-        let (_, gym_seeker_temp_data) = match id.split(':').next() {
-            Some("gym_seeker") => self.is_gym_seeker_data_empty_by_id(&id).await?,
-            _ => self.is_gym_seeker_user_empty(&id).await?,
-        };
-
-        let (_, trainer_temp_data) = match id.split(':').next() {
-            Some("trainer") => self.is_trainer_data_empty_by_id(&id).await?,
-            _ => self.is_trainer_user_empty(&id).await?,
-        };
-
-        let data = match user_type {
-            "gym" => {
+        let data = match id {
+            ProfileId::Gym(gym_id) => {
+                let (_, gym_temp_data) = self.is_gym_data_empty_by_id(gym_id).await?;
                 let gym_data = gym_temp_data
-                    .first()
-                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-                    .clone();
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
                 Post {
                     id: None,
                     content: Some(content),
                     created_at: Some(time_now.clone()),
-                    user_id: Some(gym_data.clone().owner_id),
-                    gym_id: gym_data.clone().id,
+                    user_id: Some(gym_data.owner_id),
+                    gym_id: gym_data.id,
                     gym_seeker_id: None,
                     trainer_id: None,
                     is_gym: Some(true),
                     is_gym_seeker: Some(false),
                     is_trainer: Some(false),
                     updated_at: Some(time_now.clone()),
+                    version: 0,
+                    deleted_at: None,
                 }
             }
-            "gym_seeker" => {
+            ProfileId::GymSeeker(gym_seeker_id) => {
+                let (_, gym_seeker_temp_data) =
+                    self.is_gym_seeker_data_empty_by_id(gym_seeker_id).await?;
                 let gym_seeker_data = gym_seeker_temp_data
-                    .first()
-                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-                    .clone();
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
                 Post {
                     id: None,
                     content: Some(content),
                     created_at: Some(time_now.clone()),
-                    user_id: gym_seeker_data.clone().user_id,
+                    user_id: gym_seeker_data.user_id,
                     gym_id: None,
-                    gym_seeker_id: gym_seeker_data.clone().id,
+                    gym_seeker_id: gym_seeker_data.id,
                     trainer_id: None,
                     is_gym: Some(false),
                     is_gym_seeker: Some(true),
                     is_trainer: Some(false),
-                    updated_at: Some(time_now),
+                    updated_at: Some(time_now.clone()),
+                    version: 0,
+                    deleted_at: None,
                 }
             }
-            "trainer" => {
+            ProfileId::Trainer(trainer_id) => {
+                let (_, trainer_temp_data) = self.is_trainer_data_empty_by_id(trainer_id).await?;
                 let trainer_data = trainer_temp_data
-                    .first()
-                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-                    .clone();
+                    .into_iter()
+                    .next()
+                    .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
                 Post {
                     id: None,
                     content: Some(content),
                     created_at: Some(time_now.clone()),
-                    user_id: Some(trainer_data.clone().user_id),
+                    user_id: Some(trainer_data.user_id),
                     gym_id: None,
                     gym_seeker_id: None,
-                    trainer_id: trainer_data.clone().id,
+                    trainer_id: trainer_data.id,
                     is_gym: Some(false),
                     is_gym_seeker: Some(false),
                     is_trainer: Some(true),
-                    updated_at: Some(time_now),
+                    updated_at: Some(time_now.clone()),
+                    version: 0,
+                    deleted_at: None,
                 }
             }
-            _ => return Err(errors::Error::InvalidUserType((id).to_string())),
         };
 
-        self.post_repository.insert_data(data).await
+        let author = data.user_id.clone();
+        let content = data.content.clone().unwrap_or_default();
+        let post_id = self.post_repository.insert_data(data).await?;
+        if let Some(post_id) = &post_id {
+            self.index_content(post_id, author.as_ref(), &content, time_now)
+                .await;
+        }
+
+        self.cache.invalidate_feed().await;
+        Ok(post_id)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn update(&self, user_id: String, id: String, content: String) -> Result<bool> {
+    pub async fn update(
+        &self,
+        user_id: &UserId,
+        id: &PostId,
+        content: String,
+        version: Option<i64>,
+    ) -> Result<bool> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
         // Check if post data is empty first
-        let (is_empty, post_vecs) = self.is_post_data_empty_by_id(&id).await?;
+        let (is_empty, post_vecs) = self.is_post_data_empty_by_id(id).await?;
         if is_empty {
-            return Err(errors::Error::DataNotAvaliable(id.clone()));
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
         }
 
         // Safely get the post data, assuming post_vecs is non-empty after the previous check
         let post_data = post_vecs
             .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.clone()))?;
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
 
         // Check if the user_id matches
-        if post_data
-            .user_id
-            .as_ref()
-            .map_or(false, |uid| uid.to_string() != user_id)
-        {
+        if post_data.user_id.as_ref().is_some_and(|uid| uid != user_id) {
             return Err(errors::Error::UserNotVerified(format!(
                 "user {} not authorized to update",
-                id.clone()
+                id
             )));
         }
 
-        // Update post content and timestamp
-        let mut updated_post_data = post_data.clone();
-        updated_post_data.content = Some(content);
-        updated_post_data.updated_at = Some(time_now);
+        // Only the content and timestamp are written, guarded by the version the client read
+        let data = PostContentUpdate {
+            content: content.clone(),
+            updated_at: time_now.clone(),
+        };
+
+        let updated = self.post_repository.update_data(id, data, version).await?;
+        if updated {
+            let created_at = post_data.created_at.clone().unwrap_or(time_now);
+            self.index_content(id, post_data.user_id.as_ref(), &content, created_at)
+                .await;
+        }
 
-        // Call the repository's update function
-        self.post_repository
-            .update_data(id, updated_post_data)
-            .await
+        self.cache.invalidate_feed().await;
+        Ok(updated)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn delete(&self, user_id: String, id: String) -> Result<bool> {
+    pub async fn delete(&self, user_id: &UserId, id: &PostId) -> Result<bool> {
         // Check if post data is empty first
-        let (is_empty, post_vecs) = self.is_post_data_empty_by_id(&id).await?;
+        let (is_empty, post_vecs) = self.is_post_data_empty_by_id(id).await?;
         if is_empty {
-            return Err(errors::Error::DataNotAvaliable(id.clone()));
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
         }
 
         // Safely get the post data, assuming post_vecs is non-empty after the previous check
         let post_data = post_vecs
             .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.clone()))?;
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
 
         // Check if the user_id matches
-        if post_data
-            .user_id
-            .as_ref()
-            .map_or(false, |uid| uid.to_string() != user_id)
-        {
+        if post_data.user_id.as_ref().is_some_and(|uid| uid != user_id) {
             return Err(errors::Error::UserNotVerified(format!(
                 "user {} not authorized to update",
-                id.clone()
+                id
             )));
         }
 
-        self.post_repository.delete_data(id).await
+        let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
+        let deleted = self.post_repository.delete_data(id, time_now).await?;
+        // Hashtags of a deleted post no longer list or trend, restoring indexes them again
+        if let Err(error) = self.hashtag_repository.remove(id).await {
+            tracing::warn!("Keeping the hashtags of deleted post {}: {}", id, error);
+        }
+        self.cache.invalidate_feed().await;
+        Ok(deleted)
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn restore(&self, user_id: &UserId, id: &PostId) -> Result<bool> {
+        let post_vecs = self.post_repository.get_with_deleted(id).await?;
+
+        let post_data = post_vecs
+            .first()
+            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?;
+
+        // Check if the user_id matches
+        if post_data.user_id.as_ref().is_some_and(|uid| uid != user_id) {
+            return Err(errors::Error::UserNotVerified(format!(
+                "user {} not authorized to restore",
+                id
+            )));
+        }
+
+        // Only a post deleted within the grace period can be brought back
+        match &post_data.deleted_at {
+            Some(deleted_at) if is_restorable(deleted_at, Utc::now()) => {
+                let restored = self.post_repository.restore_data(id).await?;
+                let created_at = post_data
+                    .created_at
+                    .clone()
+                    .unwrap_or_else(|| deleted_at.clone());
+                self.index_content(
+                    id,
+                    post_data.user_id.as_ref(),
+                    post_data.content.as_deref().unwrap_or_default(),
+                    created_at,
+                )
+                .await;
+                self.cache.invalidate_feed().await;
+                Ok(restored)
+            }
+            Some(_) => Err(errors::Error::DataNotAvaliable(format!(
+                "{} is past its restore period",
+                id
+            ))),
+            None => Err(errors::Error::DataNotAvaliable(format!(
+                "{} is not deleted",
+                id
+            ))),
+        }
     }
 
     #[tracing::instrument(err, skip_all)]
     pub async fn get_list(
         &self,
-        user_id: String,
-        page: i64,
-    ) -> Result<Vec<PayloadPostResponseDeserilize>> {
-        let (is_empty, post_vecs) = self.post_repository.get_list(page, user_id).await?;
-        if is_empty {
-            return Ok(Vec::new());
-        }
+        user_id: &UserId,
+        cursor: Option<String>,
+        limit: u64,
+    ) -> Result<Paginated<PayloadPostResponseDeserilize>> {
+        let cursor = cursor.as_deref().map(Cursor::decode).transpose()?;
+        let posts = self
+            .post_repository
+            .get_list(cursor, limit, user_id)
+            .await?;
+        let post_ids: Vec<PostId> = posts
+            .data
+            .iter()
+            .filter_map(|post| post.id.clone())
+            .collect();
+        let mut reactions = self
+            .reaction_repository
+            .summaries(&post_ids, Some(user_id))
+            .await?;
 
-        let data = stream::iter(post_vecs)
+        let data = stream::iter(posts.data)
             .map(|payload| {
+                let reactions = payload
+                    .id
+                    .as_ref()
+                    .and_then(|id| reactions.remove(&id.to_string()))
+                    .unwrap_or_default();
                 let post_gallery_deserialized: Vec<PostGalleryForFeedDeserialize> = payload
                     .clone()
                     .post_gallery
@@ -288,10 +396,92 @@ impl PostServices {
                     is_trainer: payload.is_trainer,
                     post_gallery: Some(post_gallery_deserialized),
                     updated_at: payload.updated_at.clone(),
+                    version: payload.version,
+                    reaction_counts: reactions.counts,
+                    reacted_by_me: reactions.mine,
                 }
             })
             .collect::<Vec<_>>()
             .await;
-        Ok(data)
+        Ok(Paginated {
+            data,
+            next_cursor: posts.next_cursor,
+        })
+    }
+
+    /* Index the content of a post whose write already committed, a failure is logged instead of failing it */
+    async fn index_content(
+        &self,
+        id: &PostId,
+        author: Option<&UserId>,
+        content: &str,
+        created_at: surrealdb::sql::Datetime,
+    ) {
+        if let Err(error) = self.index(id, author, content, created_at).await {
+            tracing::warn!("Post {} was not indexed: {}", id, error);
+        }
+    }
+
+    /* Index the hashtags of the content and record its mentions, notifying the users newly mentioned */
+    async fn index(
+        &self,
+        id: &PostId,
+        author: Option<&UserId>,
+        content: &str,
+        created_at: surrealdb::sql::Datetime,
+    ) -> Result<()> {
+        self.hashtag_repository
+            .replace(id, content::hashtags(content), created_at.clone())
+            .await?;
+
+        let Some(author) = author else {
+            return Ok(());
+        };
+        let mentioned = self
+            .mention_repository
+            .record(id, author, content::mentions(content), created_at)
+            .await?;
+
+        let notifications = mentioned
+            .iter()
+            .filter_map(|user_id| {
+                notification::notification(user_id, author, NotificationKind::Mention)
+            })
+            .map(|notification| Notification {
+                post_id: Some(id.clone()),
+                ..notification
+            })
+            .collect();
+        notification::send(&self.notification_repository, Ok(notifications)).await;
+
+        Ok(())
+    }
+}
+
+/// Days during which a deleted post can still be restored
+pub const RESTORE_GRACE_PERIOD_DAYS: i64 = 30;
+
+/* Whether a post deleted at `deleted_at` is still within its restore period at `now` */
+fn is_restorable(deleted_at: &surrealdb::sql::Datetime, now: DateTime<Utc>) -> bool {
+    now.signed_duration_since(deleted_at.0) <= Duration::days(RESTORE_GRACE_PERIOD_DAYS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn posts_are_restorable_within_the_grace_period() {
+        let deleted_at = surrealdb::sql::Datetime::from(Utc::now());
+
+        assert!(is_restorable(&deleted_at, deleted_at.0));
+        assert!(is_restorable(
+            &deleted_at,
+            deleted_at.0 + Duration::days(RESTORE_GRACE_PERIOD_DAYS)
+        ));
+        assert!(!is_restorable(
+            &deleted_at,
+            deleted_at.0 + Duration::days(RESTORE_GRACE_PERIOD_DAYS) + Duration::seconds(1)
+        ));
     }
 }
@@ -0,0 +1,40 @@
+use chrono::Utc;
+
+use database::{
+    id::{PostId, UserId},
+    model::ReactionKind,
+};
+use errors::Result;
+use repository::{post::PostRepository, reaction::ReactionRepository};
+
+#[derive(Clone)]
+pub struct ReactionServices {
+    pub reaction_repository: ReactionRepository,
+    pub post_repository: PostRepository,
+}
+
+impl ReactionServices {
+    /* React to a visible post, replacing the user's earlier reaction, false when nothing changed */
+    #[tracing::instrument(err, skip_all)]
+    pub async fn react(&self, user_id: &UserId, id: &PostId, kind: ReactionKind) -> Result<bool> {
+        let (is_empty, _) = self.post_repository.is_post_empty_by_id(id).await?;
+        if is_empty {
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
+        }
+
+        let created_at = surrealdb::sql::Datetime::from(Utc::now());
+        self.reaction_repository
+            .react(id, user_id, kind, created_at)
+            .await
+    }
+
+    #[tracing::instrument(err, skip_all)]
+    pub async fn unreact(&self, user_id: &UserId, id: &PostId) -> Result<()> {
+        let deleted = self.reaction_repository.unreact(id, user_id).await?;
+        if !deleted {
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
+        }
+
+        Ok(())
+    }
+}
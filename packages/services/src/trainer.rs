@@ -1,20 +1,35 @@
 use chrono::Utc;
 
-use repository::{gym::GymRepository, trainer::TrainerRepository, user::UserRepository};
-
-use database::model::{Gym, Id, PayloadTrainerRequest, PayloadTrainerResponse, Trainer, User};
+use crate::cache::Cache;
+
+use repository::{
+    follow::FollowRepository, gym::GymRepository, trainer::TrainerRepository,
+    transaction::TransactionRepository, user::UserRepository,
+};
+
+use database::{
+    id::{GymId, ProfileId, TrainerId, UserId},
+    model::{Gym, PayloadTrainerRequest, PayloadTrainerResponse, Trainer, User},
+    transaction::Transaction,
+};
 use errors::Result;
 
 #[derive(Clone)]
 pub struct TrainerServices {
     pub trainer_repository: TrainerRepository,
+    pub follow_repository: FollowRepository,
     pub user_repository: UserRepository,
+    pub transaction_repository: TransactionRepository,
     pub gym_repository: GymRepository,
+    pub cache: Cache,
 }
 
 impl TrainerServices {
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_trainer_data_empty_by_id(&self, id: &str) -> Result<(bool, Vec<Trainer>)> {
+    pub async fn is_trainer_data_empty_by_id(
+        &self,
+        id: &TrainerId,
+    ) -> Result<(bool, Vec<Trainer>)> {
         let data_exists = self
             .trainer_repository
             .is_trainer_data_empty_by_id(id)
@@ -23,7 +38,7 @@ impl TrainerServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn is_trainer_user_empty(&self, user_id: &str) -> Result<(bool, Vec<Trainer>)> {
+    pub async fn is_trainer_user_empty(&self, user_id: &UserId) -> Result<(bool, Vec<Trainer>)> {
         let data_exists = self
             .trainer_repository
             .is_trainer_data_empty(user_id)
@@ -32,67 +47,29 @@ impl TrainerServices {
     }
 
     #[tracing::instrument(err, skip_all)]
-    async fn is_user_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self
-            .trainer_repository
-            .is_trainer_data_empty_by_email(&data.email)
-            .await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    async fn is_username_empty(&self, data: &User) -> Result<(bool, Vec<User>)> {
-        let data_exists = self.user_repository.is_data_empty_by_username(data).await?;
-        Ok(data_exists)
-    }
-
-    #[tracing::instrument(err, skip_all)]
-    async fn is_gym_id_empty(&self, gym_id: &str) -> Result<(bool, Vec<Gym>)> {
+    async fn is_gym_id_empty(&self, gym_id: &GymId) -> Result<(bool, Vec<Gym>)> {
         let data_exists = self.gym_repository.is_gym_data_empty_by_id(gym_id).await?;
         Ok(data_exists)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn register_profile(&self, mut data: User, gym_id: String) -> Result<Option<Id>> {
+    pub async fn register_profile(&self, mut data: User, gym_id: &GymId) -> Result<TrainerId> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
-        let (is_user_empty, _) = self.is_user_empty(&data).await?;
-        if !is_user_empty {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
-
-        let (is_username_empty, _) = self.is_username_empty(&data).await?;
-        if !is_username_empty {
-            return Err(errors::Error::DataExist(format!(
-                "username:{}",
-                data.username
-            )));
-        }
-
-        let is_gym_empty = self.is_gym_id_empty(&gym_id).await?;
+        let is_gym_empty = self.is_gym_id_empty(gym_id).await?;
         if is_gym_empty.0 {
-            return Err(errors::Error::DataNotAvaliable(gym_id));
+            return Err(errors::Error::DataNotAvaliable(gym_id.to_string()));
         }
 
-        let gym_id_in_db = is_gym_empty.1.first().unwrap();
-
         data.created_at = Some(time_now.clone());
-        data.updated_at = Some(time_now);
+        data.updated_at = Some(time_now.clone());
 
-        let insert_into_user_tb: Option<Id> =
-            self.user_repository.insert_data(data.clone()).await?;
-        let user_id = insert_into_user_tb.unwrap();
+        // User and trainer are written together so a failure never leaves an orphan user
+        let mut transaction = Transaction::new();
 
-        let (not_exists, _) = self
-            .is_trainer_user_empty(user_id.id.to_string().as_ref())
-            .await?;
-        if !not_exists {
-            return Err(errors::Error::DataExist(format!("email:{}", data.email)));
-        }
+        let user_id = self.user_repository.stage_insert(&mut transaction, data)?;
 
-        let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
-
-        let gym_data = Trainer {
+        let trainer_data = Trainer {
             id: None,
             created_at: Some(time_now.clone()),
             updated_at: Some(time_now.clone()),
@@ -100,87 +77,98 @@ impl TrainerServices {
             sex: Some(String::from("")),
             experience: Some(0),
             expertise: Some(String::from("")),
-            gym_id: gym_id_in_db.clone().id.unwrap(),
-            user_id: user_id.id,
+            gym_id: gym_id.clone(),
+            user_id,
+            version: 0,
         };
 
-        let insert_into_gym_tb: Option<Id> = self.trainer_repository.insert_data(gym_data).await?;
-        Ok(insert_into_gym_tb)
+        let trainer_id = self
+            .trainer_repository
+            .stage_insert(&mut transaction, trainer_data)?;
+
+        // A taken email or username fails here with DataExist from the unique indexes
+        self.transaction_repository.commit(transaction).await?;
+        Ok(trainer_id)
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn profile_details(&self, id: String) -> Result<PayloadTrainerResponse> {
-        let (is_empty, temp_data) = match id.split(':').next() {
-            Some("trainer") => self.is_trainer_data_empty_by_id(&id).await?,
-            _ => self.is_trainer_user_empty(&id).await?,
-        };
-
-        if is_empty {
-            return Err(errors::Error::DataNotAvaliable((id).to_string()));
-        }
-
-        let data_array: Vec<PayloadTrainerResponse> = temp_data
-            .into_iter()
-            .map(|trainer| PayloadTrainerResponse {
-                id: trainer.id.clone().unwrap().to_string(),
-                name: trainer.name,
-                sex: trainer.sex,
-                experience: trainer.experience,
-                expertise: trainer.expertise,
-                created_at: trainer.created_at,
-                updated_at: trainer.updated_at,
+    pub async fn profile_details(&self, id: &TrainerId) -> Result<PayloadTrainerResponse> {
+        self.cache
+            .profile(id, || async {
+                let (_, temp_data) = self.is_trainer_data_empty_by_id(id).await?;
+                let profile = first_profile(temp_data, id.to_string())?;
+                self.counted(profile).await
             })
-            .collect();
-
-        let data = data_array
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.clone()))?
-            .clone();
-        Ok(data)
+            .await
     }
 
     #[tracing::instrument(err, skip_all)]
-    pub async fn update_profile(&self, payload: &PayloadTrainerRequest, id: &str) -> Result<()> {
-        let (not_exists, existing_data) = self
-            .trainer_repository
-            .is_trainer_data_empty_by_id(id)
-            .await?;
-        if not_exists {
-            return Err(errors::Error::DataNotAvaliable(id.to_string()));
-        }
+    pub async fn profile_details_by_user(
+        &self,
+        user_id: &UserId,
+    ) -> Result<PayloadTrainerResponse> {
+        let (_, temp_data) = self.is_trainer_user_empty(user_id).await?;
+        let profile = first_profile(temp_data, user_id.to_string())?;
+        self.counted(profile).await
+    }
 
-        let existing_record = existing_data
-            .first()
-            .ok_or_else(|| errors::Error::DataNotAvaliable(id.to_string()))?
-            .clone();
+    #[tracing::instrument(err, skip_all)]
+    pub async fn update_profile(
+        &self,
+        payload: &PayloadTrainerRequest,
+        id: &TrainerId,
+    ) -> Result<()> {
         let time_now: surrealdb::sql::Datetime = surrealdb::sql::Datetime::from(Utc::now());
 
+        // Only fields sent by the client are written, the gym and owning user are left untouched
         let data = PayloadTrainerRequest {
-            name: payload
-                .name
-                .clone()
-                .or_else(|| existing_record.name.clone()),
-            sex: payload.sex.clone().or_else(|| existing_record.sex.clone()),
-            experience: payload.experience.or(existing_record.experience),
-            expertise: payload
-                .expertise
-                .clone()
-                .or_else(|| existing_record.expertise.clone()),
-            gym_id: Some(existing_record.gym_id.clone()),
-            user_id: Some(existing_record.user_id.clone()),
-            created_at: existing_record.created_at.clone(),
+            name: payload.name.clone(),
+            sex: payload.sex.clone(),
+            experience: payload.experience,
+            expertise: payload.expertise.clone(),
+            gym_id: None,
+            user_id: None,
+            created_at: None,
             updated_at: Some(time_now),
+            version: payload.version,
         };
 
-        let trainer_id = existing_record.clone().id.unwrap().to_string();
-        let update_data = self
-            .trainer_repository
-            .update_data(trainer_id, data)
-            .await?;
+        let update_data = self.trainer_repository.update_data(id, data).await?;
         if !update_data {
-            return Err(errors::Error::DatabaseError(id.to_string()));
+            return Err(errors::Error::DataNotAvaliable(id.to_string()));
         }
 
+        self.cache.invalidate_profile(id).await;
+
         Ok(())
     }
+
+    /* Fill in the follow counts, cached with the profile and dropped on follow and unfollow */
+    async fn counted(&self, mut profile: PayloadTrainerResponse) -> Result<PayloadTrainerResponse> {
+        let id = ProfileId::Trainer(profile.id.parse()?);
+        (profile.followers_count, profile.following_count) =
+            self.follow_repository.counts(&id).await?;
+        Ok(profile)
+    }
+}
+
+/* Response of the first trainer found, `id` names the lookup in the error when there is none */
+fn first_profile(temp_data: Vec<Trainer>, id: String) -> Result<PayloadTrainerResponse> {
+    let trainer = temp_data
+        .into_iter()
+        .next()
+        .ok_or(errors::Error::DataNotAvaliable(id))?;
+
+    Ok(PayloadTrainerResponse {
+        id: trainer.id.map(|id| id.to_string()).unwrap_or_default(),
+        name: trainer.name,
+        sex: trainer.sex,
+        experience: trainer.experience,
+        expertise: trainer.expertise,
+        created_at: trainer.created_at,
+        updated_at: trainer.updated_at,
+        followers_count: 0,
+        following_count: 0,
+        version: trainer.version,
+    })
 }
@@ -2,9 +2,11 @@ use environment::Environment;
 use file_storage::interface::FileStorage;
 use redis::Client;
 use services::{
-    auth::AuthServices, email::EmailServices, feed::FeedServices, gym::GymServices,
-    gymseeker::GymSeekerServices, location::LocationServices, post::PostServices,
-    trainer::TrainerServices,
+    auth::AuthServices, class::ClassServices, comment::CommentServices, email::EmailServices,
+    feed::FeedServices, follow::FollowServices, gym::GymServices, gymseeker::GymSeekerServices,
+    hashtag::HashtagServices, health::HealthServices, location::LocationServices,
+    membership::MembershipServices, message::MessageServices, notification::NotificationServices,
+    post::PostServices, reaction::ReactionServices, trainer::TrainerServices,
 };
 
 #[derive(Clone)]
@@ -16,7 +18,16 @@ pub struct AppState {
     pub trainer_services: TrainerServices,
     pub post_services: PostServices,
     pub feed_services: FeedServices,
+    pub follow_services: FollowServices,
+    pub reaction_services: ReactionServices,
+    pub comment_services: CommentServices,
+    pub hashtag_services: HashtagServices,
+    pub notification_services: NotificationServices,
+    pub message_services: MessageServices,
+    pub membership_services: MembershipServices,
+    pub class_services: ClassServices,
     pub email_services: EmailServices,
+    pub health_services: HealthServices,
     pub redis_client: Client,
     pub environment: Environment,
     pub cloud_storage: FileStorage,
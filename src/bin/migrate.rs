@@ -0,0 +1,26 @@
+use database::{
+    db::{DatabaseSource, DatabaseType, Sources},
+    migrations::Migrator,
+};
+use environment::Environment;
+use errors::Result;
+
+/* Apply pending database migrations without starting the server */
+#[tokio::main]
+async fn main() -> Result<()> {
+    let environment = Environment::new();
+
+    let mut source = DatabaseSource {
+        db_type: DatabaseType::from_environment(&environment)?,
+    };
+    let conn = source.connect().await?;
+
+    let versions = conn.migrate().await?;
+    if versions.is_empty() {
+        println!("✅ Database schema is up to date!");
+    } else {
+        println!("✅ Applied database migration(s): {:?}", versions);
+    }
+
+    Ok(())
+}